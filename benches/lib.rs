@@ -11,7 +11,10 @@ use rand::distributions::{Distribution, Normal, Uniform};
 use std::f64;
 use std::marker::PhantomData;
 
-use freude::{Ode, RungeKutta4, Stepper};
+use freude::{
+    Differencing, DormandPrince5, FiniteDifference, IController, Jacobian, Ode, PiController,
+    PidController, RungeKutta4, StepSizeController, Stepper,
+};
 
 use test::black_box;
 
@@ -83,7 +86,7 @@ impl Ode for ChaoticNeuralNet {
     fn differentiate_into(&mut self, state: &Array1<f64>, derivative: &mut Array1<f64>) {
         // dx_i/dt = x_i - ∑_j J_ij tanh(g·x_j)
         let g = self.nonlinearity;
-        self.temp_tanh.zip_mut_with(&state, |t, x| {
+        self.temp_tanh.zip_mut_with(state, |t, x| {
             *t = f64::tanh(g * *x);
         });
 
@@ -109,9 +112,44 @@ impl Ode for ChaoticNeuralNet {
     }
 }
 
+impl Jacobian for ChaoticNeuralNet {
+    fn jacobian_into(&mut self, _t: f64, state: &Array1<f64>, jacobian: &mut Array2<f64>) {
+        // ∂f_i/∂x_j = J_ij · g · (1 - tanh²(g·x_j)) - δ_ij
+        let g = self.nonlinearity;
+        jacobian.assign(&self.coupling);
+        for (mut column, &x) in jacobian.gencolumns_mut().into_iter().zip(state) {
+            let t = f64::tanh(g * x);
+            column *= g * (1.0 - t * t);
+        }
+        jacobian.diag_mut().map_inplace(|d| *d -= 1.0);
+    }
+}
+
+fn chaotic_net(size: usize) -> ChaoticNeuralNet {
+    let mean = 0.0;
+    let nonlinearity = 1.5;
+    let std_dev = 1.1;
+
+    let dist = Normal::new(mean, std_dev / f64::sqrt(size as f64));
+    let mut coupling = Array2::random((size, size), dist);
+    coupling.diag_mut().map_inplace(|c| {
+        *c = 0.0;
+    });
+
+    let temp_tanh = Array1::zeros(size);
+
+    ChaoticNeuralNet {
+        coupling,
+        nonlinearity,
+        size,
+        temp_tanh,
+    }
+}
+
 #[bench]
 fn rk4_freude(bench: &mut test::Bencher) {
     // Some generic ODE with f'(x) = a * sin x
+    #[allow(dead_code)]
     #[derive(Clone)]
     struct SimpleODE {
         a: f64,
@@ -147,7 +185,7 @@ fn rk4_manual(bench: &mut test::Bencher) {
         let k3 = f(*x + dt / 2.0 * k2);
         let k4 = f(*x + dt * k3);
 
-        *x = *x + dt / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4);
+        *x += dt / 6.0 * (k1 + 2.0 * k2 + 2.0 * k3 + k4);
     }
 
     let mut x = black_box(1.0);
@@ -173,10 +211,10 @@ fn chaotic_net_derivative(bench: &mut test::Bencher) {
     let temp_tanh = Array1::zeros(size);
 
     let mut chaotic_net = ChaoticNeuralNet {
-        coupling: coupling,
-        nonlinearity: nonlinearity,
-        size: size,
-        temp_tanh: temp_tanh,
+        coupling,
+        nonlinearity,
+        size,
+        temp_tanh,
     };
 
     let between = Uniform::new_inclusive(-1.0, 1.0);
@@ -203,10 +241,10 @@ fn chaotic_net_rk4(bench: &mut test::Bencher) {
     let temp_tanh = Array1::zeros(size);
 
     let mut chaotic_net = ChaoticNeuralNet {
-        coupling: coupling,
-        nonlinearity: nonlinearity,
-        size: size,
-        temp_tanh: temp_tanh,
+        coupling,
+        nonlinearity,
+        size,
+        temp_tanh,
     };
 
     let between = Uniform::new_inclusive(-1.0, 1.0);
@@ -220,10 +258,7 @@ fn chaotic_net_rk4(bench: &mut test::Bencher) {
     });
 }
 
-#[bench]
-fn kuramoto_vec(bench: &mut test::Bencher) {
-    let size = 8192;
-
+fn kuramoto(size: usize) -> (Kuramoto, Vec<f64>) {
     let mut rng = ::rand::thread_rng();
 
     let frequencies: Vec<_> = Normal::new(0.0, 1.0)
@@ -234,20 +269,29 @@ fn kuramoto_vec(bench: &mut test::Bencher) {
     let temp_sin = vec![0.0; size];
     let temp_cos = vec![0.0; size];
 
-    let mut kuramoto = Kuramoto {
-        frequencies: frequencies,
-        size: size,
-        temp_sin: temp_sin,
-        temp_cos: temp_cos,
+    let kuramoto = Kuramoto {
+        frequencies,
+        size,
+        temp_sin,
+        temp_cos,
     };
 
     use std::f64::consts::PI;
 
-    let mut state: Vec<_> = Uniform::new_inclusive(-PI, PI)
+    let state: Vec<_> = Uniform::new_inclusive(-PI, PI)
         .sample_iter(&mut rng)
         .take(size)
         .collect();
 
+    (kuramoto, state)
+}
+
+#[bench]
+fn kuramoto_vec(bench: &mut test::Bencher) {
+    let size = 8192;
+
+    let (mut kuramoto, mut state) = kuramoto(size);
+
     let rk4 = RungeKutta4::new(&state, 0.1);
     let mut rk4 = black_box(rk4);
     bench.iter(|| {
@@ -255,6 +299,39 @@ fn kuramoto_vec(bench: &mut test::Bencher) {
     });
 }
 
+fn kuramoto_dopri<C>(bench: &mut test::Bencher, controller: C)
+where
+    C: StepSizeController + Clone,
+{
+    let size = 1024;
+
+    let (mut kuramoto, initial) = kuramoto(size);
+
+    bench.iter(|| {
+        let mut state = initial.clone();
+        let mut dopri = DormandPrince5::new(&state, 0.1)
+            .with_tolerances(1e-8, 1e-8)
+            .with_controller(controller.clone());
+        dopri.integrate_time(&mut kuramoto, &mut state, 10.0);
+        black_box(state)
+    });
+}
+
+#[bench]
+fn kuramoto_dopri_i_controller(bench: &mut test::Bencher) {
+    kuramoto_dopri(bench, IController::new());
+}
+
+#[bench]
+fn kuramoto_dopri_pi_controller(bench: &mut test::Bencher) {
+    kuramoto_dopri(bench, PiController::new());
+}
+
+#[bench]
+fn kuramoto_dopri_pid_controller(bench: &mut test::Bencher) {
+    kuramoto_dopri(bench, PidController::new());
+}
+
 #[bench]
 fn rk4_array_speed(bench: &mut test::Bencher) {
     let size = 8192;
@@ -286,3 +363,34 @@ fn rk4_vec_speed(bench: &mut test::Bencher) {
         rk4.do_step(&mut system, &mut state);
     });
 }
+
+#[bench]
+fn chaotic_net_jacobian_analytic(bench: &mut test::Bencher) {
+    let size = 512;
+    let mut chaotic_net = chaotic_net(size);
+
+    let state = Array1::random(size, Uniform::new_inclusive(-1.0, 1.0));
+    let mut jacobian = black_box(Array2::zeros((size, size)));
+    bench.iter(|| chaotic_net.jacobian_into(0.0, &state, &mut jacobian));
+}
+
+#[bench]
+fn chaotic_net_jacobian_forward_difference(bench: &mut test::Bencher) {
+    let size = 512;
+    let mut chaotic_net = FiniteDifference::new(chaotic_net(size));
+
+    let state = Array1::random(size, Uniform::new_inclusive(-1.0, 1.0));
+    let mut jacobian = black_box(Array2::zeros((size, size)));
+    bench.iter(|| chaotic_net.jacobian_into(0.0, &state, &mut jacobian));
+}
+
+#[bench]
+fn chaotic_net_jacobian_central_difference(bench: &mut test::Bencher) {
+    let size = 512;
+    let mut chaotic_net =
+        FiniteDifference::new(chaotic_net(size)).with_differencing(Differencing::Central);
+
+    let state = Array1::random(size, Uniform::new_inclusive(-1.0, 1.0));
+    let mut jacobian = black_box(Array2::zeros((size, size)));
+    bench.iter(|| chaotic_net.jacobian_into(0.0, &state, &mut jacobian));
+}
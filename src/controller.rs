@@ -0,0 +1,245 @@
+/// The outcome of an attempted step of an adaptive stepper.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepSize {
+    /// The attempt is accepted; the next step is attempted with the given step size.
+    Accepted(f64),
+    /// The attempt is rejected and retried with the given step size.
+    Rejected(f64),
+}
+
+/// A strategy choosing the step sizes of an adaptive stepper from its error estimates.
+///
+/// `adapt` is called after every attempted step with its step size `dt` and its `error`, measured
+/// relative to the tolerances such that attempts with `error <= 1` are acceptable. The local
+/// error is assumed to behave like `dt^k`, with `k` one more than the order of the lower order
+/// method of an embedded pair.
+pub trait StepSizeController {
+    fn adapt(&mut self, dt: f64, error: f64, k: u32) -> StepSize;
+}
+
+/// The limits shared by the step size controllers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Limits {
+    pub(crate) safety: f64,
+    pub(crate) fac_min: f64,
+    pub(crate) fac_max: f64,
+    pub(crate) dt_min: f64,
+    pub(crate) dt_max: f64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            safety: 0.9,
+            fac_min: 0.2,
+            fac_max: 5.0,
+            dt_min: 0.0,
+            dt_max: f64::INFINITY,
+        }
+    }
+}
+
+impl Limits {
+    /// Scale `dt` by `factor`, after multiplying it with the safety factor and limiting it.
+    /// Rejected steps never grow, and a NaN `factor` results in the strongest reduction.
+    fn scale(&self, dt: f64, factor: f64, accepted: bool) -> f64 {
+        let fac_max = if accepted { self.fac_max } else { 1.0 };
+        let factor = self.safety * factor;
+        let factor = if factor.is_nan() {
+            self.fac_min
+        } else {
+            factor.max(self.fac_min).min(fac_max)
+        };
+
+        (dt.abs() * factor)
+            .max(self.dt_min)
+            .min(self.dt_max)
+            .copysign(dt)
+    }
+}
+
+macro_rules! impl_limits_builders {
+    ($controller:ident) => {
+        impl $controller {
+            /// Set the safety factor the proposed step sizes are multiplied with.
+            pub fn with_safety(mut self, safety: f64) -> Self {
+                self.limits.safety = safety;
+                self
+            }
+
+            /// Set the bounds of the factor a step size is changed by from one attempt to the
+            /// next. Rejected steps never grow.
+            pub fn with_factor_bounds(mut self, min: f64, max: f64) -> Self {
+                self.limits.fac_min = min;
+                self.limits.fac_max = max;
+                self
+            }
+
+            /// Set the bounds of the magnitude of the step size.
+            pub fn with_timestep_bounds(mut self, min: f64, max: f64) -> Self {
+                self.limits.dt_min = min;
+                self.limits.dt_max = max;
+                self
+            }
+        }
+
+        impl Default for $controller {
+            fn default() -> Self {
+                $controller::new()
+            }
+        }
+    };
+}
+
+/// The elementary integral controller, `dt_next = safety * dt * error^(-1/k)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IController {
+    pub(crate) limits: Limits,
+}
+
+impl IController {
+    /// Create a controller with a safety factor of `0.9`, changing the step size by a factor
+    /// between `0.2` and `5`.
+    pub fn new() -> Self {
+        IController {
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl_limits_builders!(IController);
+
+impl StepSizeController for IController {
+    fn adapt(&mut self, dt: f64, error: f64, k: u32) -> StepSize {
+        let factor = error.powf(-1.0 / f64::from(k));
+
+        if error <= 1.0 {
+            StepSize::Accepted(self.limits.scale(dt, factor, true))
+        } else {
+            StepSize::Rejected(self.limits.scale(dt, factor, false))
+        }
+    }
+}
+
+/// Gustafsson's proportional-integral controller,
+/// `dt_next = safety * dt * error^(-α/k) * previous_error^(β/k)`.
+///
+/// Taking the error of the previously accepted step into account damps oscillating step sizes.
+/// After a rejection, the step size is reduced as by the integral controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PiController {
+    pub(crate) limits: Limits,
+    pub(crate) alpha: f64,
+    pub(crate) beta: f64,
+    pub(crate) previous_error: f64,
+}
+
+impl PiController {
+    /// Create a controller with the gains `α = 0.7` and `β = 0.4`, a safety factor of `0.9`, and
+    /// changing the step size by a factor between `0.2` and `5`.
+    pub fn new() -> Self {
+        PiController {
+            limits: Limits::default(),
+            alpha: 0.7,
+            beta: 0.4,
+            previous_error: 1.0,
+        }
+    }
+
+    pub fn with_gains(mut self, alpha: f64, beta: f64) -> Self {
+        self.alpha = alpha;
+        self.beta = beta;
+        self
+    }
+}
+
+impl_limits_builders!(PiController);
+
+impl StepSizeController for PiController {
+    fn adapt(&mut self, dt: f64, error: f64, k: u32) -> StepSize {
+        let k = f64::from(k);
+
+        if error <= 1.0 {
+            let factor = error.powf(-self.alpha / k) * self.previous_error.powf(self.beta / k);
+            // Guard against a vanishing error, which would stall the controller.
+            self.previous_error = error.max(1e-4);
+            StepSize::Accepted(self.limits.scale(dt, factor, true))
+        } else {
+            let factor = error.powf(-1.0 / k);
+            StepSize::Rejected(self.limits.scale(dt, factor, false))
+        }
+    }
+}
+
+/// A PID controller after Söderlind's digital filters,
+/// `dt_next = safety * dt * e_n^(-β1/k) * e_{n-1}^(-β2/k) * e_{n-2}^(-β3/k) * (dt / dt_prev)^(-α2)`,
+/// where `e_n` is the error of the current and `e_{n-1}`, `e_{n-2}` are the errors of the
+/// previously accepted steps.
+///
+/// The default gains are those of the H211b filter with `b = 4`. After a rejection, the step size
+/// is reduced as by the integral controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PidController {
+    pub(crate) limits: Limits,
+    pub(crate) beta1: f64,
+    pub(crate) beta2: f64,
+    pub(crate) beta3: f64,
+    pub(crate) alpha2: f64,
+    pub(crate) previous_errors: [f64; 2],
+    pub(crate) previous_dt: Option<f64>,
+}
+
+impl PidController {
+    /// Create an H211b controller with `b = 4`, a safety factor of `0.9`, and changing the step
+    /// size by a factor between `0.2` and `5`.
+    pub fn new() -> Self {
+        PidController::h211b(4.0)
+    }
+
+    /// The H211b filter, `β1 = β2 = α2 = 1/b`.
+    pub fn h211b(b: f64) -> Self {
+        PidController {
+            limits: Limits::default(),
+            beta1: 1.0 / b,
+            beta2: 1.0 / b,
+            beta3: 0.0,
+            alpha2: 1.0 / b,
+            previous_errors: [1.0; 2],
+            previous_dt: None,
+        }
+    }
+
+    pub fn with_gains(mut self, beta1: f64, beta2: f64, beta3: f64, alpha2: f64) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self.beta3 = beta3;
+        self.alpha2 = alpha2;
+        self
+    }
+}
+
+impl_limits_builders!(PidController);
+
+impl StepSizeController for PidController {
+    fn adapt(&mut self, dt: f64, error: f64, k: u32) -> StepSize {
+        let k = f64::from(k);
+
+        if error <= 1.0 {
+            let [e1, e2] = self.previous_errors;
+            let ratio = self.previous_dt.map_or(1.0, |previous| dt / previous);
+
+            let factor = error.powf(-self.beta1 / k)
+                * e1.powf(-self.beta2 / k)
+                * e2.powf(-self.beta3 / k)
+                * ratio.powf(-self.alpha2);
+
+            // Guard against a vanishing error, which would stall the controller.
+            self.previous_errors = [error.max(1e-4), e1];
+            self.previous_dt = Some(dt);
+            StepSize::Accepted(self.limits.scale(dt, factor, true))
+        } else {
+            let factor = error.powf(-1.0 / k);
+            StepSize::Rejected(self.limits.scale(dt, factor, false))
+        }
+    }
+}
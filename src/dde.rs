@@ -0,0 +1,456 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
+use crate::stepper::{DenseOutput, ROUNDOFF};
+use crate::tolerance::Componentwise;
+
+/// A system of delay differential equations, dx/dt = f(t, x(t), x(s) for s < t).
+///
+/// The right-hand side reads the past states from the `History`, e.g. x(t - τ) for a delay τ
+/// that may depend on the time and the state.
+pub trait Dde {
+    type State: Clone;
+
+    /// The constant delays of the system, whose discontinuities `DdeIntegrator` steps onto.
+    ///
+    /// A history that doesn't match the solution at the initial time makes the derivative
+    /// discontinuous there, and the delays propagate the discontinuity to t0 + τ, t0 + 2τ, and
+    /// so on, where it smooths out by one order each time. None by default.
+    fn delays(&self) -> Vec<f64> {
+        Vec::new()
+    }
+
+    fn differentiate_into(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        history: &History<Self::State>,
+        derivative: &mut Self::State,
+    );
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        state.clone_from(value);
+    }
+}
+
+/// The function giving the state before the initial time.
+type InitialHistory<S> = Box<dyn Fn(f64, &mut S)>;
+
+/// The past of the solution of a `Dde`: the initial history up to the initial time, and the
+/// accepted steps after it.
+///
+/// Within each step, the history interpolates the states and derivatives at its ends and the
+/// dense output of the stepper at its midpoint by a polynomial of degree 4. Beyond the last
+/// step, which happens for delays shorter than the step size, it extrapolates the last step.
+pub struct History<S> {
+    t0: f64,
+    initial: InitialHistory<S>,
+
+    times: Vec<f64>,
+    states: Vec<S>,
+    derivatives: Vec<S>,
+    midpoints: Vec<S>,
+
+    shape: Option<S>,
+}
+
+impl<S> History<S>
+where
+    S: Componentwise + Clone,
+{
+    /// Create a history starting from the function `initial`, which writes the state at times
+    /// `t <= t0` into its second argument.
+    fn new<F>(t0: f64, initial: F) -> Self
+    where
+        F: Fn(f64, &mut S) + 'static,
+    {
+        History {
+            t0,
+            initial: Box::new(initial),
+
+            times: Vec::new(),
+            states: Vec::new(),
+            derivatives: Vec::new(),
+            midpoints: Vec::new(),
+
+            shape: None,
+        }
+    }
+
+    /// The initial time.
+    pub fn initial_time(&self) -> f64 {
+        self.t0
+    }
+
+    /// The times of the steps since the initial time, including it.
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// The states at `times`.
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// Write the state at time `t` into `out`.
+    pub fn state_at_into(&self, t: f64, out: &mut S) {
+        let n = self.times.len();
+        if t <= self.t0 || n == 0 {
+            (self.initial)(t, out);
+            return;
+        }
+        if n == 1 {
+            S::linear_combination(
+                out,
+                &[(1.0, &self.states[0]), (t - self.t0, &self.derivatives[0])],
+            );
+            return;
+        }
+
+        let step = match self
+            .times
+            .binary_search_by(|time| time.partial_cmp(&t).unwrap())
+        {
+            Ok(i) => {
+                S::linear_combination(out, &[(1.0, &self.states[i])]);
+                return;
+            }
+            Err(i) => i.min(n - 1) - 1,
+        };
+
+        let dt = self.times[step + 1] - self.times[step];
+        let theta = (t - self.times[step]) / dt;
+        let theta2 = theta * theta;
+        let theta3 = theta2 * theta;
+        let theta4 = theta2 * theta2;
+        S::linear_combination(
+            out,
+            &[
+                (
+                    -8.0 * theta4 + 18.0 * theta3 - 11.0 * theta2 + 1.0,
+                    &self.states[step],
+                ),
+                (
+                    dt * (-2.0 * theta4 + 5.0 * theta3 - 4.0 * theta2 + theta),
+                    &self.derivatives[step],
+                ),
+                (
+                    16.0 * theta4 - 32.0 * theta3 + 16.0 * theta2,
+                    &self.midpoints[step],
+                ),
+                (
+                    -8.0 * theta4 + 14.0 * theta3 - 5.0 * theta2,
+                    &self.states[step + 1],
+                ),
+                (
+                    dt * (2.0 * theta4 - 3.0 * theta3 + theta2),
+                    &self.derivatives[step + 1],
+                ),
+            ],
+        );
+    }
+
+    /// The state at time `t`.
+    ///
+    /// Panics before the integration has started.
+    pub fn state_at(&self, t: f64) -> S {
+        let mut out = self
+            .shape
+            .clone()
+            .expect("History: the integration has not started");
+        self.state_at_into(t, &mut out);
+        out
+    }
+
+    /// Record the state and its derivative at the end of an accepted step.
+    fn push(&mut self, t: f64, state: &S, derivative: &S) {
+        self.times.push(t);
+        self.states.push(state.clone());
+        self.derivatives.push(derivative.clone());
+    }
+}
+
+impl<S> fmt::Debug for History<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("t0", &self.t0)
+            .field("times", &self.times)
+            .field("states", &self.states)
+            .field("derivatives", &self.derivatives)
+            .field("midpoints", &self.midpoints)
+            .finish()
+    }
+}
+
+/// A `Dde` together with its history, seen as an ODE by the steppers.
+struct Delayed<'a, D: Dde> {
+    system: &'a mut D,
+    history: &'a History<D::State>,
+}
+
+impl<'a, D> NonautonomousOde for Delayed<'a, D>
+where
+    D: Dde,
+{
+    type State = D::State;
+
+    fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+        self.system
+            .differentiate_into(t, state, self.history, derivative);
+    }
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        self.system.update_state(state, value);
+    }
+}
+
+/// Integrates a `Dde` by the method of steps, with any stepper providing `DenseOutput`.
+///
+/// Every accepted step is recorded in the `History` with the dense output of the stepper at
+/// its midpoint, and the derivative at its end, which takes one additional evaluation of the
+/// right-hand side per step, counted by `stats` but not in the stats of the stepper. The steps
+/// end exactly on the discontinuities propagated by the constant delays of the system from the
+/// initial time, up to `with_discontinuity_order` delays later.
+///
+/// Only integration forward in time is supported.
+pub struct DdeIntegrator<St: DenseOutput> {
+    stepper: St,
+    history: History<St::State>,
+    derivative: Option<St::State>,
+    /// The evaluations of the right-hand side recording the derivatives in the history.
+    record_evaluations: usize,
+
+    discontinuity_order: usize,
+    discontinuities: Option<VecDeque<f64>>,
+}
+
+impl<St> DdeIntegrator<St>
+where
+    St: DenseOutput,
+    St::State: Componentwise + Clone,
+{
+    /// Create an integrator starting at the time of `stepper`, with the function `initial`
+    /// giving the state before it.
+    pub fn new<F>(stepper: St, initial: F) -> Self
+    where
+        F: Fn(f64, &mut St::State) + 'static,
+    {
+        let history = History::new(stepper.time(), initial);
+        DdeIntegrator {
+            stepper,
+            history,
+            derivative: None,
+            record_evaluations: 0,
+
+            discontinuity_order: 5,
+            discontinuities: None,
+        }
+    }
+
+    /// Set how many delays after the initial time the discontinuities are tracked, 5 by
+    /// default. The derivative of order k is continuous after k delays.
+    pub fn with_discontinuity_order(mut self, order: usize) -> Self {
+        self.discontinuity_order = order;
+        self
+    }
+
+    pub fn stepper(&self) -> &St {
+        &self.stepper
+    }
+
+    pub fn stepper_mut(&mut self) -> &mut St {
+        &mut self.stepper
+    }
+
+    pub fn history(&self) -> &History<St::State> {
+        &self.history
+    }
+
+    /// The stats of the stepper, including the evaluations of the right-hand side recording the
+    /// derivatives in the history.
+    pub fn stats(&self) -> Stats {
+        let mut stats = *self.stepper.stats();
+        stats.rhs_evaluations += self.record_evaluations;
+        stats
+    }
+
+    /// Reset the stats of the stepper and the count of the evaluations recording the history.
+    pub fn reset_stats(&mut self) {
+        self.stepper.reset_stats();
+        self.record_evaluations = 0;
+    }
+
+    /// Do a single step of the stepper, shortened to end on the next discontinuity.
+    pub fn do_step<D>(&mut self, system: &mut D, state: &mut St::State)
+    where
+        D: Dde<State = St::State>,
+    {
+        if self.derivative.is_none() {
+            self.start(system, state);
+        }
+
+        let t = self.stepper.time();
+        let dt = self.stepper.timestep();
+        assert!(
+            dt > 0.0,
+            "DdeIntegrator: only positive step sizes are supported"
+        );
+
+        let discontinuities = self.discontinuities.as_mut().unwrap();
+        // `Option::is_some_and` is too recent for the supported compilers.
+        #[allow(clippy::unnecessary_map_or)]
+        while discontinuities
+            .front()
+            .map_or(false, |&d| d <= t + ROUNDOFF * t.abs().max(1.0))
+        {
+            discontinuities.pop_front();
+        }
+        let next = discontinuities
+            .front()
+            .cloned()
+            .filter(|&d| t + dt * (1.0 + ROUNDOFF) >= d);
+        if let Some(d) = next {
+            self.stepper.set_timestep(d - t);
+        }
+
+        self.stepper.do_step(
+            &mut Delayed {
+                system,
+                history: &self.history,
+            },
+            state,
+        );
+        let mut midpoint = state.clone();
+        self.stepper.interpolate(0.5, &mut midpoint);
+        self.history.midpoints.push(midpoint);
+
+        // Snap onto the discontinuity, and keep the step size proposed before shortening the
+        // step.
+        if let Some(d) = next {
+            if (self.stepper.time() - d).abs() <= ROUNDOFF * d.abs().max(1.0) {
+                self.stepper.set_time(d);
+                self.stepper.set_timestep(dt);
+            }
+        }
+
+        self.record(system, state);
+    }
+
+    /// Do `n` steps, returning the time integrated over.
+    pub fn integrate_n_steps<D>(&mut self, system: &mut D, state: &mut St::State, n: usize) -> f64
+    where
+        D: Dde<State = St::State>,
+    {
+        let t0 = self.stepper.time();
+        for _ in 0..n {
+            self.do_step(system, state);
+        }
+        self.stepper.time() - t0
+    }
+
+    /// Integrate over exactly the duration `t`, returning the time integrated over and the
+    /// number of steps.
+    pub fn integrate_time<D>(
+        &mut self,
+        system: &mut D,
+        state: &mut St::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        D: Dde<State = St::State>,
+    {
+        let t0 = self.stepper.time();
+        let t_end = t0 + t;
+        let mut count = 0;
+
+        while self.stepper.time() < t_end - ROUNDOFF * t_end.abs().max(1.0) {
+            let time = self.stepper.time();
+            let proposed = self.stepper.timestep();
+
+            // Shorten the last step to end on t_end.
+            let truncated = time + proposed > t_end;
+            if truncated {
+                self.stepper.set_timestep(t_end - time);
+            }
+            self.do_step(system, state);
+            count += 1;
+
+            if truncated {
+                if (self.stepper.time() - t_end).abs() <= ROUNDOFF * t_end.abs().max(1.0) {
+                    self.stepper.set_time(t_end);
+                    self.stepper.set_timestep(proposed);
+                } else if self.stepper.timestep() == t_end - time {
+                    // Stopped short on a discontinuity.
+                    self.stepper.set_timestep(proposed);
+                }
+            }
+        }
+        (self.stepper.time() - t0, count)
+    }
+
+    /// Record the initial state and find the discontinuities ahead.
+    fn start<D>(&mut self, system: &mut D, state: &St::State)
+    where
+        D: Dde<State = St::State>,
+    {
+        let t0 = self.stepper.time();
+
+        let delays: Vec<f64> = system
+            .delays()
+            .into_iter()
+            .filter(|&delay| delay > 0.0)
+            .collect();
+        let mut discontinuities = vec![t0];
+        let mut level = vec![t0];
+        for _ in 0..self.discontinuity_order {
+            level = level
+                .iter()
+                .flat_map(|t| delays.iter().map(move |delay| t + delay))
+                .collect();
+            level.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            level.dedup_by(|a, b| (*a - *b).abs() <= ROUNDOFF * a.abs().max(1.0));
+            discontinuities.extend(&level);
+        }
+        discontinuities.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        discontinuities.dedup_by(|a, b| (*a - *b).abs() <= ROUNDOFF * a.abs().max(1.0));
+        discontinuities.retain(|&d| d > t0);
+        self.discontinuities = Some(discontinuities.into_iter().collect());
+
+        self.history.shape = Some(state.clone());
+        self.derivative = Some(state.clone());
+        self.record(system, state);
+    }
+
+    /// Record the state at the current time, with its derivative.
+    fn record<D>(&mut self, system: &mut D, state: &St::State)
+    where
+        D: Dde<State = St::State>,
+    {
+        let t = self.stepper.time();
+        let derivative = self.derivative.as_mut().unwrap();
+        system.differentiate_into(t, state, &self.history, derivative);
+        self.record_evaluations += 1;
+        self.history.push(t, state, derivative);
+    }
+}
+
+impl<St> fmt::Debug for DdeIntegrator<St>
+where
+    St: DenseOutput + fmt::Debug,
+    St::State: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DdeIntegrator")
+            .field("stepper", &self.stepper)
+            .field("history", &self.history)
+            .field("record_evaluations", &self.record_evaluations)
+            .field("discontinuity_order", &self.discontinuity_order)
+            .field("discontinuities", &self.discontinuities)
+            .finish()
+    }
+}
@@ -0,0 +1,164 @@
+use std::error;
+use std::fmt;
+
+use crate::ode::NonautonomousOde;
+use crate::stepper::{NewtonError, Stepper};
+use crate::tolerance::Componentwise;
+
+/// The failure of an integration, returned by the `try_*` methods of the steppers and by the
+/// implicit steppers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The state became NaN or infinite in the step starting at `time`.
+    NonFinite { time: f64 },
+    /// An adaptive stepper rejected a step at `time` and cannot reduce the step size any further
+    /// than `timestep`.
+    StepSizeUnderflow { time: f64, timestep: f64 },
+    /// The integration did not reach its end within `steps` steps.
+    MaxStepsExceeded { steps: usize },
+    /// The Newton iteration of an implicit stepper failed.
+    Newton(NewtonError),
+    /// The mass matrix at `time` is singular, but not of a semi-explicit DAE, whose algebraic
+    /// equations and variables are its zero rows and columns, e.g. `[[1, 1], [0, 0]]`.
+    SingularMassMatrix { time: f64 },
+    /// The right-hand side of the system requested to stop the integration.
+    Aborted,
+    /// The right-hand side of the system cannot be evaluated, e.g. outside of its domain.
+    Domain(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NonFinite { time } => write!(f, "non-finite state in the step at t = {}", time),
+            Error::StepSizeUnderflow { time, timestep } => write!(
+                f,
+                "step size underflow (dt = {:e}) after rejecting a step at t = {}",
+                timestep, time
+            ),
+            Error::MaxStepsExceeded { steps } => {
+                write!(f, "maximum number of {} steps exceeded", steps)
+            }
+            Error::Newton(error) => error.fmt(f),
+            Error::SingularMassMatrix { time } => write!(
+                f,
+                "singular mass matrix at t = {} of a DAE that is not semi-explicit",
+                time
+            ),
+            Error::Aborted => write!(f, "integration aborted by the right-hand side"),
+            Error::Domain(message) => write!(f, "right-hand side out of its domain: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Newton(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<NewtonError> for Error {
+    fn from(error: NewtonError) -> Self {
+        Error::Newton(error)
+    }
+}
+
+/// Evaluates the fallible right-hand side of a system for the infallible `do_step`.
+///
+/// The first error is kept, and all derivatives after it are NaN so that the step fails. Adaptive
+/// steppers `check` for it after every attempt, rather than rejecting steps until their step size
+/// underflows.
+pub(crate) struct Guarded<'a, Sy> {
+    pub(crate) system: &'a mut Sy,
+    pub(crate) error: Option<Error>,
+}
+
+impl<'a, Sy> Guarded<'a, Sy> {
+    /// Fail with the error of the right-hand side, if any.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, Sy> NonautonomousOde for Guarded<'a, Sy>
+where
+    Sy: NonautonomousOde,
+    Sy::State: Componentwise,
+{
+    type State = Sy::State;
+
+    fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+        if self.error.is_none() {
+            if let Err(error) = self.system.try_differentiate_at_into(t, state, derivative) {
+                self.error = Some(error);
+            }
+        }
+        if self.error.is_some() {
+            Self::State::assign_components(derivative, state, state, |_, _| f64::NAN);
+        }
+    }
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        self.system.update_state(state, value);
+    }
+}
+
+/// Whether all components of `state` are finite.
+pub(crate) fn is_finite<S: Componentwise>(state: &S) -> bool {
+    let mut finite = true;
+    S::for_each_component(state, state, state, |x, _, _| finite &= x.is_finite());
+    finite
+}
+
+/// Do a step with `step`, evaluating the right-hand side of `system` by its fallible
+/// `try_differentiate_at_into`.
+///
+/// Errors of the right-hand side take precedence over those of `step`. If the step fails or
+/// results in a non-finite state, `state` and the time and step size of the stepper are restored.
+pub(crate) fn guarded_step<St, Sy, F>(
+    stepper: &mut St,
+    system: &mut Sy,
+    state: &mut St::State,
+    step: F,
+) -> Result<(), Error>
+where
+    St: Stepper + ?Sized,
+    St::State: Componentwise,
+    Sy: NonautonomousOde<State = St::State>,
+    F: for<'a> FnOnce(&mut St, &mut Guarded<'a, Sy>, &mut St::State) -> Result<(), Error>,
+{
+    let t0 = stepper.time();
+    let dt0 = stepper.timestep();
+    let x0 = state.clone();
+
+    let mut guarded = Guarded {
+        system,
+        error: None,
+    };
+    let result = step(stepper, &mut guarded, state);
+
+    let result = match guarded.error {
+        Some(error) => Err(error),
+        None => result,
+    };
+    let result = result.and_then(|()| {
+        if is_finite(state) {
+            Ok(())
+        } else {
+            Err(Error::NonFinite { time: t0 })
+        }
+    });
+
+    if result.is_err() {
+        state.clone_from(&x0);
+        stepper.set_time(t0);
+        stepper.set_timestep(dt0);
+    }
+    result
+}
@@ -0,0 +1,236 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::ode::NonautonomousOde;
+use crate::stepper::DenseOutput;
+
+/// The direction of the zero crossings of an event function that trigger the event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// Crossings from negative to positive values.
+    Rising,
+    /// Crossings from positive to negative values.
+    Falling,
+    /// Crossings in either direction.
+    Either,
+}
+
+type EventFunction<'a, S> = Box<dyn FnMut(f64, &S) -> f64 + 'a>;
+
+/// A scalar event function `g(t, x)`, whose zero crossings are located during the integration.
+///
+/// A crossing is detected by a change of sign of `g` over a step and located on the dense output
+/// of that step, using the Illinois variant of regula falsi. The stepper itself is not stepped
+/// again, so that locating events changes neither the integration nor the `Stats`. The reported
+/// time and state lie just past the crossing, within `tolerance` of it.
+pub struct Event<'a, S> {
+    pub(crate) function: EventFunction<'a, S>,
+    pub(crate) direction: Direction,
+    pub(crate) terminal: bool,
+    pub(crate) tolerance: f64,
+}
+
+impl<'a, S> Event<'a, S> {
+    /// Create a non-terminal event triggered by crossings in either direction, located to within
+    /// `1e-10` in time.
+    pub fn new<F>(function: F) -> Self
+    where
+        F: FnMut(f64, &S) -> f64 + 'a,
+    {
+        Event {
+            function: Box::new(function),
+            direction: Direction::Either,
+            terminal: false,
+            tolerance: 1e-10,
+        }
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set whether the integration stops at the event.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    /// Set the absolute tolerance in time the event is located to.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+
+    fn evaluate(&mut self, t: f64, state: &S) -> f64 {
+        (self.function)(t, state)
+    }
+
+    /// Whether going from `before` to `after` is a crossing in the event's direction. Starting
+    /// exactly on zero is not a crossing.
+    fn is_triggered(&self, before: f64, after: f64) -> bool {
+        let rising = before < 0.0 && after >= 0.0;
+        let falling = before > 0.0 && after <= 0.0;
+
+        match self.direction {
+            Direction::Rising => rising,
+            Direction::Falling => falling,
+            Direction::Either => rising || falling,
+        }
+    }
+}
+
+impl<'a, S> fmt::Debug for Event<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("direction", &self.direction)
+            .field("terminal", &self.terminal)
+            .field("tolerance", &self.tolerance)
+            .finish()
+    }
+}
+
+/// An event located during the integration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventOccurrence<S> {
+    /// The position of the event in the slice of events passed to the integration.
+    pub index: usize,
+    pub time: f64,
+    pub state: S,
+    /// Whether the integration stopped at this event.
+    pub terminal: bool,
+}
+
+/// The maximum number of interpolations spent on locating a single event.
+const MAX_ITERATIONS: usize = 100;
+
+/// Locate the crossing of `event` inside the last step of `stepper` of length `dt` from `t0`,
+/// where it takes the values `g0` and `g1` at the beginning and the end `x1`.
+fn locate<St>(
+    stepper: &St,
+    event: &mut Event<'_, St::State>,
+    t0: f64,
+    g0: f64,
+    dt: f64,
+    g1: f64,
+    x1: &St::State,
+) -> (f64, St::State)
+where
+    St: DenseOutput + ?Sized,
+{
+    let tolerance = event.tolerance.max(4.0 * f64::EPSILON * t0.abs());
+
+    let (mut a, mut g_a) = (0.0, g0);
+    let (mut b, mut g_b) = (dt, g1);
+    let mut x_b = x1.clone();
+
+    // Which end of the bracket was kept in the last iteration, for the Illinois modification.
+    let mut kept = Ordering::Equal;
+
+    let mut trial = x1.clone();
+    for _ in 0..MAX_ITERATIONS {
+        if (b - a).abs() <= tolerance {
+            break;
+        }
+
+        let mut h = (a * g_b - b * g_a) / (g_b - g_a);
+        if !(h.is_finite() && (h - a) * (b - h) > 0.0) {
+            h = 0.5 * (a + b);
+        }
+
+        stepper.interpolate(h / dt, &mut trial);
+        let g = event.evaluate(t0 + h, &trial);
+
+        if g0.signum() * g <= 0.0 {
+            b = h;
+            g_b = g;
+            x_b.clone_from(&trial);
+            if kept == Ordering::Less {
+                g_a *= 0.5;
+            }
+            kept = Ordering::Less;
+        } else {
+            a = h;
+            g_a = g;
+            if kept == Ordering::Greater {
+                g_b *= 0.5;
+            }
+            kept = Ordering::Greater;
+        }
+    }
+
+    (t0 + b, x_b)
+}
+
+/// Do a single step, locating the events triggered during it.
+///
+/// `values` holds the values of the event functions at the beginning of the step and is updated
+/// to their values at its end. If a terminal event is triggered, `state` and the time of the
+/// stepper are set to the earliest one and `true` is returned; events after it are discarded.
+pub(crate) fn step_with_events<St, Sy>(
+    stepper: &mut St,
+    system: &mut Sy,
+    state: &mut St::State,
+    events: &mut [Event<'_, St::State>],
+    values: &mut [f64],
+    occurrences: &mut Vec<EventOccurrence<St::State>>,
+) -> bool
+where
+    St: DenseOutput + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    stepper.do_step(system, state);
+
+    let t1 = stepper.time();
+    let dt = stepper.last_timestep();
+    let t0 = t1 - dt;
+
+    let mut found = Vec::new();
+    for (index, (event, value)) in events.iter_mut().zip(values.iter_mut()).enumerate() {
+        let g0 = *value;
+        let g1 = event.evaluate(t1, state);
+        *value = g1;
+
+        if event.is_triggered(g0, g1) {
+            let (time, located) = locate(stepper, event, t0, g0, dt, g1, state);
+            found.push(EventOccurrence {
+                index,
+                time,
+                state: located,
+                terminal: event.terminal,
+            });
+        }
+    }
+
+    found.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+
+    let terminated = match found.iter().position(|occurrence| occurrence.terminal) {
+        Some(position) => {
+            found.truncate(position + 1);
+            let last = &found[position];
+            state.clone_from(&last.state);
+            stepper.set_time(last.time);
+            true
+        }
+        None => false,
+    };
+
+    occurrences.append(&mut found);
+    terminated
+}
+
+/// The values of the event functions at `(t, state)`.
+pub(crate) fn evaluate_all<S>(events: &mut [Event<'_, S>], t: f64, state: &S) -> Vec<f64> {
+    events
+        .iter_mut()
+        .map(|event| event.evaluate(t, state))
+        .collect()
+}
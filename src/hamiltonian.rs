@@ -0,0 +1,18 @@
+/// A separable Hamiltonian system H(t, q, p) = T(p) + V(t, q), with the equations of motion
+/// dq/dt = ∂T/∂p and dp/dt = -∂V/∂q for the position `q` and the momentum `p`.
+///
+/// The steppers implementing `SymplecticStepper` integrate it, alternately updating the position
+/// with the velocity and the momentum with the force. A second-order system d²q/dt² = a(t, q)
+/// is the case of unit mass, where the momentum is the velocity and the acceleration the force,
+/// and only needs to implement `force_into`.
+pub trait Hamiltonian {
+    type State: Clone;
+
+    /// Write the velocity `∂T/∂p` at the `momentum` into `velocity`. The identity by default.
+    fn velocity_into(&mut self, _t: f64, momentum: &Self::State, velocity: &mut Self::State) {
+        velocity.clone_from(momentum);
+    }
+
+    /// Write the force `-∂V/∂q` at the `position` into `force`.
+    fn force_into(&mut self, t: f64, position: &Self::State, force: &mut Self::State);
+}
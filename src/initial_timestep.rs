@@ -0,0 +1,58 @@
+use crate::ode::NonautonomousOde;
+use crate::tolerance::{Componentwise, ErrorControl};
+
+/// Propose a step size to start the integration of `system` from `state` at time `t0`, following
+/// Hairer, Nørsett, Wanner: Solving Ordinary Differential Equations I, Section II.4.
+///
+/// The proposal aims at a local error of about `0.01` relative to the tolerances of
+/// `error_control` for a method of order `order`, taking two evaluations of the right-hand side.
+/// Its magnitude is at most that of `dt_max`, e.g. the duration of the integration, and its
+/// sign is that of `dt_max`, which gives the direction of the integration.
+///
+/// Adaptive steppers can start with the proposal directly. For fixed-step methods it is a
+/// reasonable guess, but the resulting global error should be checked.
+pub fn initial_timestep<Sy>(
+    system: &mut Sy,
+    t0: f64,
+    state: &Sy::State,
+    dt_max: f64,
+    order: u32,
+    error_control: &ErrorControl,
+) -> f64
+where
+    Sy: NonautonomousOde,
+    Sy::State: Componentwise,
+{
+    let f0 = system.differentiate_at(t0, state);
+
+    // Norms of the state and its derivative, relative to the tolerances at the initial state
+    let d0 = error_control.error(state, state, state);
+    let d1 = error_control.error(&f0, state, state);
+
+    let h0 = if d0 < 1e-5 || d1 < 1e-5 {
+        1e-6
+    } else {
+        0.01 * d0 / d1
+    };
+    let h0 = h0.min(dt_max.abs()).copysign(dt_max);
+
+    // An explicit Euler step to estimate the second derivative
+    let mut x1 = state.clone();
+    Componentwise::assign_components(&mut x1, state, &f0, |x, f| x + h0 * f);
+    let f1 = system.differentiate_at(t0 + h0, &x1);
+    let mut difference = f1.clone();
+    Componentwise::assign_components(&mut difference, &f1, &f0, |f1, f0| f1 - f0);
+    let d2 = error_control.error(&difference, state, state) / h0.abs();
+
+    let d = d1.max(d2);
+    let h1 = if d <= 1e-15 {
+        (h0.abs() * 1e-3).max(1e-6)
+    } else {
+        (0.01 / d).powf(1.0 / f64::from(order + 1))
+    };
+
+    (100.0 * h0.abs())
+        .min(h1)
+        .min(dt_max.abs())
+        .copysign(dt_max)
+}
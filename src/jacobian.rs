@@ -0,0 +1,209 @@
+use ndarray::prelude::*;
+use ndarray::{IntoNdProducer, Zip};
+
+use crate::error::Error;
+use crate::ode::NonautonomousOde;
+use crate::stepper::ZipMarker;
+
+/// A system that provides the Jacobian `J_ij = ∂f_i/∂x_j` of its right-hand side.
+pub trait Jacobian: NonautonomousOde {
+    fn jacobian_into(&mut self, t: f64, state: &Self::State, jacobian: &mut Array2<f64>);
+
+    /// Like `jacobian_into`, but able to fail; see `Ode::try_differentiate_into`. The implicit
+    /// steppers evaluate the Jacobian through this method and propagate its errors.
+    fn try_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        jacobian: &mut Array2<f64>,
+    ) -> Result<(), Error> {
+        self.jacobian_into(t, state, jacobian);
+        Ok(())
+    }
+
+    /// The evaluations of the right-hand side made by one call of `jacobian_into` for a state of
+    /// `dim` components, which the steppers count in `Stats::rhs_evaluations`. None for an
+    /// analytic Jacobian.
+    fn jacobian_rhs_evaluations(&self, _dim: usize) -> usize {
+        0
+    }
+}
+
+/// The finite difference scheme used by `FiniteDifference`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Differencing {
+    /// `(f(x + h e_j) - f(x)) / h`, costing one evaluation of the right-hand side per component.
+    Forward,
+    /// `(f(x + h e_j) - f(x - h e_j)) / 2h`, more accurate but costing two evaluations per
+    /// component.
+    Central,
+}
+
+impl Differencing {
+    /// The evaluations of the right-hand side for a state of `dim` components.
+    pub(crate) fn evaluations(self, dim: usize) -> usize {
+        match self {
+            Differencing::Forward => dim + 1,
+            Differencing::Central => 2 * dim,
+        }
+    }
+}
+
+/// Wraps a system to approximate its Jacobian by finite differences.
+///
+/// Each component `x_j` is perturbed by `h = δ max(|x_j|, 1)`. Unless set explicitly with
+/// `with_perturbation`, the relative perturbation `δ` is `sqrt(ε)` for forward and `ε^(1/3)` for
+/// central differences, where `ε` is the machine epsilon.
+#[derive(Clone, Debug)]
+pub struct FiniteDifference<Sy> {
+    pub(crate) system: Sy,
+    pub(crate) differencing: Differencing,
+    pub(crate) perturbation: Option<f64>,
+}
+
+impl<Sy> FiniteDifference<Sy> {
+    /// Wrap `system`, using forward differences.
+    pub fn new(system: Sy) -> Self {
+        FiniteDifference {
+            system,
+            differencing: Differencing::Forward,
+            perturbation: None,
+        }
+    }
+
+    pub fn with_differencing(mut self, differencing: Differencing) -> Self {
+        self.differencing = differencing;
+        self
+    }
+
+    /// Set the relative perturbation `δ` of the components.
+    pub fn with_perturbation(mut self, perturbation: f64) -> Self {
+        self.perturbation = Some(perturbation);
+        self
+    }
+
+    /// The relative perturbation `δ` of the components.
+    pub fn perturbation(&self) -> f64 {
+        self.perturbation
+            .unwrap_or_else(|| match self.differencing {
+                Differencing::Forward => f64::EPSILON.sqrt(),
+                Differencing::Central => f64::EPSILON.cbrt(),
+            })
+    }
+
+    pub fn system(&self) -> &Sy {
+        &self.system
+    }
+
+    pub fn system_mut(&mut self) -> &mut Sy {
+        &mut self.system
+    }
+
+    pub fn into_inner(self) -> Sy {
+        self.system
+    }
+}
+
+impl<Sy> NonautonomousOde for FiniteDifference<Sy>
+where
+    Sy: NonautonomousOde,
+{
+    type State = Sy::State;
+
+    fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+        self.system.differentiate_at_into(t, state, derivative);
+    }
+
+    fn try_differentiate_at_into(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Error> {
+        self.system.try_differentiate_at_into(t, state, derivative)
+    }
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        self.system.update_state(state, value);
+    }
+}
+
+/// Copy `state` into `perturbed`, moving its `j`-th component `x_j` by `δ max(|x_j|, 1)`.
+/// Returns `x_j` and its perturbed value.
+fn perturb<P>(perturbed: &mut P, state: &P, j: usize, delta: f64) -> (f64, f64)
+where
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    let mut x_j = 0.0;
+    let mut x_h = 0.0;
+    Zip::indexed(perturbed).and(state).apply(|i, p, &x| {
+        if i == j {
+            x_j = x;
+            x_h = x + delta * x.abs().max(1.0);
+            *p = x_h;
+        } else {
+            *p = x;
+        }
+    });
+    (x_j, x_h)
+}
+
+impl<Sy, P: ZipMarker> Jacobian for FiniteDifference<Sy>
+where
+    Sy: NonautonomousOde<State = P>,
+    P: Clone,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    fn jacobian_into(&mut self, t: f64, state: &P, jacobian: &mut Array2<f64>) {
+        self.try_jacobian_into(t, state, jacobian)
+            .unwrap_or_else(|error| panic!("FiniteDifference: {}", error));
+    }
+
+    /// Evaluates the right-hand side by its fallible `try_differentiate_at_into`.
+    fn try_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &P,
+        jacobian: &mut Array2<f64>,
+    ) -> Result<(), Error> {
+        let delta = self.perturbation();
+
+        let mut perturbed = state.clone();
+        let mut lower = state.clone();
+        let mut upper = state.clone();
+
+        if self.differencing == Differencing::Forward {
+            self.system
+                .try_differentiate_at_into(t, state, &mut lower)?;
+        }
+
+        for (j, column) in jacobian.gencolumns_mut().into_iter().enumerate() {
+            let (x, x_upper) = perturb(&mut perturbed, state, j, delta);
+            self.system
+                .try_differentiate_at_into(t, &perturbed, &mut upper)?;
+
+            // Divide by the difference of the actually representable arguments.
+            let h = match self.differencing {
+                Differencing::Forward => x_upper - x,
+                Differencing::Central => {
+                    let (_, x_lower) = perturb(&mut perturbed, state, j, -delta);
+                    self.system
+                        .try_differentiate_at_into(t, &perturbed, &mut lower)?;
+                    x_upper - x_lower
+                }
+            };
+
+            Zip::from(column)
+                .and(&upper)
+                .and(&lower)
+                .apply(|jac, &f_upper, &f_lower| *jac = (f_upper - f_lower) / h);
+        }
+        Ok(())
+    }
+
+    fn jacobian_rhs_evaluations(&self, dim: usize) -> usize {
+        self.differencing.evaluations(dim)
+    }
+}
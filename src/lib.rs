@@ -1,9 +1,44 @@
+mod controller;
+mod dde;
+mod error;
+mod event;
+mod hamiltonian;
+mod initial_timestep;
+mod jacobian;
+mod linalg;
+mod mass_matrix;
+mod noise_process;
+mod observer;
 mod ode;
+mod residual;
+mod sde;
+mod second_order;
+mod solution;
+mod stats;
 mod stepper;
+mod steps;
+mod tolerance;
 
 #[cfg(feature = "tuple")]
 mod tuples;
 
 // Re-exports
-pub use ode::Ode;
+pub use controller::{IController, PiController, PidController, StepSize, StepSizeController};
+pub use dde::{Dde, DdeIntegrator, History};
+pub use error::Error;
+pub use event::{Direction, Event, EventOccurrence};
+pub use hamiltonian::Hamiltonian;
+pub use initial_timestep::initial_timestep;
+pub use jacobian::{Differencing, FiniteDifference, Jacobian};
+pub use mass_matrix::MassMatrix;
+pub use noise_process::NoiseProcess;
+pub use observer::{ArrayRecorder, Observer, Sampled, Trajectory};
+pub use ode::{NonautonomousOde, Ode};
+pub use residual::{Residual, ResidualJacobian};
+pub use sde::{Noise, Sde};
+pub use second_order::SecondOrderOde;
+pub use solution::{solve, try_solve, Solution, SolveOptions};
+pub use stats::Stats;
 pub use stepper::*;
+pub use steps::Steps;
+pub use tolerance::{Componentwise, ErrorControl, ErrorNorm, Tolerance};
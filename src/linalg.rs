@@ -0,0 +1,85 @@
+use ndarray::prelude::*;
+
+/// An in-place LU decomposition with partial pivoting, `P A = L U`.
+///
+/// `L` (with unit diagonal, not stored) and `U` share the storage of the decomposed matrix.
+#[derive(Debug)]
+pub(crate) struct Lu {
+    pub(crate) lu: Array2<f64>,
+    pub(crate) pivots: Vec<usize>,
+}
+
+impl Lu {
+    pub(crate) fn new(n: usize) -> Self {
+        Lu {
+            lu: Array2::zeros((n, n)),
+            pivots: (0..n).collect(),
+        }
+    }
+
+    /// Decompose the matrix currently stored in `self.lu`, returning `false` if it is singular.
+    pub(crate) fn factorize(&mut self) -> bool {
+        let n = self.lu.rows();
+
+        for k in 0..n {
+            let mut pivot = k;
+            let mut max = self.lu[(k, k)].abs();
+            for i in k + 1..n {
+                let value = self.lu[(i, k)].abs();
+                if value > max {
+                    max = value;
+                    pivot = i;
+                }
+            }
+
+            if max == 0.0 || max.is_nan() {
+                return false;
+            }
+
+            self.pivots[k] = pivot;
+            if pivot != k {
+                for j in 0..n {
+                    self.lu.swap((k, j), (pivot, j));
+                }
+            }
+
+            let diagonal = self.lu[(k, k)];
+            for i in k + 1..n {
+                let factor = self.lu[(i, k)] / diagonal;
+                self.lu[(i, k)] = factor;
+                if factor != 0.0 {
+                    for j in k + 1..n {
+                        let u_kj = self.lu[(k, j)];
+                        self.lu[(i, j)] -= factor * u_kj;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Solve `A x = b` in place, using a previous decomposition of `A`.
+    pub(crate) fn solve_into(&self, b: &mut Array1<f64>) {
+        let n = self.lu.rows();
+
+        for k in 0..n {
+            b.swap(k, self.pivots[k]);
+        }
+
+        for i in 1..n {
+            let mut sum = b[i];
+            for j in 0..i {
+                sum -= self.lu[(i, j)] * b[j];
+            }
+            b[i] = sum;
+        }
+
+        for i in (0..n).rev() {
+            let mut sum = b[i];
+            for j in i + 1..n {
+                sum -= self.lu[(i, j)] * b[j];
+            }
+            b[i] = sum / self.lu[(i, i)];
+        }
+    }
+}
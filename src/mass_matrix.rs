@@ -0,0 +1,24 @@
+use ndarray::prelude::*;
+
+use crate::jacobian::FiniteDifference;
+use crate::ode::NonautonomousOde;
+
+/// A system of differential-algebraic equations in mass matrix form, M(t) dx/dt = f(t, x), where
+/// the mass matrix M may be singular.
+///
+/// The rows of a singular M that are zero are algebraic equations 0 = f_i(t, x), and the
+/// components whose columns are zero are algebraic variables. `Radau5` integrates such systems
+/// of index 1, where the algebraic equations determine the algebraic variables.
+pub trait MassMatrix: NonautonomousOde {
+    /// Write the mass matrix at time `t` into `mass`.
+    fn mass_matrix_into(&mut self, t: f64, mass: &mut Array2<f64>);
+}
+
+impl<Sy> MassMatrix for FiniteDifference<Sy>
+where
+    Sy: MassMatrix,
+{
+    fn mass_matrix_into(&mut self, t: f64, mass: &mut Array2<f64>) {
+        self.system.mass_matrix_into(t, mass);
+    }
+}
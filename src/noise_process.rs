@@ -0,0 +1,370 @@
+use ndarray::Array2;
+use rand::Rng;
+
+use crate::sde::wiener_increment;
+use crate::tolerance::Componentwise;
+
+/// A realization of Wiener processes W(t) with the components of a state, e.g. a `Vec<f64>`,
+/// `Array1<f64>`, or `Array2<f64>`, starting from W(0) = 0 and drawn on demand from a seeded
+/// random number generator.
+///
+/// Every value drawn is kept, so that the realization can be replayed exactly with `set_time`,
+/// e.g. to run several schemes on the same Brownian path. Values at times between those already
+/// drawn are sampled from the Brownian bridge, so refining a path keeps it consistent with the
+/// increments drawn before. The time integral of the path is drawn along with it, for the
+/// iterated integrals of stochastic Runge–Kutta methods.
+///
+/// By default the components are independent standard Wiener processes. `with_covariance`
+/// correlates them.
+///
+/// The SDE steppers draw their increments from a `NoiseProcess` with one component per Wiener
+/// process: one per component of the state for `Noise::Diagonal`, a single one (e.g. an `f64`)
+/// for `Noise::Scalar`, and `m` for `Noise::General(m)`.
+#[derive(Clone, Debug)]
+pub struct NoiseProcess<W, R> {
+    t: f64,
+    rng: R,
+
+    // The times drawn so far in increasing order, the values of the independent Wiener
+    // processes at them, and their time integrals ∫ (W(s) - W(t_{i-1})) ds over the interval
+    // from the time before.
+    times: Vec<f64>,
+    values: Vec<W>,
+    integrals: Vec<W>,
+
+    // The lower triangular Cholesky factor of the covariance, if any.
+    factor: Option<Array2<f64>>,
+
+    // Standard normal samples.
+    xi1: W,
+    xi2: W,
+
+    out: W,
+    out_integral: W,
+    components: Vec<f64>,
+}
+
+impl<W, R> NoiseProcess<W, R>
+where
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    /// Create a new process with the components of `shape`, drawing from `rng`.
+    pub fn new(shape: &W, rng: R) -> Self {
+        let mut zero = shape.clone();
+        W::linear_combination(&mut zero, &[]);
+
+        NoiseProcess {
+            t: 0.0,
+            rng,
+
+            times: vec![0.0],
+            values: vec![zero.clone()],
+            integrals: vec![zero.clone()],
+
+            factor: None,
+
+            xi1: zero.clone(),
+            xi2: zero.clone(),
+
+            out: zero.clone(),
+            out_integral: zero,
+            components: Vec::new(),
+        }
+    }
+
+    /// Correlate the components by the symmetric positive definite `covariance`, so that the
+    /// increment over `dt` has the covariance `covariance * dt`.
+    ///
+    /// The rows and columns of `covariance` follow the components of the state in row-major
+    /// order.
+    ///
+    /// Panics if `covariance` doesn't match the number of components or is not positive
+    /// definite.
+    pub fn with_covariance(mut self, covariance: &Array2<f64>) -> Self {
+        let n = self.components();
+        assert_eq!(
+            covariance.dim(),
+            (n, n),
+            "the covariance must be a square matrix with one row per component"
+        );
+
+        self.factor = Some(cholesky(covariance));
+        self
+    }
+
+    /// The number of components, i.e. of Wiener processes.
+    pub fn components(&self) -> usize {
+        let mut n = 0;
+        W::for_each_component(&self.out, &self.out, &self.out, |_, _, _| n += 1);
+        n
+    }
+
+    /// The current time, at which the next increment starts.
+    pub fn time(&self) -> f64 {
+        self.t
+    }
+
+    /// Move to time `t`; going back replays the increments drawn before.
+    pub fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    /// Forget the realization, starting a new one from W(0) = 0 at time 0.
+    pub fn clear(&mut self) {
+        self.t = 0.0;
+        self.times.truncate(1);
+        self.values.truncate(1);
+        self.integrals.truncate(1);
+    }
+
+    /// The random number generator, e.g. to reseed it.
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    /// The value W(t), drawing it if necessary.
+    ///
+    /// Panics if `t` is negative.
+    pub fn value_at(&mut self, t: f64) -> &W {
+        let i = self.sample(t);
+        self.out.clone_from(&self.values[i]);
+        correlate(&self.factor, &mut self.components, &mut self.out);
+        &self.out
+    }
+
+    /// The increment W(t + dt) - W(t) from the current time t, which advances to t + dt.
+    pub fn increment(&mut self, dt: f64) -> &W {
+        let (start, end) = self.advance(dt);
+
+        W::linear_combination(
+            &mut self.out,
+            &[(1.0, &self.values[end]), (-1.0, &self.values[start])],
+        );
+        correlate(&self.factor, &mut self.components, &mut self.out);
+        &self.out
+    }
+
+    /// The increment W(t + dt) - W(t) from the current time t, together with its time integral
+    /// ∫ (W(s) - W(t)) ds from t to t + dt, which the time advances to.
+    pub fn increment_with_integral(&mut self, dt: f64) -> (&W, &W) {
+        let (start, end) = self.advance(dt);
+        let (first, last) = (start.min(end), start.max(end));
+
+        // Add up the integrals over the intervals from the value at the first time.
+        W::linear_combination(&mut self.out_integral, &[]);
+        for i in first + 1..=last {
+            let dt_i = self.times[i] - self.times[i - 1];
+            let sum = self.out_integral.clone();
+            W::linear_combination(
+                &mut self.out_integral,
+                &[
+                    (1.0, &sum),
+                    (1.0, &self.integrals[i]),
+                    (dt_i, &self.values[i - 1]),
+                    (-dt_i, &self.values[first]),
+                ],
+            );
+        }
+
+        W::linear_combination(
+            &mut self.out,
+            &[(1.0, &self.values[end]), (-1.0, &self.values[start])],
+        );
+        if end < start {
+            // Integrating backward, from the value at the last time.
+            let integral = self.out_integral.clone();
+            W::linear_combination(
+                &mut self.out_integral,
+                &[(-1.0, &integral), (dt, &self.out)],
+            );
+        }
+
+        correlate(&self.factor, &mut self.components, &mut self.out);
+        correlate(&self.factor, &mut self.components, &mut self.out_integral);
+        (&self.out, &self.out_integral)
+    }
+
+    /// The components of the increment over `dt`, and of its time integral if `integral` is
+    /// given, in the order of `Componentwise`. The time advances to t + dt.
+    pub(crate) fn increment_components(
+        &mut self,
+        dt: f64,
+        increment: &mut Vec<f64>,
+        integral: Option<&mut Vec<f64>>,
+    ) {
+        match integral {
+            Some(integral) => {
+                let (dw, dz) = self.increment_with_integral(dt);
+                components_into(dw, increment);
+                components_into(dz, integral);
+            }
+            None => components_into(self.increment(dt), increment),
+        }
+    }
+
+    /// Draw the values at t and t + dt unless they are known, and advance to t + dt, returning
+    /// the indices of both.
+    fn advance(&mut self, dt: f64) -> (usize, usize) {
+        let t = self.t;
+        self.sample(t);
+        let end = self.sample(t + dt);
+        let start = self.position(t).unwrap();
+        self.t += dt;
+        (start, end)
+    }
+
+    /// The index of the time `t`, if it has been drawn.
+    fn position(&self, t: f64) -> Result<usize, usize> {
+        self.times
+            .binary_search_by(|time| time.partial_cmp(&t).unwrap())
+    }
+
+    /// Draw the value of the independent processes at `t` unless it is known, returning its
+    /// index.
+    fn sample(&mut self, t: f64) -> usize {
+        assert!(t >= 0.0, "the noise process starts at t = 0, not {}", t);
+
+        let i = match self.position(t) {
+            Ok(i) => return i,
+            Err(i) => i,
+        };
+
+        self.draw_normals();
+        let before = &self.values[i - 1];
+        let (mut value, mut integral) = (before.clone(), before.clone());
+        if i == self.times.len() {
+            // A fresh increment beyond the end of the path.
+            let h = t - self.times[i - 1];
+            let sqrt_h = h.sqrt();
+            W::linear_combination(&mut value, &[(1.0, before), (sqrt_h, &self.xi1)]);
+            W::linear_combination(
+                &mut integral,
+                &[
+                    (h * sqrt_h / 2.0, &self.xi1),
+                    (h * sqrt_h / (2.0 * 3f64.sqrt()), &self.xi2),
+                ],
+            );
+
+            self.times.push(t);
+            self.values.push(value);
+            self.integrals.push(integral);
+            return i;
+        }
+
+        // The Brownian bridge between the neighbouring values, sampling (W(s), Z(s)) conditioned
+        // on (W(h), Z(h)), where Z(s) = ∫_0^s W(u) du and the times are relative to the value
+        // before.
+        let h = self.times[i] - self.times[i - 1];
+        let s = t - self.times[i - 1];
+        let u = s / h;
+
+        // In units of sqrt(h) for W and h^(3/2) for Z, the conditional mean is K (W(h), Z(h)).
+        let k11 = -2.0 * u + 3.0 * u * u;
+        let k12 = 6.0 * u - 6.0 * u * u;
+        let k21 = -u * u + u * u * u;
+        let k22 = 3.0 * u * u - 2.0 * u * u * u;
+
+        // The conditional covariance Σ11 - K Σ21, and its Cholesky factor.
+        let (c11, c12) = (u, u - u * u / 2.0);
+        let (c21, c22) = (u * u / 2.0, u * u / 2.0 - u * u * u / 6.0);
+        let s11 = u - (k11 * c11 + k12 * c12);
+        let s21 = u * u / 2.0 - (k21 * c11 + k22 * c12);
+        let s22 = u * u * u / 3.0 - (k21 * c21 + k22 * c22);
+
+        let l11 = s11.max(0.0).sqrt();
+        let l21 = if l11 > 0.0 { s21 / l11 } else { 0.0 };
+        let l22 = (s22 - l21 * l21).max(0.0).sqrt();
+
+        let sqrt_h = h.sqrt();
+        let mut dw = before.clone();
+        W::linear_combination(&mut dw, &[(1.0, &self.values[i]), (-1.0, before)]);
+        let dz = &self.integrals[i];
+
+        let mut first_dw = dw.clone();
+        W::linear_combination(
+            &mut first_dw,
+            &[(k11, &dw), (k12 / h, dz), (sqrt_h * l11, &self.xi1)],
+        );
+        W::linear_combination(
+            &mut integral,
+            &[
+                (k21 * h, &dw),
+                (k22, dz),
+                (h * sqrt_h * l21, &self.xi1),
+                (h * sqrt_h * l22, &self.xi2),
+            ],
+        );
+        W::linear_combination(&mut value, &[(1.0, before), (1.0, &first_dw)]);
+
+        // The integral over the rest of the interval starts from the new value.
+        let mut rest = dw;
+        W::linear_combination(
+            &mut rest,
+            &[(1.0, dz), (-1.0, &integral), (-(h - s), &first_dw)],
+        );
+
+        self.integrals[i] = rest;
+        self.times.insert(i, t);
+        self.values.insert(i, value);
+        self.integrals.insert(i, integral);
+        i
+    }
+
+    /// Fill `xi1` and `xi2` with independent standard normal samples.
+    fn draw_normals(&mut self) {
+        let rng = &mut self.rng;
+        W::assign_components(&mut self.xi1, &self.xi2, &self.xi2, |_, _| {
+            wiener_increment(rng, 1.0)
+        });
+        W::assign_components(&mut self.xi2, &self.xi1, &self.xi1, |_, _| {
+            wiener_increment(rng, 1.0)
+        });
+    }
+}
+
+/// Write the components of `w` into `into`.
+fn components_into<W: Componentwise>(w: &W, into: &mut Vec<f64>) {
+    into.clear();
+    W::for_each_component(w, w, w, |x, _, _| into.push(x));
+}
+
+/// Multiply `w` by the Cholesky factor of the covariance, if any.
+fn correlate<W: Componentwise + Clone>(
+    factor: &Option<Array2<f64>>,
+    components: &mut Vec<f64>,
+    w: &mut W,
+) {
+    let factor = match factor {
+        Some(factor) => factor,
+        None => return,
+    };
+
+    components_into(w, components);
+    let mut row = 0;
+    let shape = w.clone();
+    W::assign_components(w, &shape, &shape, |_, _| {
+        let y = (0..=row).map(|j| factor[(row, j)] * components[j]).sum();
+        row += 1;
+        y
+    });
+}
+
+/// The lower triangular Cholesky factor L of `a = L Lᵀ`.
+fn cholesky(a: &Array2<f64>) -> Array2<f64> {
+    let n = a.rows();
+    let mut l = Array2::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| l[(i, k)] * l[(j, k)]).sum();
+            if i == j {
+                let pivot = a[(i, i)] - sum;
+                assert!(pivot > 0.0, "the covariance is not positive definite");
+                l[(i, i)] = pivot.sqrt();
+            } else {
+                l[(i, j)] = (a[(i, j)] - sum) / l[(j, j)];
+            }
+        }
+    }
+    l
+}
@@ -0,0 +1,194 @@
+use ndarray::prelude::*;
+use ndarray::{IntoNdProducer, Zip};
+
+use crate::stepper::ZipMarker;
+
+/// A callback invoked by the `*_with` integration methods of the steppers.
+///
+/// `observe` is called once with the initial state and step index `0`, and then after every step
+/// with the current time, the new state, and the number of steps taken so far. Closures
+/// `FnMut(f64, &S, usize)` are observers, and `()` is an observer that does nothing.
+pub trait Observer<S> {
+    fn observe(&mut self, t: f64, state: &S, step: usize);
+}
+
+impl<S> Observer<S> for () {
+    fn observe(&mut self, _t: f64, _state: &S, _step: usize) {}
+}
+
+impl<S, F> Observer<S> for F
+where
+    F: FnMut(f64, &S, usize),
+{
+    fn observe(&mut self, t: f64, state: &S, step: usize) {
+        self(t, state, step)
+    }
+}
+
+/// Records the times and a copy of every observed state.
+#[derive(Clone, Debug)]
+pub struct Trajectory<S> {
+    pub(crate) times: Vec<f64>,
+    pub(crate) states: Vec<S>,
+}
+
+impl<S> Trajectory<S> {
+    pub fn new() -> Self {
+        Trajectory {
+            times: Vec::new(),
+            states: Vec::new(),
+        }
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Return the recorded times and states.
+    pub fn into_inner(self) -> (Vec<f64>, Vec<S>) {
+        (self.times, self.states)
+    }
+}
+
+impl<S> Default for Trajectory<S> {
+    fn default() -> Self {
+        Trajectory::new()
+    }
+}
+
+impl<S: Clone> Observer<S> for Trajectory<S> {
+    fn observe(&mut self, t: f64, state: &S, _step: usize) {
+        self.times.push(t);
+        self.states.push(state.clone());
+    }
+}
+
+/// Records the times and the observed states as the rows of an `Array2<f64>`.
+///
+/// The states can be scalars, or one dimensional arrays or `Vec`s, all of the same length.
+#[derive(Clone, Debug, Default)]
+pub struct ArrayRecorder {
+    pub(crate) times: Vec<f64>,
+    pub(crate) data: Vec<f64>,
+    pub(crate) dim: usize,
+}
+
+impl ArrayRecorder {
+    pub fn new() -> Self {
+        ArrayRecorder {
+            times: Vec::new(),
+            data: Vec::new(),
+            dim: 0,
+        }
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// The recorded states, one per row.
+    pub fn states(&self) -> ArrayView2<'_, f64> {
+        ArrayView2::from_shape((self.times.len(), self.dim), &self.data)
+            .expect("recorded states have a consistent length")
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Return the recorded times and states, one per row.
+    pub fn into_inner(self) -> (Vec<f64>, Array2<f64>) {
+        let states = Array2::from_shape_vec((self.times.len(), self.dim), self.data)
+            .expect("recorded states have a consistent length");
+        (self.times, states)
+    }
+
+    /// Record `t` after the components of a state have been appended to `data`.
+    ///
+    /// Panics if the state has a different length than the previous ones.
+    fn push_row(&mut self, t: f64, start: usize) {
+        let dim = self.data.len() - start;
+        if self.times.is_empty() {
+            self.dim = dim;
+        } else {
+            assert_eq!(
+                dim, self.dim,
+                "ArrayRecorder: state of length {} observed after states of length {}",
+                dim, self.dim
+            );
+        }
+        self.times.push(t);
+    }
+}
+
+impl Observer<f64> for ArrayRecorder {
+    fn observe(&mut self, t: f64, state: &f64, _step: usize) {
+        let start = self.data.len();
+        self.data.push(*state);
+        self.push_row(t, start);
+    }
+}
+
+impl<P: ZipMarker> Observer<P> for ArrayRecorder
+where
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+{
+    fn observe(&mut self, t: f64, state: &P, _step: usize) {
+        let start = self.data.len();
+        let data = &mut self.data;
+        Zip::from(state).apply(|&x| data.push(x));
+        self.push_row(t, start);
+    }
+}
+
+/// Passes only every `k`-th step, starting with the initial state, on to another observer.
+#[derive(Clone, Debug)]
+pub struct Sampled<O> {
+    pub(crate) observer: O,
+    pub(crate) every: usize,
+}
+
+impl<O> Sampled<O> {
+    /// Panics if `every` is `0`.
+    pub fn new(observer: O, every: usize) -> Self {
+        assert!(every > 0, "Sampled: cannot sample every 0th step");
+        Sampled { observer, every }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn into_inner(self) -> O {
+        self.observer
+    }
+}
+
+impl<S, O> Observer<S> for Sampled<O>
+where
+    O: Observer<S>,
+{
+    // `usize::is_multiple_of` is too recent for the supported compilers.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn observe(&mut self, t: f64, state: &S, step: usize) {
+        if step % self.every == 0 {
+            self.observer.observe(t, state, step);
+        }
+    }
+}
@@ -1,8 +1,25 @@
+use crate::error::Error;
+
 pub trait Ode {
     type State: Clone;
 
     fn differentiate_into(&mut self, state: &Self::State, derivative: &mut Self::State);
 
+    /// Like `differentiate_into`, but able to fail, e.g. if `state` lies outside of the domain of
+    /// the right-hand side. The `try_*` methods of the steppers evaluate the right-hand side
+    /// through this method and return its error.
+    ///
+    /// Systems with a fallible right-hand side implement it, and implement `differentiate_into` by
+    /// panicking on an error.
+    fn try_differentiate_into(
+        &mut self,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Error> {
+        self.differentiate_into(state, derivative);
+        Ok(())
+    }
+
     fn differentiate(&mut self, state: &Self::State) -> Self::State {
         let mut derivative = state.clone();
         self.differentiate_into(state, &mut derivative);
@@ -13,3 +30,64 @@ pub trait Ode {
         state.clone_from(value);
     }
 }
+
+/// A system of ODEs whose right-hand side depends explicitly on time, dx/dt = f(t, x).
+///
+/// This is the trait the steppers integrate. Every autonomous `Ode` implements it by ignoring the
+/// time argument, so only forced or otherwise time dependent systems need to implement it
+/// directly.
+pub trait NonautonomousOde {
+    type State: Clone;
+
+    fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State);
+
+    /// Like `differentiate_at_into`, but able to fail; see `Ode::try_differentiate_into`.
+    fn try_differentiate_at_into(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Error> {
+        self.differentiate_at_into(t, state, derivative);
+        Ok(())
+    }
+
+    fn differentiate_at(&mut self, t: f64, state: &Self::State) -> Self::State {
+        let mut derivative = state.clone();
+        self.differentiate_at_into(t, state, &mut derivative);
+        derivative
+    }
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        state.clone_from(value);
+    }
+}
+
+impl<T> NonautonomousOde for T
+where
+    T: Ode,
+{
+    type State = T::State;
+
+    fn differentiate_at_into(
+        &mut self,
+        _t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) {
+        self.differentiate_into(state, derivative);
+    }
+
+    fn try_differentiate_at_into(
+        &mut self,
+        _t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Error> {
+        self.try_differentiate_into(state, derivative)
+    }
+
+    fn update_state(&self, state: &mut Self::State, value: &Self::State) {
+        Ode::update_state(self, state, value);
+    }
+}
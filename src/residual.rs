@@ -0,0 +1,159 @@
+use ndarray::prelude::*;
+
+use crate::error::Error;
+use crate::jacobian::{Differencing, FiniteDifference};
+
+/// A fully implicit system of differential-algebraic equations in residual form,
+/// F(t, x, dx/dt) = 0, integrated by `Bdf`.
+pub trait Residual {
+    /// Write the residual F(t, x, dx/dt) into `residual`.
+    fn residual_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    );
+
+    /// Like `residual_into`, but able to fail; see `Ode::try_differentiate_into`.
+    fn try_residual_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) -> Result<(), Error> {
+        self.residual_into(t, state, derivative, residual);
+        Ok(())
+    }
+}
+
+/// A system in residual form that provides the iteration matrix `α ∂F/∂x' + ∂F/∂x` of the
+/// Newton iteration, where `x'` is the derivative `dx/dt`.
+pub trait ResidualJacobian: Residual {
+    fn residual_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        alpha: f64,
+        jacobian: &mut Array2<f64>,
+    );
+
+    /// Like `residual_jacobian_into`, but able to fail; `Bdf` evaluates the iteration matrix
+    /// through this method and propagates its errors.
+    fn try_residual_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        alpha: f64,
+        jacobian: &mut Array2<f64>,
+    ) -> Result<(), Error> {
+        self.residual_jacobian_into(t, state, derivative, alpha, jacobian);
+        Ok(())
+    }
+
+    /// The evaluations of the residual made by one call of `residual_jacobian_into` for a state
+    /// of `dim` components, which `Bdf` counts in `Stats::rhs_evaluations`. None for an analytic
+    /// iteration matrix.
+    fn residual_evaluations(&self, _dim: usize) -> usize {
+        0
+    }
+}
+
+impl<Sy> Residual for FiniteDifference<Sy>
+where
+    Sy: Residual,
+{
+    fn residual_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) {
+        self.system.residual_into(t, state, derivative, residual);
+    }
+
+    fn try_residual_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) -> Result<(), Error> {
+        self.system
+            .try_residual_into(t, state, derivative, residual)
+    }
+}
+
+/// Perturbs each component `x_j` by `h` and its derivative `x'_j` by `α h` at the same time,
+/// which gives the column `α ∂F/∂x'_j + ∂F/∂x_j` with a single evaluation of the residual for
+/// forward differences.
+impl<Sy> ResidualJacobian for FiniteDifference<Sy>
+where
+    Sy: Residual,
+{
+    fn residual_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        alpha: f64,
+        jacobian: &mut Array2<f64>,
+    ) {
+        self.try_residual_jacobian_into(t, state, derivative, alpha, jacobian)
+            .unwrap_or_else(|error| panic!("FiniteDifference: {}", error));
+    }
+
+    /// Evaluates the residual by its fallible `try_residual_into`.
+    fn try_residual_jacobian_into(
+        &mut self,
+        t: f64,
+        state: &Array1<f64>,
+        derivative: &Array1<f64>,
+        alpha: f64,
+        jacobian: &mut Array2<f64>,
+    ) -> Result<(), Error> {
+        let delta = self.perturbation();
+
+        let mut x = state.clone();
+        let mut dx = derivative.clone();
+        let mut lower = state.clone();
+        let mut upper = state.clone();
+
+        if self.differencing == Differencing::Forward {
+            self.system
+                .try_residual_into(t, state, derivative, &mut lower)?;
+        }
+
+        for (j, mut column) in jacobian.gencolumns_mut().into_iter().enumerate() {
+            let h = delta * state[j].abs().max(1.0);
+
+            // Divide by the difference of the actually representable arguments.
+            x[j] = state[j] + h;
+            dx[j] = derivative[j] + alpha * h;
+            self.system.try_residual_into(t, &x, &dx, &mut upper)?;
+            let h = match self.differencing {
+                Differencing::Forward => x[j] - state[j],
+                Differencing::Central => {
+                    let x_upper = x[j];
+                    x[j] = state[j] - h;
+                    dx[j] = derivative[j] - alpha * h;
+                    self.system.try_residual_into(t, &x, &dx, &mut lower)?;
+                    x_upper - x[j]
+                }
+            };
+            x[j] = state[j];
+            dx[j] = derivative[j];
+
+            column.assign(&((&upper - &lower) / h));
+        }
+        Ok(())
+    }
+
+    fn residual_evaluations(&self, dim: usize) -> usize {
+        self.differencing.evaluations(dim)
+    }
+}
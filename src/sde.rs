@@ -0,0 +1,76 @@
+use rand::distributions::StandardNormal;
+use rand::Rng;
+
+use crate::ode::NonautonomousOde;
+use crate::tolerance::Componentwise;
+
+/// The structure of the noise driving an `Sde`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Noise {
+    /// Every component is driven by its own Wiener process, `dx_i = f_i dt + g_i dW_i`.
+    Diagonal,
+    /// All components are driven by the same Wiener process, `dx = f dt + g dW`.
+    Scalar,
+    /// `m` Wiener processes, each with its own column `g_j` of the diffusion,
+    /// `dx = f dt + Σ_j g_j dW_j`.
+    General(usize),
+}
+
+impl Noise {
+    /// The number of columns of the diffusion.
+    pub(crate) fn columns(self) -> usize {
+        match self {
+            Noise::Diagonal | Noise::Scalar => 1,
+            Noise::General(m) => m,
+        }
+    }
+
+    /// The number of Wiener processes driving a system with `n` components.
+    pub(crate) fn processes(self, n: usize) -> usize {
+        match self {
+            Noise::Diagonal => n,
+            Noise::Scalar => 1,
+            Noise::General(m) => m,
+        }
+    }
+}
+
+/// A system of stochastic differential equations in the Itô sense, dx = f(t, x) dt + g(t, x) dW.
+///
+/// The drift `f` is the right-hand side of the `NonautonomousOde`, and the diffusion `g` is
+/// given by `diffusion_into`. The steppers implementing `SdeStepper` integrate it.
+pub trait Sde: NonautonomousOde {
+    /// The structure of the noise, `Noise::Diagonal` by default.
+    fn noise(&self) -> Noise {
+        Noise::Diagonal
+    }
+
+    /// Write the diffusion at time `t` into `diffusion`, which holds one column per Wiener
+    /// process for `Noise::General`, and a single one with the shape of the state otherwise.
+    fn diffusion_into(&mut self, t: f64, state: &Self::State, diffusion: &mut [Self::State]);
+}
+
+/// Draw a Wiener increment over a step of size `dt`, given `sqrt_dt = sqrt(|dt|)`.
+pub(crate) fn wiener_increment<R: Rng>(rng: &mut R, sqrt_dt: f64) -> f64 {
+    sqrt_dt * rng.sample::<f64, _>(StandardNormal)
+}
+
+/// Panic unless the noise process of the stepper `name`, with `components` components, has one
+/// per Wiener process of `noise` driving `state`.
+pub(crate) fn check_noise_process<T: Componentwise>(
+    name: &str,
+    noise: Noise,
+    state: &T,
+    components: usize,
+) {
+    let mut n = 0;
+    T::for_each_component(state, state, state, |_, _, _| n += 1);
+    let processes = noise.processes(n);
+    if components != processes {
+        panic!(
+            "{}: the noise process has {} components, but the system is driven by {} Wiener \
+             processes",
+            name, components, processes
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::hamiltonian::Hamiltonian;
+
+/// A system of second-order ODEs d²x/dt² = f(t, x), whose right-hand side does not depend on
+/// the velocity dx/dt.
+///
+/// The steppers implementing `RknStepper` integrate it, updating the position and the velocity.
+/// Every such system is also a `Hamiltonian` system of unit mass, with the acceleration as the
+/// force, for the `SymplecticStepper`s.
+pub trait SecondOrderOde {
+    type State: Clone;
+
+    /// Write the acceleration f(t, x) at the `position` into `acceleration`.
+    fn acceleration_into(&mut self, t: f64, position: &Self::State, acceleration: &mut Self::State);
+}
+
+impl<T> Hamiltonian for T
+where
+    T: SecondOrderOde,
+{
+    type State = T::State;
+
+    fn force_into(&mut self, t: f64, position: &Self::State, force: &mut Self::State) {
+        self.acceleration_into(t, position, force);
+    }
+}
@@ -0,0 +1,381 @@
+use std::iter;
+use std::slice;
+
+use ndarray::prelude::*;
+
+use crate::error::Error;
+use crate::observer::{ArrayRecorder, Observer};
+use crate::ode::NonautonomousOde;
+use crate::stepper::{hermite_basis, DenseOutput, ROUNDOFF};
+use crate::tolerance::Componentwise;
+
+/// The options of `solve`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SolveOptions {
+    pub(crate) save_at: Option<Vec<f64>>,
+    pub(crate) dense: bool,
+    pub(crate) max_steps: Option<usize>,
+}
+
+impl SolveOptions {
+    /// Save the initial state and the state after every step, without derivatives, and take any
+    /// number of steps.
+    pub fn new() -> Self {
+        SolveOptions {
+            save_at: None,
+            dense: false,
+            max_steps: None,
+        }
+    }
+
+    /// Save the solution only at the given times, interpolating between the steps with the
+    /// dense output of the stepper.
+    ///
+    /// The times have to lie between the start and the end of the integration, ordered in the
+    /// direction of the integration.
+    pub fn with_save_at<I>(mut self, times: I) -> Self
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        self.save_at = Some(times.into_iter().collect());
+        self
+    }
+
+    /// Set whether the derivatives are saved along with the states, so that `Solution::at`
+    /// interpolates by cubic Hermite instead of linear interpolation. Each derivative takes an
+    /// evaluation of the right-hand side beyond those of the stepper.
+    pub fn with_dense(mut self, dense: bool) -> Self {
+        self.dense = dense;
+        self
+    }
+
+    /// Limit the number of steps taken to reach the end of the integration.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn save_at(&self) -> Option<&[f64]> {
+        self.save_at.as_ref().map(|times| &times[..])
+    }
+
+    pub fn is_dense(&self) -> bool {
+        self.dense
+    }
+
+    pub fn max_steps(&self) -> Option<usize> {
+        self.max_steps
+    }
+}
+
+/// The solution of an initial value problem, as returned by `solve`.
+#[derive(Clone, Debug)]
+pub struct Solution<S> {
+    pub(crate) times: Vec<f64>,
+    pub(crate) states: Vec<S>,
+    pub(crate) derivatives: Option<Vec<S>>,
+}
+
+impl<S> Solution<S> {
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// The derivatives at the saved times, if `SolveOptions::with_dense` was set.
+    pub fn derivatives(&self) -> Option<&[S]> {
+        self.derivatives
+            .as_ref()
+            .map(|derivatives| &derivatives[..])
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Iterate over the pairs of saved times and states.
+    pub fn iter(&self) -> iter::Zip<iter::Copied<slice::Iter<'_, f64>>, slice::Iter<'_, S>> {
+        self.times.iter().copied().zip(self.states.iter())
+    }
+
+    /// Return the saved times and states.
+    pub fn into_inner(self) -> (Vec<f64>, Vec<S>) {
+        (self.times, self.states)
+    }
+
+    /// The saved states as the rows of an `Array2<f64>`.
+    ///
+    /// Panics if the states don't all have the same length.
+    pub fn to_array(&self) -> Array2<f64>
+    where
+        ArrayRecorder: Observer<S>,
+    {
+        let mut recorder = ArrayRecorder::new();
+        for (step, (t, state)) in self.iter().enumerate() {
+            recorder.observe(t, state, step);
+        }
+        recorder.into_inner().1
+    }
+
+    /// The state at time `t`, interpolated between the saved states.
+    ///
+    /// Panics if the solution is empty or `t` lies outside of its time span.
+    pub fn at(&self, t: f64) -> S
+    where
+        S: Componentwise + Clone,
+    {
+        assert!(
+            !self.is_empty(),
+            "Solution: cannot interpolate an empty solution"
+        );
+        let mut out = self.states[0].clone();
+        self.at_into(t, &mut out);
+        out
+    }
+
+    /// Write the state at time `t`, interpolated between the saved states, into `out`.
+    ///
+    /// The interpolation is cubic Hermite if the derivatives were saved, and linear otherwise.
+    ///
+    /// Panics if the solution is empty or `t` lies outside of its time span.
+    pub fn at_into(&self, t: f64, out: &mut S)
+    where
+        S: Componentwise,
+    {
+        assert!(
+            !self.is_empty(),
+            "Solution: cannot interpolate an empty solution"
+        );
+
+        let n = self.len();
+        let forward = self.times[n - 1] >= self.times[0];
+        let (lower, upper) = if forward {
+            (self.times[0], self.times[n - 1])
+        } else {
+            (self.times[n - 1], self.times[0])
+        };
+        assert!(
+            t >= lower && t <= upper,
+            "Solution: t = {} lies outside of the time span [{}, {}]",
+            t,
+            lower,
+            upper
+        );
+
+        if n == 1 {
+            S::linear_combination(out, &[(1.0, &self.states[0])]);
+            return;
+        }
+
+        // The interval [t0, t1] containing t, in the direction of the integration.
+        let i = self
+            .times
+            .partition_point(|&s| if forward { s <= t } else { s >= t })
+            .max(1)
+            .min(n - 1);
+        let (t0, t1) = (self.times[i - 1], self.times[i]);
+        let (x0, x1) = (&self.states[i - 1], &self.states[i]);
+
+        let dt = t1 - t0;
+        if dt == 0.0 {
+            S::linear_combination(out, &[(1.0, x1)]);
+            return;
+        }
+        let theta = (t - t0) / dt;
+
+        match &self.derivatives {
+            Some(derivatives) => {
+                let (f0, f1) = (&derivatives[i - 1], &derivatives[i]);
+                let (h00, h10, h01, h11) = hermite_basis(theta);
+                S::linear_combination(out, &[(h00, x0), (h01, x1), (dt * h10, f0), (dt * h11, f1)]);
+            }
+            None => S::linear_combination(out, &[(1.0 - theta, x0), (theta, x1)]),
+        }
+    }
+
+    fn push<Sy>(&mut self, system: &mut Sy, t: f64, state: &S) -> Result<(), Error>
+    where
+        S: Clone,
+        Sy: NonautonomousOde<State = S>,
+    {
+        if let Some(derivatives) = &mut self.derivatives {
+            let mut derivative = state.clone();
+            system.try_differentiate_at_into(t, state, &mut derivative)?;
+            derivatives.push(derivative);
+        }
+        self.times.push(t);
+        self.states.push(state.clone());
+        Ok(())
+    }
+}
+
+impl<'a, S> IntoIterator for &'a Solution<S> {
+    type Item = (f64, &'a S);
+    type IntoIter = iter::Zip<iter::Copied<slice::Iter<'a, f64>>, slice::Iter<'a, S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Integrate `system` from `state` at the current time of `stepper` up to the time `t_end`, which
+/// is hit exactly by shortening the final step, and return the saved solution.
+///
+/// Without `SolveOptions::with_save_at`, the initial state and the state after every step are
+/// saved. Otherwise, the states at the given times are taken from the dense output of the
+/// stepper, which does not include the effect of `Ode::update_state` at the end of a step. With
+/// `SolveOptions::with_dense`, every saved derivative takes an additional evaluation of the
+/// right-hand side, which is not counted in the stats of the stepper.
+///
+/// Panics if the times to save at are not ordered or lie outside of the integration, or if the
+/// maximum number of steps is exceeded.
+pub fn solve<St, Sy>(
+    stepper: &mut St,
+    system: &mut Sy,
+    state: St::State,
+    t_end: f64,
+    options: &SolveOptions,
+) -> Solution<St::State>
+where
+    St: DenseOutput + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    let step = |stepper: &mut St, system: &mut Sy, state: &mut St::State| {
+        stepper.do_step(system, state);
+        Ok(())
+    };
+    solve_by(stepper, system, state, t_end, options, step)
+        .unwrap_or_else(|error| panic!("solve: {}", error))
+}
+
+/// Like `solve`, but doing the steps with `Stepper::try_do_step` and returning the first failure.
+///
+/// Fails with `Error::MaxStepsExceeded` if `t_end` is not reached within the maximum number of
+/// steps.
+pub fn try_solve<St, Sy>(
+    stepper: &mut St,
+    system: &mut Sy,
+    state: St::State,
+    t_end: f64,
+    options: &SolveOptions,
+) -> Result<Solution<St::State>, Error>
+where
+    St: DenseOutput + ?Sized,
+    St::State: Componentwise,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    let step = |stepper: &mut St, system: &mut Sy, state: &mut St::State| {
+        stepper.try_do_step(system, state)
+    };
+    solve_by(stepper, system, state, t_end, options, step)
+}
+
+fn solve_by<St, Sy, F>(
+    stepper: &mut St,
+    system: &mut Sy,
+    mut state: St::State,
+    t_end: f64,
+    options: &SolveOptions,
+    mut step: F,
+) -> Result<Solution<St::State>, Error>
+where
+    St: DenseOutput + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+    F: FnMut(&mut St, &mut Sy, &mut St::State) -> Result<(), Error>,
+{
+    let t_start = stepper.time();
+    let direction = (t_end - t_start).signum();
+
+    if let Some(times) = &options.save_at {
+        let mut previous = t_start;
+        for &t in times.iter().chain(iter::once(&t_end)) {
+            assert!(
+                (t - previous) * direction >= 0.0,
+                "solve: the times to save at must be ordered from t = {} to t = {}",
+                t_start,
+                t_end
+            );
+            previous = t;
+        }
+    }
+
+    let mut solution = Solution {
+        times: Vec::new(),
+        states: Vec::new(),
+        derivatives: if options.dense {
+            Some(Vec::new())
+        } else {
+            None
+        },
+    };
+
+    // The position of the next time to save at.
+    let mut next = 0;
+    let mut save =
+        |solution: &mut Solution<St::State>, stepper: &St, system: &mut Sy, state: &St::State| {
+            let t = stepper.time();
+            match &options.save_at {
+                None => solution.push(system, t, state),
+                Some(times) => {
+                    while next < times.len() && (times[next] - t) * direction <= 0.0 {
+                        if times[next] == t {
+                            solution.push(system, t, state)?;
+                        } else {
+                            let mut interpolated = state.clone();
+                            stepper.interpolate_at(times[next], &mut interpolated);
+                            solution.push(system, times[next], &interpolated)?;
+                        }
+                        next += 1;
+                    }
+                    Ok(())
+                }
+            }
+        };
+
+    save(&mut solution, stepper, system, &state)?;
+
+    // Step towards t_end, which is before t_start when integrating backward in time.
+    stepper.set_timestep(stepper.timestep().abs().copysign(t_end - t_start));
+
+    let mut count = 0usize;
+    loop {
+        let remaining = t_end - stepper.time();
+        if remaining.abs() <= count.max(1) as f64 * ROUNDOFF * t_start.abs().max(t_end.abs()) {
+            break;
+        }
+
+        if let Some(max_steps) = options.max_steps {
+            if count >= max_steps {
+                return Err(Error::MaxStepsExceeded { steps: max_steps });
+            }
+        }
+
+        if stepper.timestep().abs() >= remaining.abs() {
+            // Shorten the final step, like `Stepper::do_step_with_timestep`.
+            let saved = stepper.timestep();
+            stepper.set_timestep(remaining);
+            let result = step(stepper, system, &mut state);
+            stepper.set_timestep(saved);
+            result?;
+        } else {
+            step(stepper, system, &mut state)?;
+        }
+        count += 1;
+
+        let remaining = t_end - stepper.time();
+        if remaining.abs() <= count as f64 * ROUNDOFF * t_start.abs().max(t_end.abs()) {
+            stepper.set_time(t_end);
+        }
+
+        save(&mut solution, stepper, system, &state)?;
+    }
+
+    Ok(solution)
+}
@@ -0,0 +1,48 @@
+/// Counters of the work done by a stepper, accumulated over all its steps since its creation or
+/// the last `reset_stats`.
+///
+/// Events are located on the dense output of the steps, without any further steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    /// The evaluations of the right-hand side, including those of a `FiniteDifference`
+    /// approximating the Jacobian. `Bdf` counts the evaluations of the residual.
+    pub rhs_evaluations: usize,
+    pub accepted_steps: usize,
+    /// The steps rejected by the error control of an adaptive stepper.
+    pub rejected_steps: usize,
+    pub jacobian_evaluations: usize,
+    pub lu_factorizations: usize,
+    pub newton_iterations: usize,
+    /// The smallest magnitude of the step size of the accepted steps, infinite before the first.
+    pub min_timestep: f64,
+    /// The largest magnitude of the step size of the accepted steps, `0` before the first.
+    pub max_timestep: f64,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats {
+            rhs_evaluations: 0,
+            accepted_steps: 0,
+            rejected_steps: 0,
+            jacobian_evaluations: 0,
+            lu_factorizations: 0,
+            newton_iterations: 0,
+            min_timestep: f64::INFINITY,
+            max_timestep: 0.0,
+        }
+    }
+
+    /// Count an accepted step of size `dt`.
+    pub(crate) fn accept(&mut self, dt: f64) {
+        self.accepted_steps += 1;
+        self.min_timestep = self.min_timestep.min(dt.abs());
+        self.max_timestep = self.max_timestep.max(dt.abs());
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats::new()
+    }
+}
@@ -1,30 +1,100 @@
-use ndarray::{ArrayBase, Data, Dimension};
+use ndarray::{Array1, ArrayBase, Data, Dimension};
+use std::convert::Infallible;
 
-use crate::ode::Ode;
+use crate::error::{self, Error};
+use crate::event::{self, Event, EventOccurrence};
+use crate::hamiltonian::Hamiltonian;
+use crate::jacobian::Jacobian;
+use crate::mass_matrix::MassMatrix;
+use crate::observer::Observer;
+use crate::ode::NonautonomousOde;
+use crate::residual::ResidualJacobian;
+use crate::sde::Sde;
+use crate::second_order::SecondOrderOde;
+use crate::stats::Stats;
+use crate::steps::Steps;
+use crate::tolerance::Componentwise;
 
+pub(crate) mod adaptive;
+mod backward_euler;
+mod bdf;
+mod butcher_tableau;
+pub(crate) mod dormand_prince_5;
 mod euler;
+mod euler_maruyama;
+mod explicit_runge_kutta;
 mod heun;
+mod implicit_midpoint;
+mod milstein;
+mod newton;
+mod nystrom4;
+mod radau5;
+mod rkn64;
 mod runge_kutta_4;
+mod stochastic_runge_kutta;
+mod symplectic;
+mod trapezoidal;
 
+pub use backward_euler::BackwardEuler;
+pub use bdf::Bdf;
+pub use butcher_tableau::ButcherTableau;
+pub use dormand_prince_5::DormandPrince5;
 pub use euler::Euler;
+pub use euler_maruyama::EulerMaruyama;
+pub use explicit_runge_kutta::ExplicitRungeKutta;
 pub use heun::Heun;
+pub use implicit_midpoint::ImplicitMidpoint;
+pub use milstein::Milstein;
+pub use newton::NewtonError;
+pub use nystrom4::Nystrom4;
+pub use radau5::Radau5;
+pub use rkn64::Rkn64;
 pub use runge_kutta_4::RungeKutta4;
+pub use stochastic_runge_kutta::{SrkMethod, StochasticRungeKutta};
+pub use symplectic::{Symplectic, SymplecticMethod};
+pub use trapezoidal::Trapezoidal;
+
+/// Remainders of `integrate_time_exact` up to this fraction of the integrated duration per step
+/// taken are considered to be accumulated roundoff, and no shortened step is taken for them.
+pub(crate) const ROUNDOFF: f64 = 4.0 * f64::EPSILON;
 
 /// A trait defining the interface of an integration method.
+///
+/// A stepper keeps track of the current time of the integration, starting at `0`. Each step
+/// advances it by the step size, and the right-hand side of the system is evaluated at the
+/// respective stage times.
+///
+/// A negative step size integrates backward in time. The `integrate_time*` methods integrate
+/// backward for a negative duration `t`, and change the sign of the step size to match it.
 pub trait Stepper {
     type State: Clone;
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = Self::State>;
+        Sy: NonautonomousOde<State = Self::State>;
 
     fn timestep(&self) -> f64;
 
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps. Adaptive steppers use it for their next attempt.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
     fn integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> f64
     where
-        Sy: Ode<State = Self::State>,
+        Sy: NonautonomousOde<State = Self::State>,
     {
-        let mut tacc = 0f64;;
+        let mut tacc = 0f64;
 
         let dt = self.timestep();
 
@@ -36,6 +106,8 @@ pub trait Stepper {
         tacc
     }
 
+    /// Integrate over a duration of at most `t`, returning the time integrated over and the
+    /// number of steps taken.
     fn integrate_time<Sy>(
         &mut self,
         system: &mut Sy,
@@ -43,20 +115,933 @@ pub trait Stepper {
         t: f64,
     ) -> (f64, usize)
     where
-        Sy: Ode<State = Self::State>,
+        Sy: NonautonomousOde<State = Self::State>,
     {
-        let mut tacc = 0f64;;
-        let mut count = 0;
+        self.integrate_time_with(system, state, t, &mut ())
+    }
+
+    /// Do a single step like `do_step`, evaluating the right-hand side by
+    /// `try_differentiate_at_into`.
+    ///
+    /// Fails with the error of the right-hand side, if the new state is not finite, or if an
+    /// adaptive stepper cannot find an acceptable step size. On failure, `state` and the time are
+    /// left unchanged.
+    fn try_do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        error::guarded_step(self, system, state, |stepper, system, state| {
+            stepper.do_step(system, state);
+            Ok(())
+        })
+    }
+
+    /// Do `n` steps like `integrate_n_steps` with `try_do_step`, stopping at the first failure.
+    fn try_integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        let mut tacc = 0f64;
 
         let dt = self.timestep();
 
-        // Ensure t is not exceeded
-        while (tacc + dt) <= t {
+        for _ in 0..n {
+            self.try_do_step(system, state)?;
+            tacc += dt;
+        }
+        Ok(tacc)
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time` with `try_do_step`,
+    /// stopping at the first failure.
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        try_fixed_steps(dt, t, |_| self.try_do_step(system, state))
+    }
+
+    /// Step through the integration of `system` from `state` with an iterator over the times and
+    /// copies of the states, see `Steps`.
+    fn iter<'a, Sy>(&'a mut self, system: &'a mut Sy, state: Self::State) -> Steps<'a, Self, Sy>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        Steps {
+            stepper: self,
+            system,
+            state,
+            started: false,
+        }
+    }
+
+    /// Do a single step with the step size `dt`, restoring the stepper's own step size afterwards.
+    /// Adaptive steppers may still take a smaller step.
+    fn do_step_with_timestep<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, dt: f64)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        let saved = self.timestep();
+        self.set_timestep(dt);
+        self.do_step(system, state);
+        self.set_timestep(saved);
+    }
+
+    /// Integrate over exactly the duration `t`, finishing with a shortened step if `t` is not a
+    /// multiple of the step size.
+    ///
+    /// Returns the time integrated over, which is `t`, the number of steps taken including the
+    /// shortened one, and the length of the shortened step, or `0` if none was needed.
+    fn integrate_time_exact<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize, f64)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        let t_start = self.time();
+        let (tacc, mut count) = self.integrate_time(system, state, t);
+
+        let mut remainder = t - tacc;
+        if remainder.abs() > count.max(1) as f64 * ROUNDOFF * t.abs() {
+            self.do_step_with_timestep(system, state, remainder);
+            count += 1;
+        } else {
+            remainder = 0.0;
+        }
+        self.set_time(t_start + t);
+        (t, count, remainder)
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, passing the initial state and the state after
+    /// every step to `observer`.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let mut tacc = 0f64;
+
+        let dt = self.timestep();
+
+        observer.observe(self.time(), state, 0);
+        for step in 1..=n {
             self.do_step(system, state);
             tacc += dt;
+            observer.observe(self.time(), state, step);
+        }
+        tacc
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time`, passing the initial state
+    /// and the state after every step to `observer`.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        observer.observe(self.time(), state, 0);
+        fixed_steps(dt, t, |count| {
+            self.do_step(system, state);
+            observer.observe(self.time(), state, count);
+        })
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, locating the zero crossings of `events`.
+    ///
+    /// Returns the time integrated over and the located events in the order they occurred. The
+    /// integration stops early at the first terminal event, leaving `state` and the time of the
+    /// stepper at that event.
+    fn integrate_n_steps_with_events<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        events: &mut [Event<'_, Self::State>],
+    ) -> (f64, Vec<EventOccurrence<Self::State>>)
+    where
+        Self: DenseOutput,
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        let t_start = self.time();
+        let mut values = event::evaluate_all(events, t_start, state);
+        let mut occurrences = Vec::new();
+
+        for _ in 0..n {
+            if event::step_with_events(self, system, state, events, &mut values, &mut occurrences) {
+                break;
+            }
+        }
+        (self.time() - t_start, occurrences)
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time`, locating the zero
+    /// crossings of `events`.
+    ///
+    /// Returns the time integrated over and the located events in the order they occurred. The
+    /// integration stops early at the first terminal event, leaving `state` and the time of the
+    /// stepper at that event.
+    fn integrate_time_with_events<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        events: &mut [Event<'_, Self::State>],
+    ) -> (f64, Vec<EventOccurrence<Self::State>>)
+    where
+        Self: DenseOutput,
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        let t_start = self.time();
+        let mut values = event::evaluate_all(events, t_start, state);
+        let mut occurrences = Vec::new();
+
+        let mut tacc = 0f64;
+
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        // Ensure t is not exceeded
+        while (tacc + self.timestep()).abs() <= t.abs() {
+            if event::step_with_events(self, system, state, events, &mut values, &mut occurrences) {
+                return (self.time() - t_start, occurrences);
+            }
+            tacc = self.time() - t_start;
+        }
+        (tacc, occurrences)
+    }
+}
+
+/// Steppers providing a continuous interpolant of the solution over their last step.
+///
+/// The interpolant covers the last call to `do_step` and goes from `time() - last_timestep()` to
+/// `time()` as long as the time has not been changed with `set_time` since. Events are located on
+/// it, so it stays valid after non-terminal events. Its end point is the state before
+/// `Ode::update_state` was applied. Before the first step, there is nothing to interpolate.
+pub trait DenseOutput: Stepper {
+    /// The step size of the last step.
+    fn last_timestep(&self) -> f64;
+
+    /// Write the interpolated state at the fraction `theta` of the last step into `out`, where
+    /// `theta = 0` is its beginning and `theta = 1` its end.
+    fn interpolate(&self, theta: f64, out: &mut Self::State);
+
+    /// Write the interpolated state at time `t` into `out`.
+    fn interpolate_at(&self, t: f64, out: &mut Self::State) {
+        let theta = 1.0 + (t - self.time()) / self.last_timestep();
+        self.interpolate(theta, out);
+    }
+}
+
+/// The cubic Hermite basis functions `(h00, h10, h01, h11)` on the unit interval.
+pub(crate) fn hermite_basis(theta: f64) -> (f64, f64, f64, f64) {
+    let theta2 = theta * theta;
+    let theta3 = theta2 * theta;
+
+    (
+        2.0 * theta3 - 3.0 * theta2 + 1.0,
+        theta3 - 2.0 * theta2 + theta,
+        -2.0 * theta3 + 3.0 * theta2,
+        theta3 - theta2,
+    )
+}
+
+/// A trait defining the interface of an implicit integration method.
+///
+/// Implicit methods solve for the next state with a Newton iteration, which needs the Jacobian of
+/// the system. Systems without an analytic Jacobian can be wrapped in a `FiniteDifference`. The
+/// right-hand side is evaluated by `try_differentiate_at_into`. If it or the Newton iteration
+/// fails, the error is returned and the state is left unchanged.
+///
+/// As for `Stepper`, a negative step size or duration integrates backward in time.
+pub trait ImplicitStepper {
+    type State: Clone;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = Self::State>;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+    {
+        let mut tacc = 0f64;
+
+        let dt = self.timestep();
+
+        for _ in 0..n {
+            self.do_step(system, state)?;
+            tacc += dt;
+        }
+        Ok(tacc)
+    }
+
+    /// Integrate over a duration of at most `t`, returning the time integrated over and the
+    /// number of steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+    {
+        self.integrate_time_with(system, state, t, &mut ())
+    }
+
+    /// Do a single step with the step size `dt`, restoring the stepper's own step size afterwards.
+    fn do_step_with_timestep<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        dt: f64,
+    ) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+    {
+        let saved = self.timestep();
+        self.set_timestep(dt);
+        let result = self.do_step(system, state);
+        self.set_timestep(saved);
+        result
+    }
+
+    /// Integrate over exactly the duration `t`, finishing with a shortened step if `t` is not a
+    /// multiple of the step size.
+    ///
+    /// Returns the time integrated over, which is `t`, the number of steps taken including the
+    /// shortened one, and the length of the shortened step, or `0` if none was needed.
+    fn integrate_time_exact<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize, f64), Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+    {
+        let t_start = self.time();
+        let (tacc, mut count) = self.integrate_time(system, state, t)?;
+
+        let mut remainder = t - tacc;
+        if remainder.abs() > count.max(1) as f64 * ROUNDOFF * t.abs() {
+            self.do_step_with_timestep(system, state, remainder)?;
             count += 1;
+        } else {
+            remainder = 0.0;
+        }
+        self.set_time(t_start + t);
+        Ok((t, count, remainder))
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, passing the initial state and the state after
+    /// every step to `observer`.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let mut tacc = 0f64;
+
+        let dt = self.timestep();
+
+        observer.observe(self.time(), state, 0);
+        for step in 1..=n {
+            self.do_step(system, state)?;
+            tacc += dt;
+            observer.observe(self.time(), state, step);
+        }
+        Ok(tacc)
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time`, passing the initial state
+    /// and the state after every step to `observer`.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: Jacobian<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        observer.observe(self.time(), state, 0);
+        try_fixed_steps(dt, t, |count| {
+            self.do_step(system, state)?;
+            observer.observe(self.time(), state, count);
+            Ok(())
+        })
+    }
+}
+
+/// Implicit steppers providing a continuous interpolant of the solution over their last step,
+/// like `DenseOutput` for the explicit ones.
+///
+/// The interpolant is the collocation polynomial of the method, built from the derivatives of its
+/// last Newton iteration, and covers the last successful call to `do_step`.
+pub trait ImplicitDenseOutput: ImplicitStepper {
+    /// The step size of the last step.
+    fn last_timestep(&self) -> f64;
+
+    /// Write the interpolated state at the fraction `theta` of the last step into `out`, where
+    /// `theta = 0` is its beginning and `theta = 1` its end.
+    fn interpolate(&self, theta: f64, out: &mut Self::State);
+
+    /// Write the interpolated state at time `t` into `out`.
+    fn interpolate_at(&self, t: f64, out: &mut Self::State) {
+        let theta = 1.0 + (t - self.time()) / self.last_timestep();
+        self.interpolate(theta, out);
+    }
+}
+
+/// A trait defining the interface of an integration method for stochastic differential
+/// equations.
+///
+/// The Wiener increments are drawn from the random number generator owned by the stepper, so
+/// that seeding it reproduces a run. The increments of a step of size `dt` are normally
+/// distributed with variance `|dt|`.
+pub trait SdeStepper {
+    type State: Clone;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = Self::State>;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> f64
+    where
+        Sy: Sde<State = Self::State>,
+    {
+        self.integrate_n_steps_with(system, state, n, &mut ())
+    }
+
+    /// Integrate over a duration of at most `t`, returning the time integrated over and the
+    /// number of steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: Sde<State = Self::State>,
+    {
+        self.integrate_time_with(system, state, t, &mut ())
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, passing the initial state and the state after
+    /// every step to `observer`.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: Sde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let mut tacc = 0f64;
+
+        let dt = self.timestep();
+
+        observer.observe(self.time(), state, 0);
+        for step in 1..=n {
+            self.do_step(system, state);
+            tacc += dt;
+            observer.observe(self.time(), state, step);
+        }
+        tacc
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time`, passing the initial state
+    /// and the state after every step to `observer`.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: Sde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        observer.observe(self.time(), state, 0);
+        fixed_steps(dt, t, |count| {
+            self.do_step(system, state);
+            observer.observe(self.time(), state, count);
+        })
+    }
+}
+
+/// A trait defining the interface of an integration method for differential-algebraic
+/// equations in mass matrix form, M(t) dx/dt = f(t, x).
+///
+/// Like the implicit steppers, these solve their stage equations with a Newton iteration using
+/// the Jacobian of the right-hand side, and report failures as `Error`.
+pub trait MassMatrixStepper {
+    type State: Clone;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = Self::State>;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: MassMatrix + Jacobian<State = Self::State>,
+    {
+        self.integrate_n_steps_with(system, state, n, &mut ())
+    }
+
+    /// Integrate over a duration of `t`, returning the time integrated over and the number of
+    /// steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = Self::State>,
+    {
+        self.integrate_time_with(system, state, t, &mut ())
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, passing the initial state and the state after
+    /// every step to `observer`.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error>
+    where
+        Sy: MassMatrix + Jacobian<State = Self::State>,
+        O: Observer<Self::State>;
+
+    /// Integrate over a duration of `t` like `integrate_time`, passing the initial state and
+    /// the state after every step to `observer`.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = Self::State>,
+        O: Observer<Self::State>;
+}
+
+/// A trait defining the interface of an integration method for fully implicit
+/// differential-algebraic equations F(t, x, dx/dt) = 0, which updates the state and its
+/// derivative in place.
+///
+/// Like the implicit steppers, these solve their equations with a Newton iteration using the
+/// iteration matrix of the residual, and report failures as `Error`. The state and its
+/// derivative have to be consistent at the start of the integration.
+pub trait ResidualStepper {
+    fn do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+    ) -> Result<(), Error>
+    where
+        Sy: ResidualJacobian;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        self.integrate_n_steps_with(system, state, derivative, n, &mut ())
+    }
+
+    /// Integrate over a duration of `t`, returning the time integrated over and the number of
+    /// steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        self.integrate_time_with(system, state, derivative, t, &mut ())
+    }
+
+    /// Do `n` steps like `integrate_n_steps`, passing the initial state and the state after
+    /// every step to `observer`.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        n: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error>
+    where
+        Sy: ResidualJacobian,
+        O: Observer<Array1<f64>>;
+
+    /// Integrate over a duration of `t` like `integrate_time`, passing the initial state and
+    /// the state after every step to `observer`.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        t: f64,
+        observer: &mut O,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: ResidualJacobian,
+        O: Observer<Array1<f64>>;
+}
+
+/// A trait defining the interface of an integration method for separable Hamiltonian systems,
+/// which updates the position and the momentum in place.
+pub trait SymplecticStepper {
+    type State: Clone;
+
+    fn do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        momentum: &mut Self::State,
+    ) where
+        Sy: Hamiltonian<State = Self::State>;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        momentum: &mut Self::State,
+        n: usize,
+    ) -> f64
+    where
+        Sy: Hamiltonian<State = Self::State>,
+    {
+        let mut tacc = 0f64;
+
+        let dt = self.timestep();
+        for _ in 0..n {
+            self.do_step(system, position, momentum);
+            tacc += dt;
         }
-        (tacc, count)
+        tacc
+    }
+
+    /// Integrate over a duration of at most `t`, returning the time integrated over and the
+    /// number of steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        momentum: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: Hamiltonian<State = Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        fixed_steps(dt, t, |_| self.do_step(system, position, momentum))
+    }
+}
+
+/// A trait defining the interface of a Runge–Kutta–Nyström method for second-order ODEs, which
+/// updates the position and the velocity in place.
+pub trait RknStepper {
+    type State: Clone;
+
+    fn do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        velocity: &mut Self::State,
+    ) where
+        Sy: SecondOrderOde<State = Self::State>;
+
+    fn timestep(&self) -> f64;
+
+    /// The current time of the integration.
+    fn time(&self) -> f64;
+
+    /// Set the current time of the integration, e.g. to start at some `t0 != 0`.
+    fn set_time(&mut self, t: f64);
+
+    /// Set the step size of the following steps. Adaptive steppers use it for their next attempt.
+    fn set_timestep(&mut self, dt: f64);
+
+    /// The work done since the creation of the stepper or the last `reset_stats`.
+    fn stats(&self) -> &Stats;
+
+    fn reset_stats(&mut self);
+
+    /// Do `n` steps, returning the time integrated over.
+    fn integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        velocity: &mut Self::State,
+        n: usize,
+    ) -> f64
+    where
+        Sy: SecondOrderOde<State = Self::State>,
+    {
+        let t_start = self.time();
+        for _ in 0..n {
+            self.do_step(system, position, velocity);
+        }
+        self.time() - t_start
+    }
+
+    /// Integrate over a duration of at most `t`, returning the time integrated over and the
+    /// number of steps taken.
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        velocity: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: SecondOrderOde<State = Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        fixed_steps(dt, t, |_| self.do_step(system, position, velocity))
+    }
+
+    /// Do a single step like `do_step`, failing instead of panicking if an adaptive stepper
+    /// cannot find an acceptable step size. On failure, the position, the velocity, the time, and
+    /// the step size are left unchanged.
+    fn try_do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        velocity: &mut Self::State,
+    ) -> Result<(), Error>
+    where
+        Sy: SecondOrderOde<State = Self::State>,
+    {
+        self.do_step(system, position, velocity);
+        Ok(())
+    }
+
+    /// Integrate over a duration of at most `t` like `integrate_time` with `try_do_step`,
+    /// stopping at the first failure.
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut Self::State,
+        velocity: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: SecondOrderOde<State = Self::State>,
+    {
+        let dt = fixed_timestep(self.timestep(), t);
+        self.set_timestep(dt);
+
+        try_fixed_steps(dt, t, |_| self.try_do_step(system, position, velocity))
+    }
+}
+
+/// The step size `dt` of fixed steps towards the end of a duration `t`, which is negative when
+/// integrating backward in time.
+pub(crate) fn fixed_timestep(dt: f64, t: f64) -> f64 {
+    dt.abs().copysign(t)
+}
+
+/// Do fixed steps of size `dt` by `step`, which is passed the number of the step, for a duration
+/// of at most `t`, stopping at the first failure.
+///
+/// Returns the time integrated over and the number of steps taken.
+pub(crate) fn try_fixed_steps<E, F>(dt: f64, t: f64, mut step: F) -> Result<(f64, usize), E>
+where
+    F: FnMut(usize) -> Result<(), E>,
+{
+    let mut tacc = 0f64;
+    let mut count = 0;
+
+    // Ensure t is not exceeded
+    while (tacc + dt).abs() <= t.abs() {
+        count += 1;
+        step(count)?;
+        tacc += dt;
+    }
+    Ok((tacc, count))
+}
+
+/// Do fixed steps like `try_fixed_steps` with an infallible `step`.
+pub(crate) fn fixed_steps<F>(dt: f64, t: f64, mut step: F) -> (f64, usize)
+where
+    F: FnMut(usize),
+{
+    let result: Result<_, Infallible> = try_fixed_steps(dt, t, |count| {
+        step(count);
+        Ok(())
+    });
+    match result {
+        Ok(result) => result,
+        Err(never) => match never {},
     }
 }
 
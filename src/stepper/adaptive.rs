@@ -0,0 +1,198 @@
+use crate::controller::{StepSize, StepSizeController};
+use crate::error::Error;
+use crate::event::{self, Event, EventOccurrence};
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
+
+use super::{DenseOutput, ROUNDOFF};
+
+/// The parts of an adaptive stepper involved in the control of its step size.
+pub(crate) struct StepControl<'a, C> {
+    pub(crate) t: &'a mut f64,
+    /// The step size of the next attempt.
+    pub(crate) dt: &'a mut f64,
+    /// The step size of the last accepted step.
+    pub(crate) last_dt: &'a mut f64,
+    pub(crate) controller: &'a mut C,
+    pub(crate) stats: &'a mut Stats,
+}
+
+/// The step size control shared by the adaptive steppers, which attempt steps until one is
+/// accepted by their step size controller.
+pub(crate) trait Adaptive {
+    type Controller: StepSizeController;
+
+    fn step_control(&mut self) -> StepControl<'_, Self::Controller>;
+
+    /// Adapt the step size to the scaled `error` of the last attempt, whose local error behaves
+    /// like `dt^k`, returning whether the attempt is accepted. An accepted attempt advances the
+    /// time.
+    ///
+    /// Fails if the step size underflows after repeated rejections.
+    fn adapt(&mut self, error: f64, k: u32) -> Result<bool, Error> {
+        let control = self.step_control();
+        let dt = *control.dt;
+        match control.controller.adapt(dt, error, k) {
+            StepSize::Accepted(next) => {
+                control.stats.accept(dt);
+                *control.last_dt = dt;
+                *control.t += dt;
+                *control.dt = next;
+                Ok(true)
+            }
+            StepSize::Rejected(next) => self.retry(next).map(|()| false),
+        }
+    }
+
+    /// Reject the attempted step and retry it with the smaller step size `dt`.
+    ///
+    /// Fails if `dt` does not shrink, or if it is lost in the roundoff of the time, so that the
+    /// time could not advance. The threshold is relative to the time, so that problems on fast
+    /// time scales can take steps far below `f64::EPSILON` near `t = 0`.
+    fn retry(&mut self, dt: f64) -> Result<(), Error> {
+        let control = self.step_control();
+        control.stats.rejected_steps += 1;
+
+        let t = *control.t;
+        if dt.abs() <= ROUNDOFF * t.abs() || dt == 0.0 || dt.abs() >= control.dt.abs() {
+            return Err(Error::StepSizeUnderflow {
+                time: t,
+                timestep: dt,
+            });
+        }
+        *control.dt = dt;
+        Ok(())
+    }
+
+    /// Move to the time `t` at the end of a truncated step, which lies within the roundoff of the
+    /// time reached.
+    fn end_at(&mut self, t: f64) {
+        *self.step_control().t = t;
+    }
+}
+
+/// Integrate over exactly the duration `t` by the accepted steps of `step`, truncating the last
+/// step, and stopping at the first failure. `observe` is passed the time, the state, and the
+/// number of steps taken, before the first step and after every step.
+///
+/// Returns the duration integrated over and the number of steps.
+pub(crate) fn integrate_until<St, X, F, O>(
+    stepper: &mut St,
+    state: &mut X,
+    t: f64,
+    mut step: F,
+    observe: O,
+) -> Result<(f64, usize), Error>
+where
+    St: Adaptive + ?Sized,
+    X: ?Sized,
+    F: FnMut(&mut St, &mut X) -> Result<(), Error>,
+    O: FnMut(f64, &X, usize),
+{
+    integrate_until_stop(
+        stepper,
+        state,
+        t,
+        |stepper, state| step(stepper, state).map(|()| false),
+        observe,
+    )
+}
+
+/// Integrate like `integrate_until`, but stop early once `step` returns `true`, e.g. at a terminal
+/// event, leaving the time of the stepper where `step` left it.
+pub(crate) fn integrate_until_stop<St, X, F, O>(
+    stepper: &mut St,
+    state: &mut X,
+    t: f64,
+    mut step: F,
+    mut observe: O,
+) -> Result<(f64, usize), Error>
+where
+    St: Adaptive + ?Sized,
+    X: ?Sized,
+    F: FnMut(&mut St, &mut X) -> Result<bool, Error>,
+    O: FnMut(f64, &X, usize),
+{
+    let mut tacc = 0f64;
+    let mut count = 0;
+
+    let control = stepper.step_control();
+    let t_start = *control.t;
+    let t_end = t_start + t;
+
+    // Step towards t, which is negative when integrating backward in time.
+    *control.dt = control.dt.abs().copysign(t);
+
+    observe(*control.t, state, 0);
+
+    while tacc.abs() < t.abs() {
+        let remaining = t - tacc;
+        let proposed = *stepper.step_control().dt;
+
+        // Truncate the last step so that t is hit exactly.
+        let truncated = proposed.abs() >= remaining.abs();
+        if truncated {
+            *stepper.step_control().dt = remaining;
+        }
+
+        let stop = step(stepper, state)?;
+        count += 1;
+
+        let last_dt = *stepper.step_control().last_dt;
+        let completed = truncated && last_dt == remaining;
+        if completed {
+            *stepper.step_control().dt = proposed;
+        }
+        if stop {
+            tacc = *stepper.step_control().t - t_start;
+            observe(*stepper.step_control().t, state, count);
+            break;
+        }
+        if completed {
+            stepper.end_at(t_end);
+            tacc = t;
+        } else {
+            tacc += last_dt;
+        }
+        observe(*stepper.step_control().t, state, count);
+    }
+    Ok((tacc, count))
+}
+
+/// Integrate like `integrate_until`, locating the zero crossings of `events` and stopping early at
+/// the first terminal one.
+///
+/// Returns the duration integrated over and the located events in the order they occurred.
+pub(crate) fn integrate_until_with_events<St, Sy>(
+    stepper: &mut St,
+    system: &mut Sy,
+    state: &mut St::State,
+    t: f64,
+    events: &mut [Event<'_, St::State>],
+) -> (f64, Vec<EventOccurrence<St::State>>)
+where
+    St: Adaptive + DenseOutput + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    let mut values = event::evaluate_all(events, stepper.time(), state);
+    let mut occurrences = Vec::new();
+
+    let (tacc, _) = integrate_until_stop(
+        stepper,
+        state,
+        t,
+        |stepper, state| {
+            Ok(event::step_with_events(
+                stepper,
+                system,
+                state,
+                events,
+                &mut values,
+                &mut occurrences,
+            ))
+        },
+        |_, _, _| {},
+    )
+    .unwrap_or_else(|error| unreachable!("{}", error));
+    (tacc, occurrences)
+}
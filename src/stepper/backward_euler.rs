@@ -0,0 +1,132 @@
+use ndarray::{IntoNdProducer, Ix1, Zip};
+use std::fmt::Debug;
+
+use crate::error::Error;
+use crate::jacobian::Jacobian;
+use crate::stats::Stats;
+
+use super::newton::Newton;
+use super::{ImplicitDenseOutput, ImplicitStepper, ZipMarker};
+
+/// The backward (implicit) Euler method, x_{n+1} = x_n + dt f(t_{n+1}, x_{n+1}).
+///
+/// First order and L-stable, this is the most robust choice for stiff systems.
+#[derive(Debug)]
+pub struct BackwardEuler<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) newton: Newton<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<P: ZipMarker> BackwardEuler<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    pub fn new(state: &P, dt: f64) -> Self {
+        let newton = Newton::new(state);
+
+        BackwardEuler {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+            newton,
+            stats: Stats::new(),
+        }
+    }
+
+    /// Set the tolerance on the scaled Newton updates, `1e-10` by default.
+    pub fn with_newton_tolerance(mut self, tolerance: f64) -> Self {
+        self.newton.tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of Newton iterations per step, `20` by default.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.newton.max_iterations = max_iterations;
+        self
+    }
+}
+
+impl<P: ZipMarker> ImplicitStepper for BackwardEuler<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = P>,
+    {
+        self.newton.solve(
+            system,
+            self.t,
+            self.dt,
+            state,
+            1.0,
+            0.0,
+            1.0,
+            &mut self.stats,
+        )?;
+
+        system.update_state(state, &self.newton.z);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+        self.stats.accept(self.dt);
+        Ok(())
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<P: ZipMarker> ImplicitDenseOutput for BackwardEuler<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    /// The straight line `x_{n+1} + (theta - 1) dt f` through the end point of the step, with the
+    /// derivative `f` evaluated before the last Newton update. The start point `theta = 0`
+    /// matches `x_n` only up to the tolerance of the Newton iteration.
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let w = (theta - 1.0) * self.last_dt;
+
+        Zip::from(out)
+            .and(&self.newton.z)
+            .and(&self.newton.f)
+            .apply(|out, &x1, &f| *out = x1 + w * f);
+    }
+}
@@ -0,0 +1,642 @@
+use ndarray::prelude::*;
+use std::collections::VecDeque;
+
+use crate::controller::{IController, StepSizeController};
+use crate::error::Error;
+use crate::linalg::Lu;
+use crate::observer::Observer;
+use crate::residual::ResidualJacobian;
+use crate::stats::Stats;
+use crate::tolerance::{ErrorControl, ErrorNorm, Tolerance};
+
+use super::adaptive::{self, Adaptive, StepControl};
+use super::newton::NewtonError;
+use super::ResidualStepper;
+
+/// The largest order of the BDF formulas that are zero-stable.
+const MAX_ORDER: usize = 5;
+
+/// The variable-order, variable-step backward differentiation formulas (BDF) of orders 1 to 5
+/// for fully implicit differential-algebraic equations F(t, x, dx/dt) = 0 of index 1, in the
+/// spirit of the `DASSL` and `IDA` codes.
+///
+/// Each step predicts the new state by extrapolating the polynomial through the last `k + 1`
+/// accepted states, where `k` is the current order, and corrects it with Newton's method, with
+/// the derivative taken from the polynomial through the new and the last `k` states. The
+/// iteration matrix `α ∂F/∂x' + ∂F/∂x` is evaluated once per attempted step; if the iteration
+/// fails, the step is retried with a quarter of the step size. The local error is estimated from
+/// the difference of the predicted and the corrected state and controlled like that of
+/// `DormandPrince5`, while the step size grows by at most a factor of 2 per step. After `k + 1`
+/// steps at the same order, the order is lowered or raised if that is estimated to allow larger
+/// steps.
+///
+/// The stepper starts at order 1 from a state and its derivative, which must be consistent, see
+/// `make_consistent`. It keeps the history of accepted states and starts over from order 1 when
+/// the state or the time is changed in between steps.
+#[derive(Debug)]
+pub struct Bdf<C = IController> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) order: usize,
+    pub(crate) max_order: usize,
+    pub(crate) steps_at_order: usize,
+
+    pub(crate) error_control: ErrorControl,
+    pub(crate) controller: C,
+
+    pub(crate) newton_tolerance: f64,
+    pub(crate) max_iterations: usize,
+    pub(crate) algebraic: Vec<usize>,
+
+    /// The times and states of the accepted steps, the latest first.
+    pub(crate) times: VecDeque<f64>,
+    pub(crate) states: VecDeque<Array1<f64>>,
+
+    pub(crate) predicted: Array1<f64>,
+    pub(crate) corrected: Array1<f64>,
+    pub(crate) derivative: Array1<f64>,
+    pub(crate) beta: Array1<f64>,
+    pub(crate) residual: Array1<f64>,
+    pub(crate) delta: Array1<f64>,
+
+    pub(crate) jacobian: Array2<f64>,
+    pub(crate) lu: Lu,
+
+    pub(crate) stats: Stats,
+}
+
+impl Bdf {
+    /// Create a new stepper with initial step size `dt`, using an absolute tolerance of `1e-6`
+    /// and a relative tolerance of `1e-3`.
+    pub fn new(state: &Array1<f64>, dt: f64) -> Self {
+        let n = state.len();
+
+        Bdf {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            order: 1,
+            max_order: MAX_ORDER,
+            steps_at_order: 0,
+
+            error_control: ErrorControl::new(1e-6, 1e-3),
+            controller: IController::new(),
+
+            newton_tolerance: 0.33,
+            max_iterations: 4,
+            algebraic: Vec::new(),
+
+            times: VecDeque::new(),
+            states: VecDeque::new(),
+
+            predicted: Array1::zeros(n),
+            corrected: Array1::zeros(n),
+            derivative: Array1::zeros(n),
+            beta: Array1::zeros(n),
+            residual: Array1::zeros(n),
+            delta: Array1::zeros(n),
+
+            jacobian: Array2::zeros((n, n)),
+            lu: Lu::new(n),
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl<C> Bdf<C>
+where
+    C: StepSizeController,
+{
+    /// Replace the step size controller.
+    pub fn with_controller<C2>(self, controller: C2) -> Bdf<C2>
+    where
+        C2: StepSizeController,
+    {
+        Bdf {
+            dt: self.dt,
+            last_dt: self.last_dt,
+            t: self.t,
+
+            order: self.order,
+            max_order: self.max_order,
+            steps_at_order: self.steps_at_order,
+
+            error_control: self.error_control,
+            controller,
+
+            newton_tolerance: self.newton_tolerance,
+            max_iterations: self.max_iterations,
+            algebraic: self.algebraic,
+
+            times: self.times,
+            states: self.states,
+
+            predicted: self.predicted,
+            corrected: self.corrected,
+            derivative: self.derivative,
+            beta: self.beta,
+            residual: self.residual,
+            delta: self.delta,
+
+            jacobian: self.jacobian,
+            lu: self.lu,
+
+            stats: self.stats,
+        }
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Set the absolute and relative tolerances the local error is controlled against, either
+    /// for all components or per component.
+    pub fn with_tolerances<A, R>(mut self, atol: A, rtol: R) -> Self
+    where
+        A: Into<Tolerance>,
+        R: Into<Tolerance>,
+    {
+        self.error_control = ErrorControl::new(atol, rtol).with_norm(self.error_control.norm);
+        self
+    }
+
+    /// Set the norm the scaled errors of the components are combined with.
+    pub fn with_error_norm(mut self, norm: ErrorNorm) -> Self {
+        self.error_control.norm = norm;
+        self
+    }
+
+    /// Replace the whole error control.
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    pub fn error_control(&self) -> &ErrorControl {
+        &self.error_control
+    }
+
+    /// Limit the order of the formulas to `max_order`, `5` by default.
+    ///
+    /// Panics unless `1 <= max_order <= 5`.
+    pub fn with_max_order(mut self, max_order: usize) -> Self {
+        assert!(
+            (1..=MAX_ORDER).contains(&max_order),
+            "Bdf: the order must be between 1 and {}, got {}",
+            MAX_ORDER,
+            max_order
+        );
+        self.max_order = max_order;
+        self.order = self.order.min(max_order);
+        self
+    }
+
+    /// Set the tolerance on the Newton updates, scaled like the local error, `0.33` by default.
+    pub fn with_newton_tolerance(mut self, tolerance: f64) -> Self {
+        self.newton_tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of Newton iterations per attempted step, `4` by default.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Mark the components of the state that are algebraic variables, whose derivatives do not
+    /// appear in the residual. `make_consistent` computes them instead of their derivatives.
+    pub fn with_algebraic_variables(mut self, variables: &[usize]) -> Self {
+        self.algebraic = variables.to_vec();
+        self
+    }
+
+    /// The order of the formula used for the next step.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// The step size of the last accepted step.
+    pub fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    /// Make the derivative consistent with the state at the current time, by solving
+    /// F(t, x, dx/dt) = 0 with Newton's method. For the algebraic variables set with
+    /// `with_algebraic_variables`, the state is solved for instead, keeping their derivatives.
+    ///
+    /// The iteration matrix is assembled from the columns of `∂F/∂x'` for the differential and
+    /// of `∂F/∂x` for the algebraic variables. Fails if it is singular or the Newton iteration
+    /// does not converge.
+    pub fn make_consistent<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+    ) -> Result<(), Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        let t = self.t;
+        let n = state.len();
+        let mut is_algebraic = vec![false; n];
+        for &j in &self.algebraic {
+            is_algebraic[j] = true;
+        }
+
+        // `∂F/∂x` and `∂F/∂x' + ∂F/∂x`.
+        let mut jacobian = Array2::zeros((n, n));
+        let mut increment = f64::INFINITY;
+        for _ in 0..20 {
+            self.stats.rhs_evaluations += 1;
+            system.try_residual_into(t, state, derivative, &mut self.residual)?;
+            self.stats.jacobian_evaluations += 2;
+            self.stats.rhs_evaluations += 2 * system.residual_evaluations(n);
+            system.try_residual_jacobian_into(t, state, derivative, 0.0, &mut self.jacobian)?;
+            system.try_residual_jacobian_into(t, state, derivative, 1.0, &mut jacobian)?;
+            self.stats.newton_iterations += 1;
+
+            for (j, &algebraic) in is_algebraic.iter().enumerate() {
+                let mut column = self.lu.lu.column_mut(j);
+                if algebraic {
+                    column.assign(&self.jacobian.column(j));
+                } else {
+                    column.assign(&(&jacobian.column(j) - &self.jacobian.column(j)));
+                }
+            }
+
+            self.stats.lu_factorizations += 1;
+            if !self.lu.factorize() {
+                return Err(NewtonError::SingularMatrix.into());
+            }
+            self.delta.assign(&-&self.residual);
+            self.lu.solve_into(&mut self.delta);
+
+            increment = 0.0;
+            for (j, &algebraic) in is_algebraic.iter().enumerate() {
+                let value = if algebraic {
+                    &mut state[j]
+                } else {
+                    &mut derivative[j]
+                };
+                *value += self.delta[j];
+                increment = increment.max(self.delta[j].abs() / (1.0 + value.abs()));
+            }
+            if !increment.is_finite() {
+                break;
+            }
+
+            if increment <= 1e-12 {
+                return Ok(());
+            }
+        }
+
+        Err(NewtonError::NotConverged {
+            iterations: 20,
+            increment,
+        }
+        .into())
+    }
+
+    /// Do one accepted step, retrying rejected attempts with smaller step sizes.
+    fn step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+    ) -> Result<(), Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        // Start over from a state that does not continue the history.
+        if self.times.front() != Some(&self.t) || self.states.front() != Some(&*state) {
+            self.times.clear();
+            self.states.clear();
+            self.times.push_front(self.t);
+            self.states.push_front(state.clone());
+            self.order = 1;
+            self.steps_at_order = 0;
+        }
+
+        let mut failures = 0;
+        loop {
+            let dt = self.dt;
+            let t1 = self.t + dt;
+
+            // Without previous states, the first step of order 1 predicts along the derivative.
+            let k = self.order.min((self.times.len() - 1).max(1));
+            if self.times.len() == 1 {
+                self.predicted.assign(state);
+                self.predicted.scaled_add(dt, derivative);
+            } else {
+                extrapolate(&self.times, &self.states, k, t1, &mut self.predicted);
+            }
+
+            // x'(t1) ≈ α_0 x(t1) + β.
+            let alpha = coefficients(&self.times, k, t1);
+            self.beta.fill(0.0);
+            for (a, x) in alpha[1..].iter().zip(&self.states) {
+                self.beta.scaled_add(*a, x);
+            }
+
+            if !self.correct(system, t1, alpha[0])? {
+                failures += 1;
+                self.retry(dt / 4.0)?;
+                continue;
+            }
+
+            // The predictor and the corrector share the leading term of their errors,
+            // proportional to the (k + 1)-th derivative.
+            let oldest = self.times[k.min(self.times.len() - 1)];
+            let scale = 1.0 / (alpha[0] * (t1 - oldest));
+            self.delta.assign(&(&self.corrected - &self.predicted));
+            self.delta *= scale;
+            let error = self
+                .error_control
+                .error(&self.delta, state, &self.corrected);
+
+            if self.adapt(error, k as u32 + 1)? {
+                state.assign(&self.corrected);
+                derivative.assign(&self.beta);
+                derivative.scaled_add(alpha[0], state);
+
+                self.times.push_front(t1);
+                self.states.push_front(state.clone());
+                self.times.truncate(MAX_ORDER + 3);
+                self.states.truncate(MAX_ORDER + 3);
+
+                self.order = k;
+                self.select_order();
+
+                // Larger ratios of successive step sizes compromise the stability of the higher
+                // orders.
+                if self.dt.abs() > 2.0 * dt.abs() {
+                    self.dt = 2.0 * dt;
+                }
+                return Ok(());
+            }
+            failures += 1;
+            if failures >= 2 {
+                self.order = (k - 1).max(1);
+                self.steps_at_order = 0;
+            }
+        }
+    }
+
+    /// Solve F(t1, x, α_0 x + β) = 0 for the corrected state, starting from the predicted one.
+    /// Returns whether the Newton iteration converged.
+    fn correct<Sy>(&mut self, system: &mut Sy, t1: f64, alpha0: f64) -> Result<bool, Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        self.corrected.assign(&self.predicted);
+
+        let mut previous: Option<f64> = None;
+        let mut factor = 1.0;
+        for iteration in 0..self.max_iterations {
+            self.stats.newton_iterations += 1;
+            self.derivative.assign(&self.beta);
+            self.derivative.scaled_add(alpha0, &self.corrected);
+            self.stats.rhs_evaluations += 1;
+            system.try_residual_into(t1, &self.corrected, &self.derivative, &mut self.residual)?;
+
+            // The iteration matrix at the predicted state, once the residual is known to be
+            // defined there.
+            if iteration == 0 {
+                self.stats.jacobian_evaluations += 1;
+                self.stats.rhs_evaluations += system.residual_evaluations(self.jacobian.rows());
+                system.try_residual_jacobian_into(
+                    t1,
+                    &self.corrected,
+                    &self.derivative,
+                    alpha0,
+                    &mut self.jacobian,
+                )?;
+                self.lu.lu.assign(&self.jacobian);
+                self.stats.lu_factorizations += 1;
+                if !self.lu.factorize() {
+                    return Ok(false);
+                }
+            }
+
+            self.delta.assign(&-&self.residual);
+            self.lu.solve_into(&mut self.delta);
+            self.corrected += &self.delta;
+
+            // The scaled size of the update, and the estimated rate of convergence.
+            let size = self
+                .error_control
+                .error(&self.delta, &self.predicted, &self.corrected);
+            if !size.is_finite() {
+                return Ok(false);
+            }
+            if let Some(previous) = previous {
+                let rate = size / previous;
+                if rate >= 0.9 {
+                    return Ok(false);
+                }
+                factor = rate / (1.0 - rate);
+            }
+            if factor * size <= self.newton_tolerance {
+                return Ok(true);
+            }
+            previous = Some(size);
+        }
+        Ok(false)
+    }
+
+    /// After `k + 1` accepted steps at order `k`, move to the neighbouring order with the
+    /// smallest estimated error at the last step size, `|h|^(q+1) q! |x[t_0, ..., t_(q+1)]|` for
+    /// order `q` in terms of the divided differences of the latest states.
+    fn select_order(&mut self) {
+        self.steps_at_order += 1;
+        let k = self.order;
+        if self.steps_at_order <= k || self.times.len() < k + 2 {
+            return;
+        }
+
+        let highest = (k + 1).min(self.max_order).min(self.times.len() - 2);
+        let mut estimates = Vec::with_capacity(highest + 1);
+        let mut differences: Vec<Array1<f64>> = self.states.iter().cloned().collect();
+        let mut factorial = 1.0;
+        for level in 1..=highest + 1 {
+            for i in 0..differences.len() - level {
+                let dt = self.times[i] - self.times[i + level];
+                let (upper, lower) = differences.split_at_mut(i + 1);
+                upper[i] -= &lower[0];
+                upper[i] /= dt;
+            }
+            // The estimate for order q = level - 1.
+            if level >= 2 {
+                factorial *= (level - 1) as f64;
+            }
+            let scaled = &differences[0] * (self.last_dt.abs().powi(level as i32) * factorial);
+            estimates.push(
+                self.error_control
+                    .error(&scaled, &self.states[1], &self.states[0]),
+            );
+        }
+
+        let mut order = k;
+        if k > 1 && estimates[k - 1] <= estimates[k] {
+            order = k - 1;
+        } else if highest > k && estimates[k + 1] < estimates[k] {
+            order = k + 1;
+        }
+        if order != k {
+            self.order = order;
+            self.steps_at_order = 0;
+        }
+    }
+}
+
+impl<C> ResidualStepper for Bdf<C>
+where
+    C: StepSizeController,
+{
+    /// Do one accepted step, retrying rejected attempts with smaller step sizes, and update the
+    /// derivative to that of the new state.
+    fn do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+    ) -> Result<(), Error>
+    where
+        Sy: ResidualJacobian,
+    {
+        self.step(system, state, derivative)
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    /// Do `n` accepted steps, returning the time integrated over.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        n: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error>
+    where
+        Sy: ResidualJacobian,
+        O: Observer<Array1<f64>>,
+    {
+        let mut tacc = 0f64;
+
+        observer.observe(self.t, state, 0);
+        for count in 1..=n {
+            self.step(system, state, derivative)?;
+            tacc += self.last_dt;
+            observer.observe(self.t, state, count);
+        }
+        Ok(tacc)
+    }
+
+    /// Integrate over exactly the duration `t`, truncating the last step.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Array1<f64>,
+        derivative: &mut Array1<f64>,
+        t: f64,
+        observer: &mut O,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: ResidualJacobian,
+        O: Observer<Array1<f64>>,
+    {
+        adaptive::integrate_until(
+            self,
+            &mut (state, derivative),
+            t,
+            |stepper, (state, derivative)| stepper.step(system, state, derivative),
+            |t, (state, _), count| observer.observe(t, state, count),
+        )
+    }
+}
+
+impl<C> Adaptive for Bdf<C>
+where
+    C: StepSizeController,
+{
+    type Controller = C;
+
+    fn step_control(&mut self) -> StepControl<'_, C> {
+        StepControl {
+            t: &mut self.t,
+            dt: &mut self.dt,
+            last_dt: &mut self.last_dt,
+            controller: &mut self.controller,
+            stats: &mut self.stats,
+        }
+    }
+
+    /// Also moves the latest time of the history, which continues from there.
+    fn end_at(&mut self, t: f64) {
+        self.t = t;
+        if let Some(time) = self.times.front_mut() {
+            *time = t;
+        }
+    }
+}
+
+/// Evaluate the polynomial through the latest `k + 1` states at `t`.
+fn extrapolate(
+    times: &VecDeque<f64>,
+    states: &VecDeque<Array1<f64>>,
+    k: usize,
+    t: f64,
+    into: &mut Array1<f64>,
+) {
+    into.fill(0.0);
+    for j in 0..=k {
+        let mut lagrange = 1.0;
+        for m in (0..=k).filter(|&m| m != j) {
+            lagrange *= (t - times[m]) / (times[j] - times[m]);
+        }
+        into.scaled_add(lagrange, &states[j]);
+    }
+}
+
+/// The weights `α_j` of the derivative at `t1` of the polynomial through `t1` and the latest `k`
+/// times, where `α_0` belongs to `t1`.
+fn coefficients(times: &VecDeque<f64>, k: usize, t1: f64) -> Vec<f64> {
+    let mut alpha = vec![0.0; k + 1];
+    alpha[0] = times.iter().take(k).map(|&t| 1.0 / (t1 - t)).sum();
+    for j in 1..=k {
+        let t_j = times[j - 1];
+        let mut weight = 1.0 / (t_j - t1);
+        for m in (1..=k).filter(|&m| m != j) {
+            weight *= (t1 - times[m - 1]) / (t_j - times[m - 1]);
+        }
+        alpha[j] = weight;
+    }
+    alpha
+}
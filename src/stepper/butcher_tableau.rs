@@ -0,0 +1,143 @@
+use ndarray::array;
+use ndarray::prelude::*;
+
+/// The coefficients of an explicit Runge-Kutta method.
+///
+/// For an `s`-stage method, `a` is the strictly lower triangular `s×s` matrix of stage
+/// coefficients, `b` holds the `s` weights and `c` the `s` nodes:
+///
+/// ```text
+/// c | a
+/// --+---
+///   | b
+/// ```
+#[derive(Clone, Debug)]
+pub struct ButcherTableau {
+    pub(crate) a: Array2<f64>,
+    pub(crate) b: Array1<f64>,
+    pub(crate) c: Array1<f64>,
+}
+
+impl ButcherTableau {
+    /// Construct a tableau from its coefficients.
+    ///
+    /// Panics if the dimensions of `a`, `b`, and `c` do not match, or if `a` is not strictly lower
+    /// triangular, i.e. if the method is not explicit.
+    pub fn new(a: Array2<f64>, b: Array1<f64>, c: Array1<f64>) -> Self {
+        let stages = b.len();
+
+        assert!(
+            stages > 0,
+            "ButcherTableau: a method needs at least one stage"
+        );
+        assert_eq!(
+            a.dim(),
+            (stages, stages),
+            "ButcherTableau: `a` has to be a square matrix matching the number of weights"
+        );
+        assert_eq!(
+            c.len(),
+            stages,
+            "ButcherTableau: the number of nodes has to match the number of weights"
+        );
+
+        for ((i, j), &a_ij) in a.indexed_iter() {
+            assert!(
+                j < i || a_ij == 0.0,
+                "ButcherTableau: `a` has to be strictly lower triangular for an explicit method"
+            );
+        }
+
+        ButcherTableau { a, b, c }
+    }
+
+    /// The number of stages of the method.
+    pub fn stages(&self) -> usize {
+        self.b.len()
+    }
+
+    pub fn a(&self) -> ArrayView2<'_, f64> {
+        self.a.view()
+    }
+
+    pub fn b(&self) -> ArrayView1<'_, f64> {
+        self.b.view()
+    }
+
+    pub fn c(&self) -> ArrayView1<'_, f64> {
+        self.c.view()
+    }
+
+    /// The explicit Euler method, order 1.
+    pub fn euler() -> Self {
+        Self::new(array![[0.0]], array![1.0], array![0.0])
+    }
+
+    /// Heun's method, order 2.
+    pub fn heun() -> Self {
+        Self::new(
+            array![[0.0, 0.0], [1.0, 0.0]],
+            array![1.0 / 2.0, 1.0 / 2.0],
+            array![0.0, 1.0],
+        )
+    }
+
+    /// The explicit midpoint method, order 2.
+    pub fn midpoint() -> Self {
+        Self::new(
+            array![[0.0, 0.0], [1.0 / 2.0, 0.0]],
+            array![0.0, 1.0],
+            array![0.0, 1.0 / 2.0],
+        )
+    }
+
+    /// Ralston's method, the second order method with minimal truncation error.
+    pub fn ralston() -> Self {
+        Self::new(
+            array![[0.0, 0.0], [2.0 / 3.0, 0.0]],
+            array![1.0 / 4.0, 3.0 / 4.0],
+            array![0.0, 2.0 / 3.0],
+        )
+    }
+
+    /// The strong stability preserving method of order 3 by Shu and Osher.
+    pub fn ssprk3() -> Self {
+        Self::new(
+            array![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0 / 4.0, 1.0 / 4.0, 0.0]
+            ],
+            array![1.0 / 6.0, 1.0 / 6.0, 2.0 / 3.0],
+            array![0.0, 1.0, 1.0 / 2.0],
+        )
+    }
+
+    /// The classical Runge-Kutta method, order 4.
+    pub fn rk4() -> Self {
+        Self::new(
+            array![
+                [0.0, 0.0, 0.0, 0.0],
+                [1.0 / 2.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / 2.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0]
+            ],
+            array![1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+            array![0.0, 1.0 / 2.0, 1.0 / 2.0, 1.0],
+        )
+    }
+
+    /// Kutta's 3/8-rule, order 4.
+    pub fn three_eighths() -> Self {
+        Self::new(
+            array![
+                [0.0, 0.0, 0.0, 0.0],
+                [1.0 / 3.0, 0.0, 0.0, 0.0],
+                [-1.0 / 3.0, 1.0, 0.0, 0.0],
+                [1.0, -1.0, 1.0, 0.0]
+            ],
+            array![1.0 / 8.0, 3.0 / 8.0, 3.0 / 8.0, 1.0 / 8.0],
+            array![0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0],
+        )
+    }
+}
@@ -0,0 +1,940 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::controller::{IController, StepSizeController};
+use crate::error::{self, Error, Guarded};
+use crate::event::{Event, EventOccurrence};
+use crate::initial_timestep::initial_timestep;
+use crate::observer::Observer;
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
+use crate::tolerance::{Componentwise, ErrorControl, ErrorNorm, Tolerance};
+
+use super::adaptive::{self, Adaptive, StepControl};
+use super::{DenseOutput, Stepper, ZipMarker};
+
+// Coefficients of the Dormand–Prince 5(4) pair, see Hairer, Nørsett, Wanner: Solving Ordinary
+// Differential Equations I, Table 5.2.
+pub(crate) const C2: f64 = 1.0 / 5.0;
+pub(crate) const C3: f64 = 3.0 / 10.0;
+pub(crate) const C4: f64 = 4.0 / 5.0;
+pub(crate) const C5: f64 = 8.0 / 9.0;
+
+pub(crate) const A21: f64 = 1.0 / 5.0;
+
+pub(crate) const A31: f64 = 3.0 / 40.0;
+pub(crate) const A32: f64 = 9.0 / 40.0;
+
+pub(crate) const A41: f64 = 44.0 / 45.0;
+pub(crate) const A42: f64 = -56.0 / 15.0;
+pub(crate) const A43: f64 = 32.0 / 9.0;
+
+pub(crate) const A51: f64 = 19372.0 / 6561.0;
+pub(crate) const A52: f64 = -25360.0 / 2187.0;
+pub(crate) const A53: f64 = 64448.0 / 6561.0;
+pub(crate) const A54: f64 = -212.0 / 729.0;
+
+pub(crate) const A61: f64 = 9017.0 / 3168.0;
+pub(crate) const A62: f64 = -355.0 / 33.0;
+pub(crate) const A63: f64 = 46732.0 / 5247.0;
+pub(crate) const A64: f64 = 49.0 / 176.0;
+pub(crate) const A65: f64 = -5103.0 / 18656.0;
+
+// Weights of the 5th order solution; b2 = 0.
+pub(crate) const B1: f64 = 35.0 / 384.0;
+pub(crate) const B3: f64 = 500.0 / 1113.0;
+pub(crate) const B4: f64 = 125.0 / 192.0;
+pub(crate) const B5: f64 = -2187.0 / 6784.0;
+pub(crate) const B6: f64 = 11.0 / 84.0;
+
+// Differences between the weights of the 5th and the embedded 4th order solution; e2 = 0.
+pub(crate) const E1: f64 = 71.0 / 57600.0;
+pub(crate) const E3: f64 = -71.0 / 16695.0;
+pub(crate) const E4: f64 = 71.0 / 1920.0;
+pub(crate) const E5: f64 = -17253.0 / 339_200.0;
+pub(crate) const E6: f64 = 22.0 / 525.0;
+pub(crate) const E7: f64 = -1.0 / 40.0;
+
+// Coefficients of the dense output of order 4, see Hairer, Nørsett, Wanner: Solving Ordinary
+// Differential Equations I, Section II.6; d2 = 0.
+pub(crate) const D1: f64 = -12_715_105_075.0 / 11_282_082_432.0;
+pub(crate) const D3: f64 = 87_487_479_700.0 / 32_700_410_799.0;
+pub(crate) const D4: f64 = -10_690_763_975.0 / 1_880_347_072.0;
+pub(crate) const D5: f64 = 701_980_252_875.0 / 199_316_789_632.0;
+pub(crate) const D6: f64 = -1_453_857_185.0 / 822_651_844.0;
+pub(crate) const D7: f64 = 69_997_945.0 / 29_380_423.0;
+
+/// The adaptive Dormand–Prince 5(4) method.
+///
+/// Each call to `do_step` performs exactly one accepted step. The local error is estimated from
+/// the embedded 4th order solution and measured as the root mean square of the componentwise
+/// errors scaled by `atol + rtol * max(|x|, |x_next|)`. Steps with a scaled error above 1 are
+/// rejected and retried with a smaller step size; after every accepted step the step size is
+/// adapted for the next one. The step sizes are chosen by the `StepSizeController` `C`, by
+/// default an integral controller.
+///
+/// The derivative at the end of an accepted step is not reused for the next step, since
+/// `Ode::update_state` may change the state in between.
+#[derive(Debug)]
+pub struct DormandPrince5<T: Debug, C = IController> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) error_control: ErrorControl,
+    pub(crate) controller: C,
+
+    pub(crate) temp: T,
+    pub(crate) err: T,
+
+    pub(crate) k1: T,
+    pub(crate) k2: T,
+    pub(crate) k3: T,
+    pub(crate) k4: T,
+    pub(crate) k5: T,
+    pub(crate) k6: T,
+    pub(crate) k7: T,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T> DormandPrince5<T>
+where
+    T: Clone + Debug,
+{
+    /// Create a new stepper with initial step size `dt`, using an absolute tolerance of `1e-6`
+    /// and a relative tolerance of `1e-3`.
+    pub fn new(state: &T, dt: f64) -> Self {
+        let temp = state.clone();
+        let err = state.clone();
+
+        let k1 = state.clone();
+        let k2 = state.clone();
+        let k3 = state.clone();
+        let k4 = state.clone();
+        let k5 = state.clone();
+        let k6 = state.clone();
+        let k7 = state.clone();
+
+        DormandPrince5 {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            error_control: ErrorControl::new(1e-6, 1e-3),
+            controller: IController::new(),
+
+            temp,
+            err,
+
+            k1,
+            k2,
+            k3,
+            k4,
+            k5,
+            k6,
+            k7,
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl<T, C> DormandPrince5<T, C>
+where
+    T: Clone + Debug,
+    C: StepSizeController,
+{
+    /// Replace the step size controller.
+    pub fn with_controller<C2>(self, controller: C2) -> DormandPrince5<T, C2>
+    where
+        C2: StepSizeController,
+    {
+        DormandPrince5 {
+            dt: self.dt,
+            last_dt: self.last_dt,
+            t: self.t,
+
+            error_control: self.error_control,
+            controller,
+
+            temp: self.temp,
+            err: self.err,
+
+            k1: self.k1,
+            k2: self.k2,
+            k3: self.k3,
+            k4: self.k4,
+            k5: self.k5,
+            k6: self.k6,
+            k7: self.k7,
+
+            stats: self.stats,
+        }
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Set the absolute and relative tolerances the local error is controlled against, either
+    /// for all components or per component.
+    pub fn with_tolerances<A, R>(mut self, atol: A, rtol: R) -> Self
+    where
+        A: Into<Tolerance>,
+        R: Into<Tolerance>,
+    {
+        self.error_control = ErrorControl::new(atol, rtol).with_norm(self.error_control.norm);
+        self
+    }
+
+    /// Set the norm the scaled errors of the components are combined with.
+    pub fn with_error_norm(mut self, norm: ErrorNorm) -> Self {
+        self.error_control.norm = norm;
+        self
+    }
+
+    /// Replace the whole error control.
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    pub fn error_control(&self) -> &ErrorControl {
+        &self.error_control
+    }
+
+    /// Start with the step size proposed by `initial_timestep` for integrating `system` from
+    /// `state` at the current time, using the tolerances of the stepper. The sign of `dt_max`
+    /// gives the direction of the integration.
+    pub fn select_initial_timestep<Sy>(&mut self, system: &mut Sy, state: &T, dt_max: f64)
+    where
+        Sy: NonautonomousOde<State = T>,
+        T: Componentwise,
+    {
+        self.dt = initial_timestep(system, self.t, state, dt_max, 5, &self.error_control);
+        self.stats.rhs_evaluations += 2;
+    }
+
+    /// The step size of the last accepted step.
+    pub fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    /// The weights of `k1`, `k3`, `k4`, `k5`, `k6`, and `k7` in the dense output
+    /// `x1 + w1 * k1 + w3 * k3 + ... + w7 * k7` of the last accepted step.
+    pub(crate) fn dense_weights(&self, theta: f64) -> [f64; 6] {
+        let dt = self.last_dt;
+        let theta1 = 1.0 - theta;
+
+        // The weight of the state at the beginning of the step, which is recovered as
+        // x0 = x1 - dt * (b1 * k1 + b3 * k3 + ... + b6 * k6).
+        let w0 = -theta1 * theta1 * (2.0 * theta + 1.0);
+        let d = theta * theta * theta1 * theta1;
+
+        [
+            dt * (w0 * B1 + theta * theta1 * theta1 + d * D1),
+            dt * (w0 * B3 + d * D3),
+            dt * (w0 * B4 + d * D4),
+            dt * (w0 * B5 + d * D5),
+            dt * (w0 * B6 + d * D6),
+            dt * (-theta * theta * theta1 + d * D7),
+        ]
+    }
+}
+
+impl<T, C> Adaptive for DormandPrince5<T, C>
+where
+    T: Debug,
+    C: StepSizeController,
+{
+    type Controller = C;
+
+    fn step_control(&mut self) -> StepControl<'_, C> {
+        StepControl {
+            t: &mut self.t,
+            dt: &mut self.dt,
+            last_dt: &mut self.last_dt,
+            controller: &mut self.controller,
+            stats: &mut self.stats,
+        }
+    }
+}
+
+impl<T, C> DormandPrince5<T, C>
+where
+    T: Clone + Debug,
+    C: StepSizeController,
+    DormandPrince5<T, C>: Stepper<State = T>,
+{
+    pub(crate) fn integrate_n_accepted<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: NonautonomousOde<State = T>,
+        O: Observer<T>,
+    {
+        self.integrate_n_accepted_by(system, state, n, observer, |stepper, system, state| {
+            stepper.do_step(system, state);
+            Ok(())
+        })
+        .unwrap_or_else(|error| unreachable!("{}", error))
+    }
+
+    pub(crate) fn try_integrate_n_accepted<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: NonautonomousOde<State = T>,
+        T: Componentwise,
+    {
+        self.integrate_n_accepted_by(system, state, n, &mut (), Self::try_do_step)
+    }
+
+    /// Do `n` accepted steps with `step`, stopping at the first failure.
+    fn integrate_n_accepted_by<Sy, O, F>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        n: usize,
+        observer: &mut O,
+        mut step: F,
+    ) -> Result<f64, Error>
+    where
+        Sy: NonautonomousOde<State = T>,
+        O: Observer<T>,
+        F: FnMut(&mut Self, &mut Sy, &mut T) -> Result<(), Error>,
+    {
+        let mut tacc = 0f64;
+
+        observer.observe(self.t, state, 0);
+        for count in 1..=n {
+            step(self, system, state)?;
+            tacc += self.last_dt;
+            observer.observe(self.t, state, count);
+        }
+        Ok(tacc)
+    }
+
+    pub(crate) fn integrate_until<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = T>,
+        O: Observer<T>,
+    {
+        self.integrate_until_by(system, state, t, observer, |stepper, system, state| {
+            stepper.do_step(system, state);
+            Ok(())
+        })
+        .unwrap_or_else(|error| unreachable!("{}", error))
+    }
+
+    pub(crate) fn try_integrate_until<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: NonautonomousOde<State = T>,
+        T: Componentwise,
+    {
+        self.integrate_until_by(system, state, t, &mut (), Self::try_do_step)
+    }
+
+    /// Integrate over exactly the duration `t` with `step`, truncating the last step, and
+    /// stopping at the first failure.
+    fn integrate_until_by<Sy, O, F>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut T,
+        t: f64,
+        observer: &mut O,
+        mut step: F,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: NonautonomousOde<State = T>,
+        O: Observer<T>,
+        F: FnMut(&mut Self, &mut Sy, &mut T) -> Result<(), Error>,
+    {
+        adaptive::integrate_until(
+            self,
+            state,
+            t,
+            |stepper, state| step(stepper, system, state),
+            |t, state, count| observer.observe(t, state, count),
+        )
+    }
+}
+
+impl<C> DormandPrince5<f64, C>
+where
+    C: StepSizeController,
+{
+    /// Do one accepted step, failing if the step size underflows.
+    pub(crate) fn step<Sy>(&mut self, system: &mut Sy, state: &mut f64) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = f64>,
+    {
+        self.step_checked(system, state, |_| Ok(()))
+    }
+
+    /// Do one accepted step like `step`, failing as soon as `check` fails after the evaluations of
+    /// the right-hand side of an attempt.
+    fn step_checked<Sy, F>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut f64,
+        check: F,
+    ) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = f64>,
+        F: Fn(&Sy) -> Result<(), Error>,
+    {
+        let x = *state;
+        let t = self.t;
+        system.differentiate_at_into(t, state, &mut self.k1);
+        check(system)?;
+        self.stats.rhs_evaluations += 1;
+
+        loop {
+            let dt = self.dt;
+
+            system.differentiate_at_into(t + C2 * dt, &(x + dt * A21 * self.k1), &mut self.k2);
+            system.differentiate_at_into(
+                t + C3 * dt,
+                &(x + dt * (A31 * self.k1 + A32 * self.k2)),
+                &mut self.k3,
+            );
+            system.differentiate_at_into(
+                t + C4 * dt,
+                &(x + dt * (A41 * self.k1 + A42 * self.k2 + A43 * self.k3)),
+                &mut self.k4,
+            );
+            system.differentiate_at_into(
+                t + C5 * dt,
+                &(x + dt * (A51 * self.k1 + A52 * self.k2 + A53 * self.k3 + A54 * self.k4)),
+                &mut self.k5,
+            );
+            system.differentiate_at_into(
+                t + dt,
+                &(x + dt
+                    * (A61 * self.k1
+                        + A62 * self.k2
+                        + A63 * self.k3
+                        + A64 * self.k4
+                        + A65 * self.k5)),
+                &mut self.k6,
+            );
+            self.temp =
+                x + dt * (B1 * self.k1 + B3 * self.k3 + B4 * self.k4 + B5 * self.k5 + B6 * self.k6);
+            system.differentiate_at_into(t + dt, &self.temp, &mut self.k7);
+
+            self.err = dt
+                * (E1 * self.k1
+                    + E3 * self.k3
+                    + E4 * self.k4
+                    + E5 * self.k5
+                    + E6 * self.k6
+                    + E7 * self.k7);
+
+            check(system)?;
+            self.stats.rhs_evaluations += 6;
+            let error = self.error_control.error(&self.err, &x, &self.temp);
+            // The error estimate of the embedded 4th order solution is O(dt^5).
+            if self.adapt(error, 5)? {
+                break;
+            }
+        }
+        system.update_state(state, &self.temp);
+        Ok(())
+    }
+}
+
+impl<C> Stepper for DormandPrince5<f64, C>
+where
+    C: StepSizeController,
+{
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: NonautonomousOde<State = f64>,
+    {
+        if let Err(error) = self.step(system, state) {
+            panic!("DormandPrince5: {}", error);
+        }
+    }
+
+    fn try_do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        error::guarded_step(self, system, state, |stepper, system, state| {
+            // The work of a failed step is undone along with the step.
+            let stats = stepper.stats;
+            let result = stepper.step_checked(system, state, Guarded::check);
+            if result.is_err() {
+                stepper.stats = stats;
+            }
+            result
+        })
+    }
+
+    fn timestep(&self) -> f64 {
+        self.timestep()
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    fn integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> f64
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        self.integrate_n_accepted(system, state, n, &mut ())
+    }
+
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        self.integrate_until(system, state, t, &mut ())
+    }
+
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        self.integrate_n_accepted(system, state, n, observer)
+    }
+
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        self.integrate_until(system, state, t, observer)
+    }
+
+    fn integrate_time_with_events<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        events: &mut [Event<'_, Self::State>],
+    ) -> (f64, Vec<EventOccurrence<Self::State>>)
+    where
+        Self: DenseOutput,
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        adaptive::integrate_until_with_events(self, system, state, t, events)
+    }
+
+    fn try_integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        self.try_integrate_n_accepted(system, state, n)
+    }
+
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        self.try_integrate_until(system, state, t)
+    }
+}
+
+impl<D, P: ZipMarker, C> DormandPrince5<P, C>
+where
+    C: StepSizeController,
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    /// Do one accepted step, failing if the step size underflows.
+    pub(crate) fn step<Sy>(&mut self, system: &mut Sy, state: &mut P) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = P>,
+    {
+        self.step_checked(system, state, |_| Ok(()))
+    }
+
+    /// Do one accepted step like `step`, failing as soon as `check` fails after the evaluations of
+    /// the right-hand side of an attempt.
+    fn step_checked<Sy, F>(&mut self, system: &mut Sy, state: &mut P, check: F) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = P>,
+        F: Fn(&Sy) -> Result<(), Error>,
+    {
+        let t = self.t;
+        system.differentiate_at_into(t, state, &mut self.k1);
+        check(system)?;
+        self.stats.rhs_evaluations += 1;
+
+        loop {
+            let dt = self.dt;
+
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .apply(|next_x, &x, &x_k1| *next_x = x + dt * A21 * x_k1);
+
+            system.differentiate_at_into(t + C2 * dt, &self.temp, &mut self.k2);
+
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .and(&self.k2)
+                .apply(|next_x, &x, &x_k1, &x_k2| *next_x = x + dt * (A31 * x_k1 + A32 * x_k2));
+
+            system.differentiate_at_into(t + C3 * dt, &self.temp, &mut self.k3);
+
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .and(&self.k2)
+                .and(&self.k3)
+                .apply(|next_x, &x, &x_k1, &x_k2, &x_k3| {
+                    *next_x = x + dt * (A41 * x_k1 + A42 * x_k2 + A43 * x_k3)
+                });
+
+            system.differentiate_at_into(t + C4 * dt, &self.temp, &mut self.k4);
+
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .and(&self.k2)
+                .and(&self.k3)
+                .and(&self.k4)
+                .apply(|next_x, &x, &x_k1, &x_k2, &x_k3, &x_k4| {
+                    *next_x = x + dt * (A51 * x_k1 + A52 * x_k2 + A53 * x_k3 + A54 * x_k4)
+                });
+
+            system.differentiate_at_into(t + C5 * dt, &self.temp, &mut self.k5);
+
+            // Zip takes at most six producers, so the remaining stages are summed up in two parts.
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .and(&self.k2)
+                .and(&self.k3)
+                .and(&self.k4)
+                .apply(|next_x, &x, &x_k1, &x_k2, &x_k3, &x_k4| {
+                    *next_x = x + dt * (A61 * x_k1 + A62 * x_k2 + A63 * x_k3 + A64 * x_k4)
+                });
+
+            Zip::from(&mut self.temp)
+                .and(&self.k5)
+                .apply(|next_x, &x_k5| *next_x += dt * A65 * x_k5);
+
+            system.differentiate_at_into(t + dt, &self.temp, &mut self.k6);
+
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .and(&self.k1)
+                .and(&self.k3)
+                .and(&self.k4)
+                .and(&self.k5)
+                .apply(|next_x, &x, &x_k1, &x_k3, &x_k4, &x_k5| {
+                    *next_x = x + dt * (B1 * x_k1 + B3 * x_k3 + B4 * x_k4 + B5 * x_k5)
+                });
+
+            Zip::from(&mut self.temp)
+                .and(&self.k6)
+                .apply(|next_x, &x_k6| *next_x += dt * B6 * x_k6);
+
+            system.differentiate_at_into(t + dt, &self.temp, &mut self.k7);
+
+            Zip::from(&mut self.err)
+                .and(&self.k1)
+                .and(&self.k3)
+                .and(&self.k4)
+                .and(&self.k5)
+                .and(&self.k6)
+                .apply(|e, &x_k1, &x_k3, &x_k4, &x_k5, &x_k6| {
+                    *e = dt * (E1 * x_k1 + E3 * x_k3 + E4 * x_k4 + E5 * x_k5 + E6 * x_k6)
+                });
+
+            Zip::from(&mut self.err)
+                .and(&self.k7)
+                .apply(|e, &x_k7| *e += dt * E7 * x_k7);
+
+            check(system)?;
+            self.stats.rhs_evaluations += 6;
+            let error = self.error_control.error(&self.err, state, &self.temp);
+            // The error estimate of the embedded 4th order solution is O(dt^5).
+            if self.adapt(error, 5)? {
+                break;
+            }
+        }
+        system.update_state(state, &self.temp);
+        Ok(())
+    }
+}
+
+impl<D, P: ZipMarker, C> Stepper for DormandPrince5<P, C>
+where
+    C: StepSizeController,
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: NonautonomousOde<State = P>,
+    {
+        if let Err(error) = self.step(system, state) {
+            panic!("DormandPrince5: {}", error);
+        }
+    }
+
+    fn try_do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        error::guarded_step(self, system, state, |stepper, system, state| {
+            // The work of a failed step is undone along with the step.
+            let stats = stepper.stats;
+            let result = stepper.step_checked(system, state, Guarded::check);
+            if result.is_err() {
+                stepper.stats = stats;
+            }
+            result
+        })
+    }
+
+    fn timestep(&self) -> f64 {
+        self.timestep()
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    fn integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> f64
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        self.integrate_n_accepted(system, state, n, &mut ())
+    }
+
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        self.integrate_until(system, state, t, &mut ())
+    }
+
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        self.integrate_n_accepted(system, state, n, observer)
+    }
+
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        O: Observer<Self::State>,
+    {
+        self.integrate_until(system, state, t, observer)
+    }
+
+    fn integrate_time_with_events<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        events: &mut [Event<'_, Self::State>],
+    ) -> (f64, Vec<EventOccurrence<Self::State>>)
+    where
+        Self: DenseOutput,
+        Sy: NonautonomousOde<State = Self::State>,
+    {
+        adaptive::integrate_until_with_events(self, system, state, t, events)
+    }
+
+    fn try_integrate_n_steps<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+    ) -> Result<f64, Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        self.try_integrate_n_accepted(system, state, n)
+    }
+
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: NonautonomousOde<State = Self::State>,
+        Self::State: Componentwise,
+    {
+        self.try_integrate_until(system, state, t)
+    }
+}
+
+impl<C> DenseOutput for DormandPrince5<f64, C>
+where
+    C: StepSizeController,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut f64) {
+        let [w1, w3, w4, w5, w6, w7] = self.dense_weights(theta);
+        *out = self.temp
+            + w1 * self.k1
+            + w3 * self.k3
+            + w4 * self.k4
+            + w5 * self.k5
+            + w6 * self.k6
+            + w7 * self.k7;
+    }
+}
+
+impl<D, P: ZipMarker, C> DenseOutput for DormandPrince5<P, C>
+where
+    C: StepSizeController,
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let [w1, w3, w4, w5, w6, w7] = self.dense_weights(theta);
+
+        // Zip takes at most six producers, so the stages are summed up in two parts.
+        Zip::from(&mut *out)
+            .and(&self.temp)
+            .and(&self.k1)
+            .and(&self.k3)
+            .and(&self.k4)
+            .and(&self.k5)
+            .apply(|out, &x1, &x_k1, &x_k3, &x_k4, &x_k5| {
+                *out = x1 + w1 * x_k1 + w3 * x_k3 + w4 * x_k4 + w5 * x_k5
+            });
+
+        Zip::from(out)
+            .and(&self.k6)
+            .and(&self.k7)
+            .apply(|out, &x_k6, &x_k7| *out += w6 * x_k6 + w7 * x_k7);
+    }
+}
@@ -1,14 +1,20 @@
 use ndarray::{Dimension, IntoNdProducer, Zip};
 use std::fmt::Debug;
 
-use crate::ode::Ode;
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
 
-use super::{Stepper, ZipMarker};
+use super::{DenseOutput, Stepper, ZipMarker};
 
 pub struct Euler<T: Debug> {
     pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
 
     pub(crate) temp: T,
+    pub(crate) k1: T,
+
+    pub(crate) stats: Stats,
 }
 
 impl<T> Euler<T>
@@ -17,13 +23,27 @@ where
 {
     pub fn new(state: &T, dt: f64) -> Self {
         let temp = state.clone();
+        let k1 = state.clone();
 
-        Euler { dt: dt, temp: temp }
+        Euler {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+            temp,
+            k1,
+            stats: Stats::new(),
+        }
     }
 
     fn timestep(&self) -> f64 {
         self.dt
     }
+
+    /// The weight of `k1` in the interpolant `x1 + w1 * k1` of the last step, the straight line
+    /// from its beginning to its end.
+    pub(crate) fn dense_weight(&self, theta: f64) -> f64 {
+        (theta - 1.0) * self.last_dt
+    }
 }
 
 impl Stepper for Euler<f64> {
@@ -31,16 +51,41 @@ impl Stepper for Euler<f64> {
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = f64>,
+        Sy: NonautonomousOde<State = f64>,
     {
-        system.differentiate_into(state, &mut self.temp);
-        self.temp = *state + &(self.dt * &self.temp);
+        system.differentiate_at_into(self.t, state, &mut self.k1);
+        self.temp = *state + self.dt * self.k1;
         system.update_state(state, &self.temp);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(self.dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
 }
 
 impl<D, P: ZipMarker> Stepper for Euler<P>
@@ -54,19 +99,76 @@ where
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = P>,
+        Sy: NonautonomousOde<State = P>,
     {
         let dt = self.dt;
-        system.differentiate_into(state, &mut self.temp);
+        system.differentiate_at_into(self.t, state, &mut self.k1);
 
         Zip::from(&mut self.temp)
             .and(&*state)
-            .apply(|next_x, &x| *next_x = x + dt * *next_x);
+            .and(&self.k1)
+            .apply(|next_x, &x, &x_k1| *next_x = x + dt * x_k1);
 
         system.update_state(state, &self.temp);
+        self.last_dt = dt;
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl DenseOutput for Euler<f64> {
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut f64) {
+        *out = self.temp + self.dense_weight(theta) * self.k1;
+    }
+}
+
+impl<D, P: ZipMarker> DenseOutput for Euler<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let w1 = self.dense_weight(theta);
+
+        Zip::from(out)
+            .and(&self.temp)
+            .and(&self.k1)
+            .apply(|out, &x1, &x_k1| *out = x1 + w1 * x_k1);
+    }
 }
@@ -0,0 +1,203 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use rand::Rng;
+use std::fmt::Debug;
+
+use crate::noise_process::NoiseProcess;
+use crate::sde::{check_noise_process, Noise, Sde};
+use crate::stats::Stats;
+use crate::tolerance::Componentwise;
+
+use super::{SdeStepper, ZipMarker};
+
+/// The Euler–Maruyama method, x_{n+1} = x_n + f(t_n, x_n) dt + g(t_n, x_n) ΔW_n.
+///
+/// Strong order 1/2 and weak order 1, for any structure of the noise.
+///
+/// The Wiener increments are drawn from a `NoiseProcess` with one component per Wiener process,
+/// over the absolute step size and from the time of the noise process, which advances with every
+/// step.
+pub struct EulerMaruyama<T: Debug, W, R> {
+    pub(crate) dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) noise_process: NoiseProcess<W, R>,
+    pub(crate) dw: Vec<f64>,
+
+    pub(crate) temp: T,
+    pub(crate) diffusion: Vec<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T, W, R> EulerMaruyama<T, W, R>
+where
+    T: Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    /// Create a new stepper drawing the Wiener increments from `noise_process`.
+    pub fn new(state: &T, dt: f64, noise_process: NoiseProcess<W, R>) -> Self {
+        let temp = state.clone();
+        let diffusion = vec![state.clone()];
+
+        EulerMaruyama {
+            dt,
+            t: 0.0,
+
+            noise_process,
+            dw: Vec::new(),
+
+            temp,
+            diffusion,
+
+            stats: Stats::new(),
+        }
+    }
+
+    pub fn noise_process(&self) -> &NoiseProcess<W, R> {
+        &self.noise_process
+    }
+
+    /// The noise process, e.g. to replay or clear its realization.
+    pub fn noise_process_mut(&mut self) -> &mut NoiseProcess<W, R> {
+        &mut self.noise_process
+    }
+}
+
+impl<W, R> SdeStepper for EulerMaruyama<f64, W, R>
+where
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = f64>,
+    {
+        let dt = self.dt;
+
+        let noise = system.noise();
+        self.noise_process
+            .increment_components(dt.abs(), &mut self.dw, None);
+        check_noise_process("EulerMaruyama", noise, state, self.dw.len());
+
+        self.diffusion.resize(noise.columns(), 0.0);
+        system.differentiate_at_into(self.t, state, &mut self.temp);
+        system.diffusion_into(self.t, state, &mut self.diffusion);
+
+        self.temp = *state + dt * self.temp;
+        for (g, dw) in self.diffusion.iter().zip(&self.dw) {
+            self.temp += g * dw;
+        }
+
+        system.update_state(state, &self.temp);
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<D, P: ZipMarker, W, R> SdeStepper for EulerMaruyama<P, W, R>
+where
+    P: Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = P>,
+    {
+        let dt = self.dt;
+        let t = self.t;
+
+        let noise = system.noise();
+        self.noise_process
+            .increment_components(dt.abs(), &mut self.dw, None);
+        check_noise_process("EulerMaruyama", noise, &*state, self.dw.len());
+
+        self.diffusion.resize(noise.columns(), state.clone());
+        system.differentiate_at_into(t, state, &mut self.temp);
+        system.diffusion_into(t, state, &mut self.diffusion);
+
+        Zip::from(&mut self.temp)
+            .and(&*state)
+            .apply(|next_x, &x| *next_x = x + dt * *next_x);
+
+        match noise {
+            Noise::Diagonal => {
+                let mut dw = self.dw.iter();
+                Zip::from(&mut self.temp)
+                    .and(&self.diffusion[0])
+                    .apply(|next_x, &g| *next_x += g * dw.next().unwrap());
+            }
+            Noise::Scalar | Noise::General(_) => {
+                for (g, &dw) in self.diffusion.iter().zip(&self.dw) {
+                    Zip::from(&mut self.temp)
+                        .and(g)
+                        .apply(|next_x, &g| *next_x += g * dw);
+                }
+            }
+        }
+
+        system.update_state(state, &self.temp);
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
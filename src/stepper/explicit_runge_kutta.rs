@@ -0,0 +1,248 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
+
+use super::{ButcherTableau, DenseOutput, Stepper, ZipMarker};
+
+/// An explicit Runge-Kutta method defined by its Butcher tableau.
+///
+/// This trades some performance for flexibility: the stages are accumulated one at a time, while
+/// the dedicated steppers like `RungeKutta4` fuse them.
+#[derive(Debug)]
+pub struct ExplicitRungeKutta<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) tableau: ButcherTableau,
+
+    pub(crate) temp: T,
+    pub(crate) k: Vec<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T> ExplicitRungeKutta<T>
+where
+    T: Clone + Debug,
+{
+    pub fn new(state: &T, dt: f64, tableau: ButcherTableau) -> Self {
+        let temp = state.clone();
+        let k = vec![state.clone(); tableau.stages()];
+
+        ExplicitRungeKutta {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            tableau,
+
+            temp,
+            k,
+
+            stats: Stats::new(),
+        }
+    }
+
+    pub fn tableau(&self) -> &ButcherTableau {
+        &self.tableau
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    /// The weight of the stage `k_i` in the interpolant `x1 + Σ w_i * k_i` of the last step.
+    ///
+    /// The interpolant is the quadratic polynomial through the states at the beginning and the
+    /// end of the step with the slope `k_1` at the beginning, which needs no further evaluations
+    /// of the right-hand side for any tableau. The state at the beginning is recovered as
+    /// `x0 = x1 - dt * Σ b_i * k_i`.
+    pub(crate) fn dense_weight(&self, theta: f64, i: usize) -> f64 {
+        let first = if i == 0 { theta - theta * theta } else { 0.0 };
+        self.last_dt * ((theta * theta - 1.0) * self.tableau.b[i] + first)
+    }
+}
+
+impl Stepper for ExplicitRungeKutta<f64> {
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: NonautonomousOde<State = f64>,
+    {
+        let dt = self.dt;
+        let t = self.t;
+        let ButcherTableau { a, b, c } = &self.tableau;
+
+        system.differentiate_at_into(t, state, &mut self.k[0]);
+
+        for i in 1..self.k.len() {
+            self.temp = *state;
+            for j in 0..i {
+                self.temp += dt * a[(i, j)] * self.k[j];
+            }
+            system.differentiate_at_into(t + c[i] * dt, &self.temp, &mut self.k[i]);
+        }
+
+        self.temp = *state;
+        for (b_i, k_i) in b.iter().zip(&self.k) {
+            self.temp += dt * b_i * k_i;
+        }
+
+        system.update_state(state, &self.temp);
+        self.last_dt = dt;
+        self.t += dt;
+
+        self.stats.rhs_evaluations += self.k.len();
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.timestep()
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<D, P: ZipMarker> Stepper for ExplicitRungeKutta<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: NonautonomousOde<State = P>,
+    {
+        let dt = self.dt;
+        let t = self.t;
+        let ButcherTableau { a, b, c } = &self.tableau;
+
+        system.differentiate_at_into(t, state, &mut self.k[0]);
+
+        for i in 1..self.k.len() {
+            Zip::from(&mut self.temp)
+                .and(&*state)
+                .apply(|next_x, &x| *next_x = x);
+
+            for j in 0..i {
+                let dt_a = dt * a[(i, j)];
+                if dt_a != 0.0 {
+                    Zip::from(&mut self.temp)
+                        .and(&self.k[j])
+                        .apply(|next_x, &x_k| *next_x += dt_a * x_k);
+                }
+            }
+
+            system.differentiate_at_into(t + c[i] * dt, &self.temp, &mut self.k[i]);
+        }
+
+        Zip::from(&mut self.temp)
+            .and(&*state)
+            .apply(|next_x, &x| *next_x = x);
+
+        for (b_i, k_i) in b.iter().zip(&self.k) {
+            let dt_b = dt * b_i;
+            if dt_b != 0.0 {
+                Zip::from(&mut self.temp)
+                    .and(k_i)
+                    .apply(|next_x, &x_k| *next_x += dt_b * x_k);
+            }
+        }
+
+        system.update_state(state, &self.temp);
+        self.last_dt = dt;
+        self.t += dt;
+
+        self.stats.rhs_evaluations += self.k.len();
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.timestep()
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl DenseOutput for ExplicitRungeKutta<f64> {
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut f64) {
+        *out = self.temp;
+        for (i, k_i) in self.k.iter().enumerate() {
+            *out += self.dense_weight(theta, i) * k_i;
+        }
+    }
+}
+
+impl<D, P: ZipMarker> DenseOutput for ExplicitRungeKutta<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        Zip::from(&mut *out)
+            .and(&self.temp)
+            .apply(|out, &x1| *out = x1);
+
+        for (i, k_i) in self.k.iter().enumerate() {
+            let w_i = self.dense_weight(theta, i);
+            if w_i != 0.0 {
+                Zip::from(&mut *out)
+                    .and(k_i)
+                    .apply(|out, &x_k| *out += w_i * x_k);
+            }
+        }
+    }
+}
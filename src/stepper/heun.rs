@@ -1,17 +1,22 @@
 use ndarray::{Dimension, IntoNdProducer, Zip};
 use std::fmt::Debug;
 
-use crate::ode::Ode;
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
 
-use super::{Stepper, ZipMarker};
+use super::{hermite_basis, DenseOutput, Stepper, ZipMarker};
 
 pub struct Heun<T: Debug> {
     pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
     pub(crate) dt_2: f64,
+    pub(crate) t: f64,
 
     pub(crate) temp: T,
     pub(crate) k1: T,
     pub(crate) k2: T,
+
+    pub(crate) stats: Stats,
 }
 
 impl<T> Heun<T>
@@ -26,18 +31,39 @@ where
         let dt_2 = dt / 2.0;
 
         Heun {
-            dt: dt,
-            dt_2: dt_2,
+            dt,
+            last_dt: 0.0,
+            dt_2,
+            t: 0.0,
+
+            temp,
+            k1,
+            k2,
 
-            temp: temp,
-            k1: k1,
-            k2: k2,
+            stats: Stats::new(),
         }
     }
 
     fn timestep(&self) -> f64 {
         self.dt
     }
+
+    pub(crate) fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+        self.dt_2 = dt / 2.0;
+    }
+
+    /// The weights of `k1` and `k2` in the interpolant `x1 + w1 * k1 + w2 * k2` of the last step.
+    ///
+    /// The interpolant is the cubic Hermite polynomial through the states at the beginning and
+    /// the end of the step, with the slopes `k1` and `k2`. The state at the beginning is
+    /// recovered as `x0 = x1 - dt / 2 * (k1 + k2)`.
+    pub(crate) fn dense_weights(&self, theta: f64) -> (f64, f64) {
+        let (h00, h10, _, h11) = hermite_basis(theta);
+        let dt = self.last_dt;
+
+        (dt * (h10 - h00 / 2.0), dt * (h11 - h00 / 2.0))
+    }
 }
 
 impl Stepper for Heun<f64> {
@@ -45,17 +71,44 @@ impl Stepper for Heun<f64> {
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = f64>,
+        Sy: NonautonomousOde<State = f64>,
     {
-        system.differentiate_into(state, &mut self.k1);
-        system.differentiate_into(&(*state + &(self.dt * &self.k1)), &mut self.k2);
-        self.temp = *state + &(self.dt_2 * &self.k1) + &(self.dt_2 * &self.k2);
+        let t = self.t;
+
+        system.differentiate_at_into(t, state, &mut self.k1);
+        system.differentiate_at_into(t + self.dt, &(*state + self.dt * self.k1), &mut self.k2);
+        self.temp = *state + self.dt_2 * self.k1 + self.dt_2 * self.k2;
         system.update_state(state, &self.temp);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+
+        self.stats.rhs_evaluations += 2;
+        self.stats.accept(self.dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.set_timestep(dt);
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
 }
 
 impl<D, P: ZipMarker> Stepper for Heun<P>
@@ -69,19 +122,20 @@ where
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = P>,
+        Sy: NonautonomousOde<State = P>,
     {
         let dt = self.dt;
         let dt_2 = self.dt_2;
+        let t = self.t;
 
-        system.differentiate_into(state, &mut self.k1);
+        system.differentiate_at_into(t, state, &mut self.k1);
 
         Zip::from(&mut self.temp)
             .and(&*state)
             .and(&self.k1)
             .apply(|next_x, &x, &x_k1| *next_x = x + dt * x_k1);
 
-        system.differentiate_into(&self.temp, &mut self.k2);
+        system.differentiate_at_into(t + dt, &self.temp, &mut self.k2);
 
         Zip::from(&mut self.temp)
             .and(&*state)
@@ -90,9 +144,67 @@ where
             .apply(|next_x, &x, &x_k1, &x_k2| *next_x = x + dt_2 * (x_k1 + x_k2));
 
         system.update_state(state, &self.temp);
+        self.last_dt = dt;
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 2;
+        self.stats.accept(dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.set_timestep(dt);
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl DenseOutput for Heun<f64> {
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut f64) {
+        let (w1, w2) = self.dense_weights(theta);
+        *out = self.temp + w1 * self.k1 + w2 * self.k2;
+    }
+}
+
+impl<D, P: ZipMarker> DenseOutput for Heun<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let (w1, w2) = self.dense_weights(theta);
+
+        Zip::from(out)
+            .and(&self.temp)
+            .and(&self.k1)
+            .and(&self.k2)
+            .apply(|out, &x1, &x_k1, &x_k2| *out = x1 + w1 * x_k1 + w2 * x_k2);
+    }
 }
@@ -0,0 +1,143 @@
+use ndarray::{IntoNdProducer, Ix1, Zip};
+use std::fmt::Debug;
+
+use crate::error::Error;
+use crate::jacobian::Jacobian;
+use crate::stats::Stats;
+
+use super::newton::Newton;
+use super::{ImplicitDenseOutput, ImplicitStepper, ZipMarker};
+
+/// The implicit midpoint rule, x_{n+1} = x_n + dt f(t_n + dt/2, (x_n + x_{n+1})/2).
+///
+/// Second order, A-stable and symplectic.
+#[derive(Debug)]
+pub struct ImplicitMidpoint<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) temp: T,
+
+    pub(crate) newton: Newton<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<P: ZipMarker> ImplicitMidpoint<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    pub fn new(state: &P, dt: f64) -> Self {
+        let temp = state.clone();
+        let newton = Newton::new(state);
+
+        ImplicitMidpoint {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            temp,
+
+            newton,
+
+            stats: Stats::new(),
+        }
+    }
+
+    /// Set the tolerance on the scaled Newton updates, `1e-10` by default.
+    pub fn with_newton_tolerance(mut self, tolerance: f64) -> Self {
+        self.newton.tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of Newton iterations per step, `20` by default.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.newton.max_iterations = max_iterations;
+        self
+    }
+}
+
+impl<P: ZipMarker> ImplicitStepper for ImplicitMidpoint<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = P>,
+    {
+        // Solve for the midpoint z = (x_n + x_{n+1})/2 = x_n + dt/2 f(t_n + dt/2, z).
+        self.newton.solve(
+            system,
+            self.t,
+            self.dt,
+            state,
+            0.5,
+            0.0,
+            0.5,
+            &mut self.stats,
+        )?;
+
+        Zip::from(&mut self.temp)
+            .and(&*state)
+            .and(&self.newton.z)
+            .apply(|next_x, &x, &z| *next_x = 2.0 * z - x);
+
+        system.update_state(state, &self.temp);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+        self.stats.accept(self.dt);
+        Ok(())
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<P: ZipMarker> ImplicitDenseOutput for ImplicitMidpoint<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    /// The straight line `x0 + theta dt f(t_n + dt/2, z)` through the midpoint `z`.
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let w = (theta - 1.0) * self.last_dt;
+
+        Zip::from(out)
+            .and(&self.temp)
+            .and(&self.newton.f)
+            .apply(|out, &x1, &f| *out = x1 + w * f);
+    }
+}
@@ -0,0 +1,221 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use rand::Rng;
+use std::fmt::Debug;
+
+use crate::noise_process::NoiseProcess;
+use crate::sde::{check_noise_process, Noise, Sde};
+use crate::stats::Stats;
+use crate::tolerance::Componentwise;
+
+use super::{SdeStepper, ZipMarker};
+
+/// The derivative free Milstein method of Kloeden and Platen,
+/// x_{n+1} = x_n + f dt + g ΔW_n + (g(t_n, x̄) - g) / (2 sqrt(dt)) (ΔW_n² - dt),
+/// with f and g evaluated at (t_n, x_n) and the support value x̄ = x_n + f dt + g sqrt(dt).
+///
+/// Strong and weak order 1. Only diagonal noise, where each `g_i` depends on `x_i` alone, and
+/// scalar noise are supported; other noise panics.
+///
+/// The Wiener increments are drawn from a `NoiseProcess` as for `EulerMaruyama`.
+pub struct Milstein<T: Debug, W, R> {
+    pub(crate) dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) noise_process: NoiseProcess<W, R>,
+    pub(crate) dw: Vec<f64>,
+
+    pub(crate) temp: T,
+    pub(crate) drift: T,
+    pub(crate) diffusion: Vec<T>,
+    pub(crate) support_diffusion: Vec<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T, W, R> Milstein<T, W, R>
+where
+    T: Componentwise + Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    /// Create a new stepper drawing the Wiener increments from `noise_process`.
+    pub fn new(state: &T, dt: f64, noise_process: NoiseProcess<W, R>) -> Self {
+        let temp = state.clone();
+        let drift = state.clone();
+        let diffusion = vec![state.clone()];
+        let support_diffusion = vec![state.clone()];
+
+        Milstein {
+            dt,
+            t: 0.0,
+
+            noise_process,
+            dw: Vec::new(),
+
+            temp,
+            drift,
+            diffusion,
+            support_diffusion,
+
+            stats: Stats::new(),
+        }
+    }
+
+    pub fn noise_process(&self) -> &NoiseProcess<W, R> {
+        &self.noise_process
+    }
+
+    /// The noise process, e.g. to replay or clear its realization.
+    pub fn noise_process_mut(&mut self) -> &mut NoiseProcess<W, R> {
+        &mut self.noise_process
+    }
+
+    /// Draw the Wiener increments over the step into `dw`.
+    fn draw_increments(&mut self, noise: Noise, state: &T) {
+        if let Noise::General(_) = noise {
+            panic!("Milstein: only diagonal and scalar noise are supported");
+        }
+        self.noise_process
+            .increment_components(self.dt.abs(), &mut self.dw, None);
+        check_noise_process("Milstein", noise, state, self.dw.len());
+    }
+}
+
+impl<W, R> SdeStepper for Milstein<f64, W, R>
+where
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = f64>,
+    {
+        let dt = self.dt;
+        let sqrt_dt = dt.abs().sqrt();
+        let t = self.t;
+
+        self.draw_increments(system.noise(), state);
+        let dw = self.dw[0];
+
+        let x = *state;
+        system.differentiate_at_into(t, state, &mut self.drift);
+        system.diffusion_into(t, state, &mut self.diffusion);
+        let (f, g) = (self.drift, self.diffusion[0]);
+
+        self.temp = x + f * dt + g * sqrt_dt;
+        system.diffusion_into(t, &self.temp, &mut self.support_diffusion);
+        let g_support = self.support_diffusion[0];
+
+        self.temp = x + f * dt + g * dw + (g_support - g) / (2.0 * sqrt_dt) * (dw * dw - dt.abs());
+
+        system.update_state(state, &self.temp);
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<D, P: ZipMarker, W, R> SdeStepper for Milstein<P, W, R>
+where
+    P: Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = P>,
+    {
+        let dt = self.dt;
+        let sqrt_dt = dt.abs().sqrt();
+        let t = self.t;
+
+        let noise = system.noise();
+        self.draw_increments(noise, state);
+
+        system.differentiate_at_into(t, state, &mut self.drift);
+        system.diffusion_into(t, state, &mut self.diffusion);
+
+        Zip::from(&mut self.temp)
+            .and(&*state)
+            .and(&self.drift)
+            .and(&self.diffusion[0])
+            .apply(|support, &x, &f, &g| *support = x + f * dt + g * sqrt_dt);
+
+        system.diffusion_into(t, &self.temp, &mut self.support_diffusion);
+
+        // Under scalar noise, all components share the one increment.
+        let mut dw = self.dw.iter().cycle();
+        Zip::from(&mut self.temp)
+            .and(&*state)
+            .and(&self.drift)
+            .and(&self.diffusion[0])
+            .and(&self.support_diffusion[0])
+            .apply(|next_x, &x, &f, &g, &g_support| {
+                let dw = *dw.next().unwrap();
+                *next_x =
+                    x + f * dt + g * dw + (g_support - g) / (2.0 * sqrt_dt) * (dw * dw - dt.abs());
+            });
+
+        system.update_state(state, &self.temp);
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 1;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
@@ -0,0 +1,163 @@
+use ndarray::prelude::*;
+use ndarray::{FoldWhile, IntoNdProducer, Zip};
+use std::error;
+use std::fmt;
+
+use crate::error::Error;
+use crate::jacobian::Jacobian;
+use crate::linalg::Lu;
+use crate::stats::Stats;
+
+use super::ZipMarker;
+
+/// The failure of a Newton iteration solving the equations of an implicit method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NewtonError {
+    /// The iteration matrix `I - γ dt J` is singular.
+    SingularMatrix,
+    /// The iteration did not converge within the maximum number of iterations; `increment` is
+    /// the scaled size of the last Newton update.
+    NotConverged { iterations: usize, increment: f64 },
+}
+
+impl fmt::Display for NewtonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewtonError::SingularMatrix => write!(f, "singular Newton iteration matrix"),
+            NewtonError::NotConverged {
+                iterations,
+                increment,
+            } => write!(
+                f,
+                "Newton iteration did not converge after {} iterations (last increment {:e})",
+                iterations, increment
+            ),
+        }
+    }
+}
+
+impl error::Error for NewtonError {}
+
+/// A simplified Newton iteration for the stage equation `z = x + dt (w0 f0 + w f(t + c dt, z))`
+/// shared by the implicit steppers.
+///
+/// The Jacobian is evaluated once per solve at the initial state `x`.
+#[derive(Debug)]
+pub(crate) struct Newton<T> {
+    pub(crate) tolerance: f64,
+    pub(crate) max_iterations: usize,
+
+    pub(crate) z: T,
+    pub(crate) f: T,
+    pub(crate) f0: T,
+
+    pub(crate) jacobian: Array2<f64>,
+    pub(crate) lu: Lu,
+    pub(crate) delta: Array1<f64>,
+}
+
+impl<P: ZipMarker> Newton<P>
+where
+    P: Clone,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    pub(crate) fn new(state: &P) -> Self {
+        let n = Zip::from(state)
+            .fold_while(0, |n, _| FoldWhile::Continue(n + 1))
+            .into_inner();
+
+        Newton {
+            tolerance: 1e-10,
+            max_iterations: 20,
+
+            z: state.clone(),
+            f: state.clone(),
+            f0: state.clone(),
+
+            jacobian: Array2::zeros((n, n)),
+            lu: Lu::new(n),
+            delta: Array1::zeros(n),
+        }
+    }
+
+    /// Solve for the stage `z`, which is left in `self.z`. The derivative `f0` has to be set
+    /// beforehand if `w0 != 0`. The work done is counted in `stats`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn solve<Sy>(
+        &mut self,
+        system: &mut Sy,
+        t: f64,
+        dt: f64,
+        x: &P,
+        c: f64,
+        w0: f64,
+        w: f64,
+        stats: &mut Stats,
+    ) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = P>,
+    {
+        let t_stage = t + c * dt;
+        let gamma = w * dt;
+
+        Zip::from(&mut self.z).and(x).apply(|z, &x| *z = x);
+
+        stats.jacobian_evaluations += 1;
+        stats.rhs_evaluations += system.jacobian_rhs_evaluations(self.jacobian.rows());
+        system.try_jacobian_into(t_stage, x, &mut self.jacobian)?;
+
+        Zip::indexed(&mut self.lu.lu)
+            .and(&self.jacobian)
+            .apply(|(i, j), m, &jac| {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                *m = identity - gamma * jac;
+            });
+
+        stats.lu_factorizations += 1;
+        if !self.lu.factorize() {
+            return Err(NewtonError::SingularMatrix.into());
+        }
+
+        let mut increment = f64::INFINITY;
+
+        for _ in 0..self.max_iterations {
+            stats.newton_iterations += 1;
+            stats.rhs_evaluations += 1;
+            system.try_differentiate_at_into(t_stage, &self.z, &mut self.f)?;
+
+            Zip::from(&mut self.delta)
+                .and(&self.z)
+                .and(x)
+                .and(&self.f0)
+                .and(&self.f)
+                .apply(|d, &z, &x, &f0, &f| *d = x + dt * (w0 * f0 + w * f) - z);
+
+            self.lu.solve_into(&mut self.delta);
+
+            increment = Zip::from(&mut self.z)
+                .and(&self.delta)
+                .fold_while(0.0, |max: f64, z, &d| {
+                    *z += d;
+                    FoldWhile::Continue(max.max(d.abs() / (1.0 + z.abs())))
+                })
+                .into_inner();
+
+            // f64::max ignores NaN, so check the updates themselves.
+            if self.delta.iter().any(|d| !d.is_finite()) {
+                increment = f64::NAN;
+                break;
+            }
+
+            if increment <= self.tolerance {
+                return Ok(());
+            }
+        }
+
+        Err(NewtonError::NotConverged {
+            iterations: self.max_iterations,
+            increment,
+        }
+        .into())
+    }
+}
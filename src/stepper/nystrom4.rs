@@ -0,0 +1,173 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::second_order::SecondOrderOde;
+use crate::stats::Stats;
+
+use super::{RknStepper, ZipMarker};
+
+/// The classical Runge–Kutta–Nyström method of order 4 for second-order ODEs
+/// d²x/dt² = f(t, x), with three evaluations of the acceleration per step,
+///
+///   k1 = f(t, x)
+///   k2 = f(t + dt/2, x + dt/2 v + dt²/8 k1)
+///   k3 = f(t + dt, x + dt v + dt²/2 k2)
+///   x1 = x + dt v + dt² (k1 + 2 k2) / 6
+///   v1 = v + dt (k1 + 4 k2 + k3) / 6
+///
+/// Integrating the second-order system directly saves a quarter of the evaluations of `RungeKutta4`
+/// applied to the equivalent first-order system of twice the size.
+#[derive(Debug)]
+pub struct Nystrom4<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) temp: T,
+
+    pub(crate) k1: T,
+    pub(crate) k2: T,
+    pub(crate) k3: T,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T> Nystrom4<T>
+where
+    T: Clone + Debug,
+{
+    pub fn new(state: &T, dt: f64) -> Self {
+        Nystrom4 {
+            dt,
+            t: 0.0,
+
+            temp: state.clone(),
+
+            k1: state.clone(),
+            k2: state.clone(),
+            k3: state.clone(),
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl RknStepper for Nystrom4<f64> {
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut f64, velocity: &mut f64)
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        let t = self.t;
+        let dt = self.dt;
+        let (x, v) = (*position, *velocity);
+
+        system.acceleration_into(t, &x, &mut self.k1);
+        self.temp = x + dt / 2.0 * v + dt * dt / 8.0 * self.k1;
+        system.acceleration_into(t + dt / 2.0, &self.temp, &mut self.k2);
+        self.temp = x + dt * v + dt * dt / 2.0 * self.k2;
+        system.acceleration_into(t + dt, &self.temp, &mut self.k3);
+
+        *position = x + dt * v + dt * dt / 6.0 * (self.k1 + 2.0 * self.k2);
+        *velocity = v + dt / 6.0 * (self.k1 + 4.0 * self.k2 + self.k3);
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 3;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<D, P: ZipMarker> RknStepper for Nystrom4<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut P, velocity: &mut P)
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        let t = self.t;
+        let dt = self.dt;
+        let dt2 = dt * dt;
+
+        system.acceleration_into(t, position, &mut self.k1);
+        Zip::from(&mut self.temp)
+            .and(&*position)
+            .and(&*velocity)
+            .and(&self.k1)
+            .apply(|y, &x, &v, &k1| *y = x + dt / 2.0 * v + dt2 / 8.0 * k1);
+        system.acceleration_into(t + dt / 2.0, &self.temp, &mut self.k2);
+        Zip::from(&mut self.temp)
+            .and(&*position)
+            .and(&*velocity)
+            .and(&self.k2)
+            .apply(|y, &x, &v, &k2| *y = x + dt * v + dt2 / 2.0 * k2);
+        system.acceleration_into(t + dt, &self.temp, &mut self.k3);
+
+        Zip::from(position)
+            .and(velocity)
+            .and(&self.k1)
+            .and(&self.k2)
+            .and(&self.k3)
+            .apply(|x, v, &k1, &k2, &k3| {
+                *x += dt * *v + dt2 / 6.0 * (k1 + 2.0 * k2);
+                *v += dt / 6.0 * (k1 + 4.0 * k2 + k3);
+            });
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 3;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
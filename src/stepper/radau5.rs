@@ -0,0 +1,636 @@
+use ndarray::prelude::*;
+use ndarray::{s, FoldWhile, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::controller::{IController, StepSizeController};
+use crate::error::Error;
+use crate::jacobian::Jacobian;
+use crate::linalg::Lu;
+use crate::mass_matrix::MassMatrix;
+use crate::observer::Observer;
+use crate::stats::Stats;
+use crate::tolerance::{ErrorControl, ErrorNorm, Tolerance};
+
+use super::adaptive::{self, Adaptive, StepControl};
+use super::newton::NewtonError;
+use super::{MassMatrixStepper, ZipMarker};
+
+/// The nodes and the coefficients of the 3-stage Radau IIA method.
+fn tableau() -> ([f64; 3], [[f64; 3]; 3]) {
+    let sqrt6 = 6f64.sqrt();
+    let c = [(4.0 - sqrt6) / 10.0, (4.0 + sqrt6) / 10.0, 1.0];
+    let a = [
+        [
+            (88.0 - 7.0 * sqrt6) / 360.0,
+            (296.0 - 169.0 * sqrt6) / 1800.0,
+            (-2.0 + 3.0 * sqrt6) / 225.0,
+        ],
+        [
+            (296.0 + 169.0 * sqrt6) / 1800.0,
+            (88.0 + 7.0 * sqrt6) / 360.0,
+            (-2.0 - 3.0 * sqrt6) / 225.0,
+        ],
+        [(16.0 - sqrt6) / 36.0, (16.0 + sqrt6) / 36.0, 1.0 / 9.0],
+    ];
+    (c, a)
+}
+
+/// The weights of the stages and the factor `γ0` of the error estimate of `RADAU5`, see Hairer,
+/// Wanner: Solving Ordinary Differential Equations II, Section IV.8.
+fn error_coefficients() -> ([f64; 3], f64) {
+    let sqrt6 = 6f64.sqrt();
+    let e = [
+        -(13.0 + 7.0 * sqrt6) / 3.0,
+        (-13.0 + 7.0 * sqrt6) / 3.0,
+        -1.0 / 3.0,
+    ];
+    let gamma0 = 30.0 / (6.0 + 81f64.cbrt() - 9f64.cbrt());
+    (e, gamma0)
+}
+
+/// The adaptive Radau IIA method of order 5 for differential-algebraic equations
+/// M(t) dx/dt = f(t, x) of index 1, after the `RADAU5` code of Hairer and Wanner.
+///
+/// The three stages are solved for together by a simplified Newton iteration with the Jacobian
+/// at the beginning of the step and a real LU decomposition of the `3n x 3n` iteration matrix.
+/// The mass matrix is taken at the beginning of each step. If the Newton iteration fails, the
+/// step is retried with half the step size. The local error is estimated as in `RADAU5` and
+/// controlled like that of `DormandPrince5`.
+///
+/// Being stiffly accurate and L-stable, the method also suits stiff ODEs, for which the mass
+/// matrix is the identity. Start a DAE from consistent initial conditions, which
+/// `make_consistent` computes for the algebraic variables.
+#[derive(Debug)]
+pub struct Radau5<T: Debug, C = IController> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) error_control: ErrorControl,
+    pub(crate) controller: C,
+
+    pub(crate) newton_tolerance: f64,
+    pub(crate) max_iterations: usize,
+
+    pub(crate) stage: T,
+    pub(crate) f: T,
+
+    pub(crate) x0: Array1<f64>,
+    pub(crate) x1: Array1<f64>,
+    pub(crate) f0: Array1<f64>,
+    pub(crate) z: Array1<f64>,
+    pub(crate) fz: Array1<f64>,
+    pub(crate) delta: Array1<f64>,
+    pub(crate) err: Array1<f64>,
+    pub(crate) mass_err: Array1<f64>,
+
+    pub(crate) mass: Array2<f64>,
+    pub(crate) jacobian: Array2<f64>,
+    pub(crate) newton_lu: Lu,
+    pub(crate) error_lu: Lu,
+
+    // Whether no step has been accepted yet.
+    pub(crate) first: bool,
+
+    pub(crate) stats: Stats,
+}
+
+impl<P: ZipMarker> Radau5<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    /// Create a new stepper with initial step size `dt`, using an absolute tolerance of `1e-6`
+    /// and a relative tolerance of `1e-3`.
+    pub fn new(state: &P, dt: f64) -> Self {
+        let n = Zip::from(state)
+            .fold_while(0, |n, _| FoldWhile::Continue(n + 1))
+            .into_inner();
+
+        Radau5 {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            error_control: ErrorControl::new(1e-6, 1e-3),
+            controller: IController::new(),
+
+            newton_tolerance: 0.03,
+            max_iterations: 7,
+
+            stage: state.clone(),
+            f: state.clone(),
+
+            x0: Array1::zeros(n),
+            x1: Array1::zeros(n),
+            f0: Array1::zeros(n),
+            z: Array1::zeros(3 * n),
+            fz: Array1::zeros(3 * n),
+            delta: Array1::zeros(3 * n),
+            err: Array1::zeros(n),
+            mass_err: Array1::zeros(n),
+
+            mass: Array2::zeros((n, n)),
+            jacobian: Array2::zeros((n, n)),
+            newton_lu: Lu::new(3 * n),
+            error_lu: Lu::new(n),
+
+            first: true,
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl<T, C> Radau5<T, C>
+where
+    T: Clone + Debug,
+    C: StepSizeController,
+{
+    /// Replace the step size controller.
+    pub fn with_controller<C2>(self, controller: C2) -> Radau5<T, C2>
+    where
+        C2: StepSizeController,
+    {
+        Radau5 {
+            dt: self.dt,
+            last_dt: self.last_dt,
+            t: self.t,
+
+            error_control: self.error_control,
+            controller,
+
+            newton_tolerance: self.newton_tolerance,
+            max_iterations: self.max_iterations,
+
+            stage: self.stage,
+            f: self.f,
+
+            x0: self.x0,
+            x1: self.x1,
+            f0: self.f0,
+            z: self.z,
+            fz: self.fz,
+            delta: self.delta,
+            err: self.err,
+            mass_err: self.mass_err,
+
+            mass: self.mass,
+            jacobian: self.jacobian,
+            newton_lu: self.newton_lu,
+            error_lu: self.error_lu,
+
+            first: self.first,
+
+            stats: self.stats,
+        }
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Set the absolute and relative tolerances the local error is controlled against, either
+    /// for all components or per component.
+    pub fn with_tolerances<A, R>(mut self, atol: A, rtol: R) -> Self
+    where
+        A: Into<Tolerance>,
+        R: Into<Tolerance>,
+    {
+        self.error_control = ErrorControl::new(atol, rtol).with_norm(self.error_control.norm);
+        self
+    }
+
+    /// Set the norm the scaled errors of the components are combined with.
+    pub fn with_error_norm(mut self, norm: ErrorNorm) -> Self {
+        self.error_control.norm = norm;
+        self
+    }
+
+    /// Replace the whole error control.
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    pub fn error_control(&self) -> &ErrorControl {
+        &self.error_control
+    }
+
+    /// Set the tolerance on the Newton updates, scaled like the local error, `0.03` by default.
+    pub fn with_newton_tolerance(mut self, tolerance: f64) -> Self {
+        self.newton_tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of Newton iterations per attempted step, `7` by default.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// The step size of the last accepted step.
+    pub fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+}
+
+impl<T, C> Adaptive for Radau5<T, C>
+where
+    T: Debug,
+    C: StepSizeController,
+{
+    type Controller = C;
+
+    fn step_control(&mut self) -> StepControl<'_, C> {
+        StepControl {
+            t: &mut self.t,
+            dt: &mut self.dt,
+            last_dt: &mut self.last_dt,
+            controller: &mut self.controller,
+            stats: &mut self.stats,
+        }
+    }
+}
+
+impl<P: ZipMarker, C> Radau5<P, C>
+where
+    P: Clone + Debug,
+    C: StepSizeController,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    /// Make `state` consistent with the algebraic equations at the current time, by solving
+    /// them for the algebraic variables with Newton's method, keeping the other components.
+    ///
+    /// The algebraic equations are the zero rows of the mass matrix, and the algebraic variables
+    /// its zero columns. Only such semi-explicit DAEs are supported.
+    ///
+    /// Fails with `Error::SingularMassMatrix` if the zero rows and columns don't pair up, or if
+    /// the mass matrix without them is singular. Fails if the Newton iteration does not
+    /// converge.
+    pub fn make_consistent<Sy>(&mut self, system: &mut Sy, state: &mut P) -> Result<(), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+    {
+        let t = self.t;
+        system.mass_matrix_into(t, &mut self.mass);
+
+        let is_zero = |lane: ArrayView1<f64>| lane.iter().all(|&m| m == 0.0);
+        let equations: Vec<usize> = (0..self.mass.rows())
+            .filter(|&i| is_zero(self.mass.row(i)))
+            .collect();
+        let variables: Vec<usize> = (0..self.mass.cols())
+            .filter(|&j| is_zero(self.mass.column(j)))
+            .collect();
+        if equations.len() != variables.len() {
+            return Err(Error::SingularMassMatrix { time: t });
+        }
+
+        // The differential part of the mass matrix has to be regular.
+        let differential: Vec<usize> = (0..self.mass.rows())
+            .filter(|i| !equations.contains(i))
+            .collect();
+        let columns: Vec<usize> = (0..self.mass.cols())
+            .filter(|j| !variables.contains(j))
+            .collect();
+        let mut lu = Lu::new(differential.len());
+        for (k, &i) in differential.iter().enumerate() {
+            for (l, &j) in columns.iter().enumerate() {
+                lu.lu[(k, l)] = self.mass[(i, j)];
+            }
+        }
+        if !lu.factorize() {
+            return Err(Error::SingularMassMatrix { time: t });
+        }
+
+        let m = equations.len();
+        if m == 0 {
+            return Ok(());
+        }
+
+        let mut lu = Lu::new(m);
+        let mut delta = Array1::zeros(m);
+        let mut increment = f64::INFINITY;
+        for _ in 0..20 {
+            self.stats.rhs_evaluations += 1;
+            system.try_differentiate_at_into(t, state, &mut self.f)?;
+            self.stats.jacobian_evaluations += 1;
+            self.stats.rhs_evaluations += system.jacobian_rhs_evaluations(self.jacobian.rows());
+            system.try_jacobian_into(t, state, &mut self.jacobian)?;
+            self.stats.newton_iterations += 1;
+
+            load(self.x0.view_mut(), &self.f);
+            for (k, &i) in equations.iter().enumerate() {
+                delta[k] = -self.x0[i];
+                for (l, &j) in variables.iter().enumerate() {
+                    lu.lu[(k, l)] = self.jacobian[(i, j)];
+                }
+            }
+
+            self.stats.lu_factorizations += 1;
+            if !lu.factorize() {
+                return Err(NewtonError::SingularMatrix.into());
+            }
+            lu.solve_into(&mut delta);
+
+            load(self.x0.view_mut(), state);
+            increment = 0.0;
+            for (k, &j) in variables.iter().enumerate() {
+                self.x0[j] += delta[k];
+                increment = increment.max(delta[k].abs() / (1.0 + self.x0[j].abs()));
+            }
+            if !increment.is_finite() {
+                break;
+            }
+            store(state, self.x0.view());
+
+            if increment <= 1e-12 {
+                return Ok(());
+            }
+        }
+
+        Err(NewtonError::NotConverged {
+            iterations: 20,
+            increment,
+        }
+        .into())
+    }
+
+    /// Do one accepted step, retrying rejected attempts with smaller step sizes.
+    fn step<Sy>(&mut self, system: &mut Sy, state: &mut P) -> Result<(), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+    {
+        let n = self.x0.len();
+        let (c, a) = tableau();
+        let (e, gamma0) = error_coefficients();
+
+        let t = self.t;
+        load(self.x0.view_mut(), state);
+        system.mass_matrix_into(t, &mut self.mass);
+        self.stats.rhs_evaluations += 1;
+        system.try_differentiate_at_into(t, state, &mut self.f)?;
+        load(self.f0.view_mut(), &self.f);
+        self.stats.jacobian_evaluations += 1;
+        self.stats.rhs_evaluations += system.jacobian_rhs_evaluations(n);
+        system.try_jacobian_into(t, state, &mut self.jacobian)?;
+
+        let first = self.first;
+        let mut rejected = false;
+        loop {
+            let dt = self.dt;
+
+            // The iteration matrix I ⊗ M - dt A ⊗ J.
+            for (i, row) in a.iter().enumerate() {
+                for (j, &a_ij) in row.iter().enumerate() {
+                    let identity = if i == j { 1.0 } else { 0.0 };
+                    let mut block = self
+                        .newton_lu
+                        .lu
+                        .slice_mut(s![i * n..(i + 1) * n, j * n..(j + 1) * n]);
+                    Zip::from(&mut block)
+                        .and(&self.mass)
+                        .and(&self.jacobian)
+                        .apply(|k, &m, &jac| *k = identity * m - dt * a_ij * jac);
+                }
+            }
+            self.stats.lu_factorizations += 1;
+            if !self.newton_lu.factorize() || !self.solve_stages(system, dt, &c, &a)? {
+                self.retry(dt / 2.0)?;
+                rejected = true;
+                continue;
+            }
+
+            // The error estimate (γ0/dt M - J)^-1 (f0 + M (e1 z1 + e2 z2 + e3 z3) / dt).
+            Zip::from(&mut self.error_lu.lu)
+                .and(&self.mass)
+                .and(&self.jacobian)
+                .apply(|k, &m, &jac| *k = gamma0 / dt * m - jac);
+            self.stats.lu_factorizations += 1;
+            if !self.error_lu.factorize() {
+                self.retry(dt / 2.0)?;
+                rejected = true;
+                continue;
+            }
+
+            {
+                let z = &self.z;
+                Zip::indexed(&mut self.err).apply(|k, err| {
+                    *err = (e[0] * z[k] + e[1] * z[n + k] + e[2] * z[2 * n + k]) / dt;
+                });
+            }
+            self.mass_err.assign(&self.mass.dot(&self.err));
+            Zip::from(&mut self.err)
+                .and(&self.mass_err)
+                .and(&self.f0)
+                .apply(|err, &m, &f0| *err = m + f0);
+            self.error_lu.solve_into(&mut self.err);
+
+            Zip::from(&mut self.x1)
+                .and(&self.x0)
+                .and(self.z.slice(s![2 * n..]))
+                .apply(|x1, &x0, &z| *x1 = x0 + z);
+            let mut error = self.error_control.error(&self.err, &self.x0, &self.x1);
+
+            // Refine a large estimate of the first or a retried step with another evaluation,
+            // which damps the error of stiff components.
+            if error >= 1.0 && (first || rejected) {
+                Zip::from(&mut self.err)
+                    .and(&self.x0)
+                    .apply(|err, &x0| *err += x0);
+                store(&mut self.stage, self.err.view());
+                self.stats.rhs_evaluations += 1;
+                system.try_differentiate_at_into(t, &self.stage, &mut self.f)?;
+                load(self.err.view_mut(), &self.f);
+                Zip::from(&mut self.err)
+                    .and(&self.mass_err)
+                    .apply(|err, &m| *err += m);
+                self.error_lu.solve_into(&mut self.err);
+                error = self.error_control.error(&self.err, &self.x0, &self.x1);
+            }
+
+            // The error estimate is O(dt^4).
+            if self.adapt(error, 4)? {
+                self.first = false;
+                break;
+            }
+            rejected = true;
+        }
+
+        store(&mut self.stage, self.x1.view());
+        system.update_state(state, &self.stage);
+        Ok(())
+    }
+
+    /// Solve for the stage increments `z`, returning whether the Newton iteration converged.
+    fn solve_stages<Sy>(
+        &mut self,
+        system: &mut Sy,
+        dt: f64,
+        c: &[f64; 3],
+        a: &[[f64; 3]; 3],
+    ) -> Result<bool, Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+    {
+        let n = self.x0.len();
+        self.z.fill(0.0);
+
+        let mut previous: Option<f64> = None;
+        let mut factor = 1.0;
+        for _ in 0..self.max_iterations {
+            self.stats.newton_iterations += 1;
+            for (j, &c_j) in c.iter().enumerate() {
+                let z = self.z.slice(s![j * n..(j + 1) * n]);
+                Zip::from(&mut self.stage)
+                    .and(&self.x0)
+                    .and(z)
+                    .apply(|stage, &x0, &z| *stage = x0 + z);
+                self.stats.rhs_evaluations += 1;
+                system.try_differentiate_at_into(self.t + c_j * dt, &self.stage, &mut self.f)?;
+                load(self.fz.slice_mut(s![j * n..(j + 1) * n]), &self.f);
+            }
+
+            // The residuals dt Σ_j a_ij f(z_j) - M z_i.
+            for (i, a_i) in a.iter().enumerate() {
+                let mass_z = self.mass.dot(&self.z.slice(s![i * n..(i + 1) * n]));
+                let fz = &self.fz;
+                Zip::indexed(self.delta.slice_mut(s![i * n..(i + 1) * n]))
+                    .and(&mass_z)
+                    .apply(|k, d, &mz| {
+                        *d = dt * (a_i[0] * fz[k] + a_i[1] * fz[n + k] + a_i[2] * fz[2 * n + k])
+                            - mz;
+                    });
+            }
+            self.newton_lu.solve_into(&mut self.delta);
+            self.z += &self.delta;
+
+            // The scaled size of the update, and the estimated rate of convergence.
+            let mut size = 0f64;
+            for i in 0..3 {
+                let delta = self.delta.slice(s![i * n..(i + 1) * n]).to_owned();
+                size = size.max(self.error_control.error(&delta, &self.x0, &self.x0));
+            }
+            // f64::max ignores NaN, so check the updates themselves.
+            if !size.is_finite() || self.delta.iter().any(|d| !d.is_finite()) {
+                return Ok(false);
+            }
+            if let Some(previous) = previous {
+                let rate = size / previous;
+                if rate >= 0.99 {
+                    return Ok(false);
+                }
+                factor = rate / (1.0 - rate);
+            }
+            if factor * size <= self.newton_tolerance {
+                return Ok(true);
+            }
+            previous = Some(size);
+        }
+        Ok(false)
+    }
+}
+
+/// Copy the components of `state` into `into`.
+fn load<P>(into: ArrayViewMut1<f64>, state: &P)
+where
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+{
+    Zip::from(into).and(state).apply(|a, &x| *a = x);
+}
+
+/// Copy `from` into the components of `state`.
+fn store<P>(state: &mut P, from: ArrayView1<f64>)
+where
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    Zip::from(state).and(from).apply(|x, &a| *x = a);
+}
+
+impl<P: ZipMarker, C> MassMatrixStepper for Radau5<P, C>
+where
+    P: Clone + Debug,
+    C: StepSizeController,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    type State = P;
+
+    /// Do one accepted step, retrying rejected attempts with smaller step sizes.
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut P) -> Result<(), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+    {
+        self.step(system, state)
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    /// Do `n` accepted steps, returning the time integrated over.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut P,
+        n: usize,
+        observer: &mut O,
+    ) -> Result<f64, Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+        O: Observer<P>,
+    {
+        let mut tacc = 0f64;
+
+        observer.observe(self.t, state, 0);
+        for count in 1..=n {
+            self.step(system, state)?;
+            tacc += self.last_dt;
+            observer.observe(self.t, state, count);
+        }
+        Ok(tacc)
+    }
+
+    /// Integrate over exactly the duration `t`, truncating the last step.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut P,
+        t: f64,
+        observer: &mut O,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: MassMatrix + Jacobian<State = P>,
+        O: Observer<P>,
+    {
+        adaptive::integrate_until(
+            self,
+            state,
+            t,
+            |stepper, state| stepper.step(system, state),
+            |t, state, count| observer.observe(t, state, count),
+        )
+    }
+}
@@ -0,0 +1,621 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::controller::{IController, StepSizeController};
+use crate::error::Error;
+use crate::second_order::SecondOrderOde;
+use crate::stats::Stats;
+use crate::tolerance::{ErrorControl, ErrorNorm, Tolerance};
+
+use super::adaptive::{self, Adaptive, StepControl};
+use super::{RknStepper, ZipMarker};
+
+// Nodes of the stages; the last stage is evaluated at the new position.
+const C: [f64; 7] = [
+    0.0,
+    1.0 / 10.0,
+    1.0 / 5.0,
+    2.0 / 5.0,
+    3.0 / 5.0,
+    4.0 / 5.0,
+    1.0,
+];
+
+// Coefficients of the earlier stages in the positions of the stages 2 to 6.
+const A: [[f64; 5]; 5] = [
+    [1.0 / 200.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 150.0, 1.0 / 75.0, 0.0, 0.0, 0.0],
+    [2.0 / 75.0, 0.0, 4.0 / 75.0, 0.0, 0.0],
+    [9.0 / 200.0, 0.0, 9.0 / 100.0, 9.0 / 200.0, 0.0],
+    [
+        199.0 / 3600.0,
+        -19.0 / 150.0,
+        47.0 / 120.0,
+        -119.0 / 1200.0,
+        89.0 / 900.0,
+    ],
+];
+
+// Weights of the velocity, the closed Newton–Cotes rule on the nodes.
+const B: [f64; 7] = [
+    19.0 / 288.0,
+    0.0,
+    25.0 / 96.0,
+    25.0 / 144.0,
+    25.0 / 144.0,
+    25.0 / 96.0,
+    19.0 / 288.0,
+];
+
+// Weights of the position, b_i (1 - c_i); the last stage does not contribute.
+const B_BAR: [f64; 6] = [
+    19.0 / 288.0,
+    0.0,
+    5.0 / 24.0,
+    5.0 / 48.0,
+    5.0 / 72.0,
+    5.0 / 96.0,
+];
+
+// Differences between the weights of the velocity and those of the embedded 4th order solution
+// [11/72, 0, 0, 25/72, 25/72, 0, 11/72].
+const E: [f64; 7] = [
+    -25.0 / 288.0,
+    0.0,
+    25.0 / 96.0,
+    -25.0 / 144.0,
+    -25.0 / 144.0,
+    25.0 / 96.0,
+    -25.0 / 288.0,
+];
+
+// The same for the position, with the embedded weights b̂_i (1 - c_i).
+const E_BAR: [f64; 6] = [
+    -25.0 / 288.0,
+    0.0,
+    5.0 / 24.0,
+    -5.0 / 48.0,
+    -5.0 / 72.0,
+    5.0 / 96.0,
+];
+
+/// An adaptive embedded Runge–Kutta–Nyström method for second-order ODEs d²x/dt² = f(t, x),
+/// with a position of order 6 and a velocity of order 5.
+///
+/// The tableau is the crate's own construction rather than a published pair: seven stages on the
+/// nodes 0, 1/10, 1/5, 2/5, 3/5, 4/5 and 1, with the velocity integrated by the Newton–Cotes
+/// weights on these nodes. The local error is estimated from an embedded solution of order 4,
+/// whose velocity weights are [11/72, 0, 0, 25/72, 25/72, 0, 11/72]. It is measured for the
+/// position and the velocity separately as in `DormandPrince5`, and the larger of the two scaled
+/// errors is controlled. Each call to `do_step` performs exactly one accepted step, with the step
+/// sizes chosen by the `StepSizeController` `C`, by default an integral controller.
+///
+/// The acceleration at the new position is the last stage of a step. It is not reused as the
+/// first stage of the next step, since the position may be changed in between.
+#[derive(Debug)]
+pub struct Rkn64<T: Debug, C = IController> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) error_control: ErrorControl,
+    pub(crate) controller: C,
+
+    pub(crate) temp: T,
+    pub(crate) x1: T,
+    pub(crate) v1: T,
+    pub(crate) err_x: T,
+    pub(crate) err_v: T,
+
+    pub(crate) k: Vec<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T> Rkn64<T>
+where
+    T: Clone + Debug,
+{
+    /// Create a new stepper with initial step size `dt`, using an absolute tolerance of `1e-6`
+    /// and a relative tolerance of `1e-3`.
+    pub fn new(state: &T, dt: f64) -> Self {
+        Rkn64 {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            error_control: ErrorControl::new(1e-6, 1e-3),
+            controller: IController::new(),
+
+            temp: state.clone(),
+            x1: state.clone(),
+            v1: state.clone(),
+            err_x: state.clone(),
+            err_v: state.clone(),
+
+            k: vec![state.clone(); C.len()],
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl<T, C> Rkn64<T, C>
+where
+    T: Clone + Debug,
+    C: StepSizeController,
+{
+    /// Replace the step size controller.
+    pub fn with_controller<C2>(self, controller: C2) -> Rkn64<T, C2>
+    where
+        C2: StepSizeController,
+    {
+        Rkn64 {
+            dt: self.dt,
+            last_dt: self.last_dt,
+            t: self.t,
+
+            error_control: self.error_control,
+            controller,
+
+            temp: self.temp,
+            x1: self.x1,
+            v1: self.v1,
+            err_x: self.err_x,
+            err_v: self.err_v,
+
+            k: self.k,
+
+            stats: self.stats,
+        }
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Set the absolute and relative tolerances the local errors of the position and the
+    /// velocity are controlled against, either for all components or per component.
+    pub fn with_tolerances<A, R>(mut self, atol: A, rtol: R) -> Self
+    where
+        A: Into<Tolerance>,
+        R: Into<Tolerance>,
+    {
+        self.error_control = ErrorControl::new(atol, rtol).with_norm(self.error_control.norm);
+        self
+    }
+
+    /// Set the norm the scaled errors of the components are combined with.
+    pub fn with_error_norm(mut self, norm: ErrorNorm) -> Self {
+        self.error_control.norm = norm;
+        self
+    }
+
+    /// Replace the whole error control.
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    pub fn error_control(&self) -> &ErrorControl {
+        &self.error_control
+    }
+
+    /// The step size of the last accepted step.
+    pub fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+}
+
+impl<T, C> Adaptive for Rkn64<T, C>
+where
+    T: Debug,
+    C: StepSizeController,
+{
+    type Controller = C;
+
+    fn step_control(&mut self) -> StepControl<'_, C> {
+        StepControl {
+            t: &mut self.t,
+            dt: &mut self.dt,
+            last_dt: &mut self.last_dt,
+            controller: &mut self.controller,
+            stats: &mut self.stats,
+        }
+    }
+}
+
+impl<T, C> Rkn64<T, C>
+where
+    T: Clone + Debug,
+    C: StepSizeController,
+    Rkn64<T, C>: RknStepper<State = T>,
+{
+    /// Integrate over exactly the duration `t` by `try_do_step`, truncating the last step.
+    fn try_integrate_until<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut T,
+        velocity: &mut T,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: SecondOrderOde<State = T>,
+    {
+        adaptive::integrate_until(
+            self,
+            &mut (position, velocity),
+            t,
+            |stepper, (position, velocity)| stepper.try_do_step(system, position, velocity),
+            |_, _, _| {},
+        )
+    }
+
+    /// Do one accepted step by `step`, restoring the step size if it fails.
+    fn guarded<F>(&mut self, step: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self) -> Result<(), Error>,
+    {
+        let dt = self.dt;
+        let result = step(self);
+        if result.is_err() {
+            self.dt = dt;
+        }
+        result
+    }
+}
+
+impl<C> Rkn64<f64, C>
+where
+    C: StepSizeController,
+{
+    /// Do one accepted step, failing if the step size underflows.
+    pub(crate) fn step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut f64,
+        velocity: &mut f64,
+    ) -> Result<(), Error>
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        let t = self.t;
+        let (x, v) = (*position, *velocity);
+        system.acceleration_into(t, &x, &mut self.k[0]);
+        self.stats.rhs_evaluations += 1;
+
+        loop {
+            let dt = self.dt;
+            let dt2 = dt * dt;
+
+            for (i, a) in A.iter().enumerate() {
+                let mut acc = 0.0;
+                for (a_j, k_j) in a.iter().zip(&self.k[..=i]) {
+                    acc += a_j * k_j;
+                }
+                self.temp = x + C[i + 1] * dt * v + dt2 * acc;
+                system.acceleration_into(t + C[i + 1] * dt, &self.temp, &mut self.k[i + 1]);
+            }
+
+            let (mut dx, mut ex) = (0.0, 0.0);
+            for ((b, e), k) in B_BAR.iter().zip(&E_BAR).zip(&self.k) {
+                dx += b * k;
+                ex += e * k;
+            }
+            self.x1 = x + dt * v + dt2 * dx;
+            self.err_x = dt2 * ex;
+
+            system.acceleration_into(t + dt, &self.x1, &mut self.k[6]);
+
+            let (mut dv, mut ev) = (0.0, 0.0);
+            for ((b, e), k) in B.iter().zip(&E).zip(&self.k) {
+                dv += b * k;
+                ev += e * k;
+            }
+            self.v1 = v + dt * dv;
+            self.err_v = dt * ev;
+
+            self.stats.rhs_evaluations += 6;
+            let error_x = self.error_control.error(&self.err_x, &x, &self.x1);
+            let error_v = self.error_control.error(&self.err_v, &v, &self.v1);
+            let error = max_error(error_x, error_v);
+            // The error estimate of the embedded 4th order solution is O(dt^5).
+            if self.adapt(error, 5)? {
+                break;
+            }
+        }
+        *position = self.x1;
+        *velocity = self.v1;
+        Ok(())
+    }
+}
+
+impl<C> RknStepper for Rkn64<f64, C>
+where
+    C: StepSizeController,
+{
+    type State = f64;
+
+    /// Do one accepted step.
+    ///
+    /// Panics if the step size underflows, see `try_do_step`.
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut f64, velocity: &mut f64)
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        self.try_do_step(system, position, velocity)
+            .unwrap_or_else(|error| panic!("Rkn64: {}", error));
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut f64,
+        velocity: &mut f64,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        self.try_integrate_time(system, position, velocity, t)
+            .unwrap_or_else(|error| panic!("Rkn64: {}", error))
+    }
+
+    fn try_do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut f64,
+        velocity: &mut f64,
+    ) -> Result<(), Error>
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        self.guarded(|stepper| stepper.step(system, position, velocity))
+    }
+
+    /// Integrate over exactly the duration `t`, truncating the last step, and stopping at the
+    /// first failure.
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut f64,
+        velocity: &mut f64,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: SecondOrderOde<State = f64>,
+    {
+        self.try_integrate_until(system, position, velocity, t)
+    }
+}
+
+impl<D, P: ZipMarker, C> Rkn64<P, C>
+where
+    C: StepSizeController,
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    /// Do one accepted step, failing if the step size underflows.
+    pub(crate) fn step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut P,
+        velocity: &mut P,
+    ) -> Result<(), Error>
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        let t = self.t;
+        system.acceleration_into(t, position, &mut self.k[0]);
+        self.stats.rhs_evaluations += 1;
+
+        loop {
+            let dt = self.dt;
+            let dt2 = dt * dt;
+
+            for (i, a) in A.iter().enumerate() {
+                let c_dt = C[i + 1] * dt;
+                Zip::from(&mut self.temp)
+                    .and(&*position)
+                    .and(&*velocity)
+                    .apply(|y, &x, &v| *y = x + c_dt * v);
+
+                for (a_j, k_j) in a.iter().zip(&self.k[..=i]) {
+                    let dt2_a = dt2 * a_j;
+                    if dt2_a != 0.0 {
+                        Zip::from(&mut self.temp)
+                            .and(k_j)
+                            .apply(|y, &k| *y += dt2_a * k);
+                    }
+                }
+
+                system.acceleration_into(t + c_dt, &self.temp, &mut self.k[i + 1]);
+            }
+
+            Zip::from(&mut self.x1)
+                .and(&mut self.err_x)
+                .and(&*position)
+                .and(&*velocity)
+                .apply(|y, e, &x, &v| {
+                    *y = x + dt * v;
+                    *e = 0.0;
+                });
+            for ((b, e), k) in B_BAR.iter().zip(&E_BAR).zip(&self.k) {
+                let (dt2_b, dt2_e) = (dt2 * b, dt2 * e);
+                if dt2_b != 0.0 || dt2_e != 0.0 {
+                    Zip::from(&mut self.x1)
+                        .and(&mut self.err_x)
+                        .and(k)
+                        .apply(|y, e, &k| {
+                            *y += dt2_b * k;
+                            *e += dt2_e * k;
+                        });
+                }
+            }
+
+            system.acceleration_into(t + dt, &self.x1, &mut self.k[6]);
+
+            Zip::from(&mut self.v1)
+                .and(&mut self.err_v)
+                .and(&*velocity)
+                .apply(|w, e, &v| {
+                    *w = v;
+                    *e = 0.0;
+                });
+            for ((b, e), k) in B.iter().zip(&E).zip(&self.k) {
+                let (dt_b, dt_e) = (dt * b, dt * e);
+                if dt_b != 0.0 || dt_e != 0.0 {
+                    Zip::from(&mut self.v1)
+                        .and(&mut self.err_v)
+                        .and(k)
+                        .apply(|w, e, &k| {
+                            *w += dt_b * k;
+                            *e += dt_e * k;
+                        });
+                }
+            }
+
+            self.stats.rhs_evaluations += 6;
+            let error_x = self.error_control.error(&self.err_x, position, &self.x1);
+            let error_v = self.error_control.error(&self.err_v, velocity, &self.v1);
+            let error = max_error(error_x, error_v);
+            // The error estimate of the embedded 4th order solution is O(dt^5).
+            if self.adapt(error, 5)? {
+                break;
+            }
+        }
+
+        Zip::from(position)
+            .and(velocity)
+            .and(&self.x1)
+            .and(&self.v1)
+            .apply(|x, v, &x1, &v1| {
+                *x = x1;
+                *v = v1;
+            });
+        Ok(())
+    }
+}
+
+impl<D, P: ZipMarker, C> RknStepper for Rkn64<P, C>
+where
+    C: StepSizeController,
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    /// Do one accepted step.
+    ///
+    /// Panics if the step size underflows, see `try_do_step`.
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut P, velocity: &mut P)
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        self.try_do_step(system, position, velocity)
+            .unwrap_or_else(|error| panic!("Rkn64: {}", error));
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    fn integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut P,
+        velocity: &mut P,
+        t: f64,
+    ) -> (f64, usize)
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        self.try_integrate_time(system, position, velocity, t)
+            .unwrap_or_else(|error| panic!("Rkn64: {}", error))
+    }
+
+    fn try_do_step<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut P,
+        velocity: &mut P,
+    ) -> Result<(), Error>
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        self.guarded(|stepper| stepper.step(system, position, velocity))
+    }
+
+    /// Integrate over exactly the duration `t`, truncating the last step, and stopping at the
+    /// first failure.
+    fn try_integrate_time<Sy>(
+        &mut self,
+        system: &mut Sy,
+        position: &mut P,
+        velocity: &mut P,
+        t: f64,
+    ) -> Result<(f64, usize), Error>
+    where
+        Sy: SecondOrderOde<State = P>,
+    {
+        self.try_integrate_until(system, position, velocity, t)
+    }
+}
+
+/// The larger of the scaled errors of the position and the velocity. Unlike `f64::max`, keep any
+/// NaN, so that non-finite steps are rejected.
+fn max_error(error_x: f64, error_v: f64) -> f64 {
+    if error_x.is_nan() || error_x > error_v {
+        error_x
+    } else {
+        error_v
+    }
+}
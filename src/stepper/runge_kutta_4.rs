@@ -1,16 +1,19 @@
 use ndarray::{Dimension, IntoNdProducer, Zip};
 use std::fmt::Debug;
 
-use crate::ode::Ode;
+use crate::ode::NonautonomousOde;
+use crate::stats::Stats;
 
-use super::{Stepper, ZipMarker};
+use super::{hermite_basis, DenseOutput, Stepper, ZipMarker};
 
 #[derive(Debug)]
 pub struct RungeKutta4<T: Debug> {
     pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
     pub(crate) dt_2: f64,
     pub(crate) dt_3: f64,
     pub(crate) dt_6: f64,
+    pub(crate) t: f64,
 
     pub(crate) temp: T,
 
@@ -18,6 +21,8 @@ pub struct RungeKutta4<T: Debug> {
     pub(crate) k2: T,
     pub(crate) k3: T,
     pub(crate) k4: T,
+
+    pub(crate) stats: Stats,
 }
 
 impl<T> RungeKutta4<T>
@@ -36,22 +41,51 @@ where
         let k4 = state.clone();
 
         RungeKutta4 {
-            dt: dt,
-            dt_2: dt_2,
-            dt_3: dt_3,
-            dt_6: dt_6,
-
-            temp: temp,
-            k1: k1,
-            k2: k2,
-            k3: k3,
-            k4: k4,
+            dt,
+            last_dt: 0.0,
+            dt_2,
+            dt_3,
+            dt_6,
+            t: 0.0,
+
+            temp,
+            k1,
+            k2,
+            k3,
+            k4,
+
+            stats: Stats::new(),
         }
     }
 
     fn timestep(&self) -> f64 {
         self.dt
     }
+
+    pub(crate) fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+        self.dt_2 = dt / 2.0;
+        self.dt_3 = dt / 3.0;
+        self.dt_6 = dt / 6.0;
+    }
+
+    /// The weights of `k1` to `k4` in the interpolant `x1 + w1 * k1 + ... + w4 * k4` of the last
+    /// step.
+    ///
+    /// The interpolant is the cubic Hermite polynomial through the states at the beginning and
+    /// the end of the step, with the slopes `k1` and `k4`. The state at the beginning is
+    /// recovered from the state at the end and the stages.
+    pub(crate) fn dense_weights(&self, theta: f64) -> (f64, f64, f64, f64) {
+        let (h00, h10, _, h11) = hermite_basis(theta);
+        let dt = self.last_dt;
+
+        (
+            dt * (h10 - h00 / 6.0),
+            -dt * h00 / 3.0,
+            -dt * h00 / 3.0,
+            dt * (h11 - h00 / 6.0),
+        )
+    }
 }
 
 impl Stepper for RungeKutta4<f64> {
@@ -59,23 +93,51 @@ impl Stepper for RungeKutta4<f64> {
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = f64>,
+        Sy: NonautonomousOde<State = f64>,
     {
-        system.differentiate_into(state, &mut self.k1);
-        system.differentiate_into(&(*state + &self.k1 * self.dt_2), &mut self.k2);
-        system.differentiate_into(&(*state + &self.k2 * self.dt_2), &mut self.k3);
-        system.differentiate_into(&(*state + &self.k3 * self.dt), &mut self.k4);
+        let t = self.t;
+        let t_2 = t + self.dt_2;
+
+        system.differentiate_at_into(t, state, &mut self.k1);
+        system.differentiate_at_into(t_2, &(*state + self.k1 * self.dt_2), &mut self.k2);
+        system.differentiate_at_into(t_2, &(*state + self.k2 * self.dt_2), &mut self.k3);
+        system.differentiate_at_into(t + self.dt, &(*state + self.k3 * self.dt), &mut self.k4);
         self.temp = *state
-            + &(self.dt_6 * &self.k1)
-            + &(self.dt_3 * &self.k2)
-            + &(self.dt_3 * &self.k3)
-            + &(self.dt_6 * &self.k4);
+            + self.dt_6 * self.k1
+            + self.dt_3 * self.k2
+            + self.dt_3 * self.k3
+            + self.dt_6 * self.k4;
         system.update_state(state, &self.temp);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+
+        self.stats.rhs_evaluations += 4;
+        self.stats.accept(self.dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.set_timestep(dt);
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
 }
 
 impl<D, P: ZipMarker> Stepper for RungeKutta4<P>
@@ -89,35 +151,36 @@ where
 
     fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
     where
-        Sy: Ode<State = P>,
+        Sy: NonautonomousOde<State = P>,
     {
         let dt = self.dt;
         let dt_2 = self.dt_2;
         let dt_3 = self.dt_3;
         let dt_6 = self.dt_6;
+        let t = self.t;
 
-        system.differentiate_into(state, &mut self.k1);
+        system.differentiate_at_into(t, state, &mut self.k1);
 
         Zip::from(&mut self.temp)
             .and(&*state)
             .and(&self.k1)
             .apply(|next_x, &x, &x_k1| *next_x = x + dt_2 * x_k1);
 
-        system.differentiate_into(&self.temp, &mut self.k2);
+        system.differentiate_at_into(t + dt_2, &self.temp, &mut self.k2);
 
         Zip::from(&mut self.temp)
             .and(&*state)
             .and(&self.k2)
             .apply(|next_x, &x, &x_k2| *next_x = x + dt_2 * x_k2);
 
-        system.differentiate_into(&self.temp, &mut self.k3);
+        system.differentiate_at_into(t + dt_2, &self.temp, &mut self.k3);
 
         Zip::from(&mut self.temp)
             .and(&*state)
             .and(&self.k3)
             .apply(|next_x, &x, &x_k3| *next_x = x + dt * x_k3);
 
-        system.differentiate_into(&self.temp, &mut self.k4);
+        system.differentiate_at_into(t + dt, &self.temp, &mut self.k4);
 
         Zip::from(&mut self.temp)
             .and(&*state)
@@ -130,9 +193,71 @@ where
             });
 
         system.update_state(state, &self.temp);
+        self.last_dt = dt;
+        self.t += dt;
+
+        self.stats.rhs_evaluations += 4;
+        self.stats.accept(dt);
     }
 
     fn timestep(&self) -> f64 {
         self.timestep()
     }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.set_timestep(dt);
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl DenseOutput for RungeKutta4<f64> {
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut f64) {
+        let (w1, w2, w3, w4) = self.dense_weights(theta);
+        *out = self.temp + w1 * self.k1 + w2 * self.k2 + w3 * self.k3 + w4 * self.k4;
+    }
+}
+
+impl<D, P: ZipMarker> DenseOutput for RungeKutta4<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let (w1, w2, w3, w4) = self.dense_weights(theta);
+
+        Zip::from(out)
+            .and(&self.temp)
+            .and(&self.k1)
+            .and(&self.k2)
+            .and(&self.k3)
+            .and(&self.k4)
+            .apply(|out, &x1, &x_k1, &x_k2, &x_k3, &x_k4| {
+                *out = x1 + w1 * x_k1 + w2 * x_k2 + w3 * x_k3 + w4 * x_k4
+            });
+    }
 }
@@ -0,0 +1,542 @@
+use rand::Rng;
+use std::fmt::Debug;
+
+use crate::controller::{IController, StepSizeController};
+use crate::error::Error;
+use crate::noise_process::NoiseProcess;
+use crate::observer::Observer;
+use crate::sde::{check_noise_process, Noise, Sde};
+use crate::stats::Stats;
+use crate::tolerance::{Componentwise, ErrorControl, ErrorNorm, Tolerance};
+
+use super::adaptive::{self, Adaptive, StepControl};
+use super::SdeStepper;
+
+/// The stochastic Runge–Kutta methods of Rößler.
+///
+/// All of them have two stages of the drift,
+///
+/// ```text
+/// H0_1 = x,  H0_2 = x + c dt f(t, x) + b g_1 I_(1,0) / dt
+/// x_{n+1} = x + dt ((1 - α) f(t, H0_1) + α f(t + c dt, H0_2)) + Σ_i g_i M_i
+/// ```
+///
+/// with `c = 3/4`, `b = 3/2`, and `α = 2/3` for the methods of strong order 1.5, and `c = 1`,
+/// `b = 0`, and `α = 1/2` for `Sri2`. They differ in the stages `g_i` of the diffusion and their
+/// multipliers `M_i`, made up of the iterated Itô integrals `I_(1) = ΔW`, `I_(1,1)`, `I_(1,0)`,
+/// and `I_(1,1,1)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SrkMethod {
+    /// SRA1, of strong order 1.5 for additive noise, where the diffusion `g(t)` does not depend
+    /// on the state. Two stages of the diffusion, `g_1 = g(t + dt)` and `g_2 = g(t)`.
+    Sra1,
+    /// SRIW1, of strong order 1.5 for general Itô diffusions with diagonal or scalar noise. Four
+    /// stages of the diffusion; also of weak order 2.
+    Sriw1,
+    /// SRI2, of strong order 1 for general Itô diffusions with diagonal or scalar noise. Three
+    /// stages of the diffusion, `g_1 = g(t, x)` and
+    ///
+    /// ```text
+    /// g_{2,3} = g(t + dt, x + dt f(t, x) ± g_1 I_(1,1) / sqrt(dt)),
+    /// M_1 = ΔW,  M_{2,3} = ± sqrt(dt) / 2
+    /// ```
+    Sri2,
+}
+
+impl SrkMethod {
+    /// The weights of `ΔW`, `I_(1,1) / sqrt(dt)`, `I_(1,0) / dt`, `I_(1,1,1) / dt`, and
+    /// `sqrt(dt)` in the multiplier of each stage of the diffusion.
+    fn weights(self) -> &'static [[f64; 5]] {
+        match self {
+            SrkMethod::Sra1 => &[[1.0, 0.0, -1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0, 0.0]],
+            SrkMethod::Sriw1 => &[
+                [-1.0, -1.0, 2.0, -2.0, 0.0],
+                [4.0 / 3.0, 4.0 / 3.0, -4.0 / 3.0, 5.0 / 3.0, 0.0],
+                [2.0 / 3.0, -1.0 / 3.0, -2.0 / 3.0, -2.0 / 3.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0, 0.0],
+            ],
+            SrkMethod::Sri2 => &[
+                [1.0, 0.0, 0.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0, 0.5],
+                [0.0, 0.0, 0.0, 0.0, -0.5],
+            ],
+        }
+    }
+
+    /// The coefficients `c`, `b`, and `α` of the second stage of the drift.
+    fn drift(self) -> (f64, f64, f64) {
+        match self {
+            SrkMethod::Sra1 | SrkMethod::Sriw1 => (0.75, 1.5, 2.0 / 3.0),
+            SrkMethod::Sri2 => (1.0, 0.0, 0.5),
+        }
+    }
+}
+
+/// An adaptive stochastic Runge–Kutta stepper of strong order 1.5 or 1 for diagonal or scalar
+/// noise.
+///
+/// The local error is estimated from the natural embeddings of the method: the difference of its
+/// drift to that of the Euler–Maruyama method, and the terms of its noise of the highest order,
+/// those of `I_(1,0)` and `I_(1,1,1)`, or of `sqrt(dt)` for `Sri2`, which are added up in
+/// magnitude per component.
+///
+/// The Wiener increments and their time integrals are drawn from a `NoiseProcess` as for
+/// `EulerMaruyama`. Rejected steps are retried from the same time of the noise process, which
+/// draws the increments of the shorter steps from those of the rejected ones by the Brownian
+/// bridge, so that the rejections don't bias the Brownian path.
+pub struct StochasticRungeKutta<T: Debug, W, R, C = IController> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) method: SrkMethod,
+    pub(crate) error_control: ErrorControl,
+    pub(crate) controller: C,
+
+    pub(crate) noise_process: NoiseProcess<W, R>,
+    pub(crate) dw: Vec<f64>,
+    pub(crate) dz: Vec<f64>,
+
+    pub(crate) temp: T,
+    pub(crate) stage: T,
+    pub(crate) err: T,
+    pub(crate) drift_err: T,
+    pub(crate) noise_err: T,
+
+    pub(crate) f1: T,
+    pub(crate) f2: T,
+    pub(crate) diffusion: Vec<Vec<T>>,
+    pub(crate) noise: Vec<T>,
+    pub(crate) noise_errs: Vec<T>,
+
+    // ΔW, I_(1,1) / sqrt(dt), I_(1,0) / dt, I_(1,1,1) / dt, and sqrt(dt).
+    pub(crate) integrals: [T; 5],
+    pub(crate) multiplier: T,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T, W, R> StochasticRungeKutta<T, W, R>
+where
+    T: Componentwise + Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+{
+    /// Create a new stepper with initial step size `dt`, drawing the Wiener increments from
+    /// `noise_process`, and using an absolute tolerance of `1e-6` and a relative tolerance of
+    /// `1e-3`.
+    pub fn new(state: &T, dt: f64, method: SrkMethod, noise_process: NoiseProcess<W, R>) -> Self {
+        let stages = method.weights().len();
+
+        StochasticRungeKutta {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+
+            method,
+            error_control: ErrorControl::new(1e-6, 1e-3),
+            controller: IController::new(),
+
+            noise_process,
+            dw: Vec::new(),
+            dz: Vec::new(),
+
+            temp: state.clone(),
+            stage: state.clone(),
+            err: state.clone(),
+            drift_err: state.clone(),
+            noise_err: state.clone(),
+
+            f1: state.clone(),
+            f2: state.clone(),
+            diffusion: vec![vec![state.clone()]; stages],
+            noise: vec![state.clone(); stages],
+            noise_errs: vec![state.clone(); stages],
+
+            integrals: [
+                state.clone(),
+                state.clone(),
+                state.clone(),
+                state.clone(),
+                state.clone(),
+            ],
+            multiplier: state.clone(),
+
+            stats: Stats::new(),
+        }
+    }
+}
+
+impl<T, W, R, C> StochasticRungeKutta<T, W, R, C>
+where
+    T: Componentwise + Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+    C: StepSizeController,
+{
+    /// Replace the step size controller.
+    pub fn with_controller<C2>(self, controller: C2) -> StochasticRungeKutta<T, W, R, C2>
+    where
+        C2: StepSizeController,
+    {
+        StochasticRungeKutta {
+            dt: self.dt,
+            last_dt: self.last_dt,
+            t: self.t,
+
+            method: self.method,
+            error_control: self.error_control,
+            controller,
+
+            noise_process: self.noise_process,
+            dw: self.dw,
+            dz: self.dz,
+
+            temp: self.temp,
+            stage: self.stage,
+            err: self.err,
+            drift_err: self.drift_err,
+            noise_err: self.noise_err,
+
+            f1: self.f1,
+            f2: self.f2,
+            diffusion: self.diffusion,
+            noise: self.noise,
+            noise_errs: self.noise_errs,
+
+            integrals: self.integrals,
+            multiplier: self.multiplier,
+
+            stats: self.stats,
+        }
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Set the absolute and relative tolerances, keeping the error norm.
+    pub fn with_tolerances<A, Rt>(mut self, atol: A, rtol: Rt) -> Self
+    where
+        A: Into<Tolerance>,
+        Rt: Into<Tolerance>,
+    {
+        self.error_control = ErrorControl::new(atol, rtol).with_norm(self.error_control.norm());
+        self
+    }
+
+    pub fn with_error_norm(mut self, norm: ErrorNorm) -> Self {
+        self.error_control = self.error_control.with_norm(norm);
+        self
+    }
+
+    pub fn with_error_control(mut self, error_control: ErrorControl) -> Self {
+        self.error_control = error_control;
+        self
+    }
+
+    pub fn error_control(&self) -> &ErrorControl {
+        &self.error_control
+    }
+
+    pub fn method(&self) -> SrkMethod {
+        self.method
+    }
+
+    pub fn noise_process(&self) -> &NoiseProcess<W, R> {
+        &self.noise_process
+    }
+
+    /// The noise process, e.g. to replay or clear its realization.
+    pub fn noise_process_mut(&mut self) -> &mut NoiseProcess<W, R> {
+        &mut self.noise_process
+    }
+
+    /// The step size of the last accepted step.
+    pub fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    /// Do one accepted step, failing if the step size underflows.
+    fn step<Sy>(&mut self, system: &mut Sy, state: &mut T) -> Result<(), Error>
+    where
+        Sy: Sde<State = T>,
+    {
+        let noise = system.noise();
+        if let Noise::General(_) = noise {
+            panic!("StochasticRungeKutta: only diagonal and scalar noise are supported");
+        }
+
+        let t = self.t;
+        system.differentiate_at_into(t, state, &mut self.f1);
+        self.stats.rhs_evaluations += 1;
+
+        let noise_time = self.noise_process.time();
+        loop {
+            self.draw_integrals(noise, state);
+            self.attempt(system, state);
+            self.stats.rhs_evaluations += 1;
+
+            let error = self.error_control.error(&self.err, state, &self.temp);
+            // The error estimate is treated like that of a method of order 1.
+            if self.adapt(error, 2)? {
+                break;
+            }
+            self.noise_process.set_time(noise_time);
+        }
+        system.update_state(state, &self.temp);
+        Ok(())
+    }
+
+    /// Draw the Wiener increments over the attempted step and their time integrals from the
+    /// noise process, and compute the scaled iterated integrals from them.
+    fn draw_integrals(&mut self, noise: Noise, state: &T) {
+        let h = self.dt.abs();
+        let sqrt_h = h.sqrt();
+
+        self.noise_process
+            .increment_components(h, &mut self.dw, Some(&mut self.dz));
+        check_noise_process("StochasticRungeKutta", noise, state, self.dw.len());
+
+        // Under scalar noise, all components share the one increment.
+        let [dw, i11, i10, i111, sqrt] = &mut self.integrals;
+        let mut increments = self.dw.iter().cycle();
+        T::assign_components(dw, state, state, |_, _| *increments.next().unwrap());
+        let mut integrals = self.dz.iter().cycle();
+        T::assign_components(i10, state, state, |_, _| integrals.next().unwrap() / h);
+        T::assign_components(i11, dw, state, |w, _| (w * w - h) / (2.0 * sqrt_h));
+        T::assign_components(i111, dw, state, |w, _| {
+            (w * w * w - 3.0 * h * w) / (6.0 * h)
+        });
+        T::assign_components(sqrt, state, state, |_, _| sqrt_h);
+    }
+
+    /// Attempt a step from `state`, leaving the result in `temp` and the error estimate in `err`.
+    fn attempt<Sy>(&mut self, system: &mut Sy, state: &T)
+    where
+        Sy: Sde<State = T>,
+    {
+        let t = self.t;
+        let dt = self.dt;
+        let sqrt_h = dt.abs().sqrt();
+
+        // The stages of the diffusion.
+        match self.method {
+            SrkMethod::Sra1 => {
+                system.diffusion_into(t + dt, state, &mut self.diffusion[0]);
+                system.diffusion_into(t, state, &mut self.diffusion[1]);
+            }
+            SrkMethod::Sriw1 => {
+                let f1 = &self.f1;
+                system.diffusion_into(t, state, &mut self.diffusion[0]);
+
+                let g1 = &self.diffusion[0][0];
+                T::linear_combination(
+                    &mut self.stage,
+                    &[(1.0, state), (dt / 4.0, f1), (sqrt_h / 2.0, g1)],
+                );
+                system.diffusion_into(t + dt / 4.0, &self.stage, &mut self.diffusion[1]);
+
+                let g1 = &self.diffusion[0][0];
+                T::linear_combination(&mut self.stage, &[(1.0, state), (dt, f1), (-sqrt_h, g1)]);
+                system.diffusion_into(t + dt, &self.stage, &mut self.diffusion[2]);
+
+                let (g1, g2, g3) = (
+                    &self.diffusion[0][0],
+                    &self.diffusion[1][0],
+                    &self.diffusion[2][0],
+                );
+                T::linear_combination(
+                    &mut self.stage,
+                    &[
+                        (1.0, state),
+                        (dt / 4.0, f1),
+                        (-5.0 * sqrt_h, g1),
+                        (3.0 * sqrt_h, g2),
+                        (sqrt_h / 2.0, g3),
+                    ],
+                );
+                system.diffusion_into(t + dt / 4.0, &self.stage, &mut self.diffusion[3]);
+            }
+            SrkMethod::Sri2 => {
+                system.diffusion_into(t, state, &mut self.diffusion[0]);
+
+                let i11 = &self.integrals[1];
+                T::assign_components(&mut self.multiplier, &self.diffusion[0][0], i11, |g, i| {
+                    g * i
+                });
+                for (i, &sign) in [1.0, -1.0].iter().enumerate() {
+                    T::linear_combination(
+                        &mut self.stage,
+                        &[(1.0, state), (dt, &self.f1), (sign, &self.multiplier)],
+                    );
+                    system.diffusion_into(t + dt, &self.stage, &mut self.diffusion[i + 1]);
+                }
+            }
+        }
+
+        // The second stage of the drift.
+        let (c, b, alpha) = self.method.drift();
+        let [dw, i11, i10, i111, sqrt] = &self.integrals;
+        T::assign_components(&mut self.multiplier, &self.diffusion[0][0], i10, |g, z| {
+            g * z
+        });
+        T::linear_combination(
+            &mut self.stage,
+            &[(1.0, state), (c * dt, &self.f1), (b, &self.multiplier)],
+        );
+        system.differentiate_at_into(t + c * dt, &self.stage, &mut self.f2);
+
+        // The noise of each stage of the diffusion, and its terms of order 1.5.
+        for (i, weights) in self.method.weights().iter().enumerate() {
+            let g = &self.diffusion[i][0];
+
+            T::linear_combination(
+                &mut self.multiplier,
+                &[
+                    (weights[0], dw),
+                    (weights[1], i11),
+                    (weights[2], i10),
+                    (weights[3], i111),
+                    (weights[4], sqrt),
+                ],
+            );
+            T::assign_components(&mut self.noise[i], g, &self.multiplier, |g, m| g * m);
+
+            T::linear_combination(
+                &mut self.multiplier,
+                &[(weights[2], i10), (weights[3], i111), (weights[4], sqrt)],
+            );
+            T::assign_components(&mut self.noise_errs[i], g, &self.multiplier, |g, m| g * m);
+        }
+
+        let mut terms = vec![
+            (1.0, state),
+            ((1.0 - alpha) * dt, &self.f1),
+            (alpha * dt, &self.f2),
+        ];
+        terms.extend(self.noise.iter().map(|noise| (1.0, noise)));
+        T::linear_combination(&mut self.temp, &terms);
+
+        T::linear_combination(
+            &mut self.drift_err,
+            &[(alpha * dt, &self.f2), (-alpha * dt, &self.f1)],
+        );
+        let terms: Vec<_> = self.noise_errs.iter().map(|noise| (1.0, noise)).collect();
+        T::linear_combination(&mut self.noise_err, &terms);
+        T::assign_components(&mut self.err, &self.drift_err, &self.noise_err, |d, n| {
+            d.abs() + n.abs()
+        });
+    }
+}
+
+impl<T, W, R, C> Adaptive for StochasticRungeKutta<T, W, R, C>
+where
+    T: Debug,
+    C: StepSizeController,
+{
+    type Controller = C;
+
+    fn step_control(&mut self) -> StepControl<'_, C> {
+        StepControl {
+            t: &mut self.t,
+            dt: &mut self.dt,
+            last_dt: &mut self.last_dt,
+            controller: &mut self.controller,
+            stats: &mut self.stats,
+        }
+    }
+}
+
+impl<T, W, R, C> SdeStepper for StochasticRungeKutta<T, W, R, C>
+where
+    T: Componentwise + Clone + Debug,
+    W: Componentwise + Clone,
+    R: Rng,
+    C: StepSizeController,
+{
+    type State = T;
+
+    /// Do one accepted step.
+    ///
+    /// Panics if the step size underflows, or if the noise is `Noise::General`.
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+    where
+        Sy: Sde<State = T>,
+    {
+        self.step(system, state)
+            .unwrap_or_else(|error| panic!("StochasticRungeKutta: {}", error));
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    /// Do `n` accepted steps, returning the time integrated over.
+    fn integrate_n_steps_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        n: usize,
+        observer: &mut O,
+    ) -> f64
+    where
+        Sy: Sde<State = T>,
+        O: Observer<T>,
+    {
+        let mut tacc = 0f64;
+
+        observer.observe(self.t, state, 0);
+        for count in 1..=n {
+            self.do_step(system, state);
+            tacc += self.last_dt;
+            observer.observe(self.t, state, count);
+        }
+        tacc
+    }
+
+    /// Integrate over exactly the duration `t`, truncating the last step.
+    fn integrate_time_with<Sy, O>(
+        &mut self,
+        system: &mut Sy,
+        state: &mut Self::State,
+        t: f64,
+        observer: &mut O,
+    ) -> (f64, usize)
+    where
+        Sy: Sde<State = T>,
+        O: Observer<T>,
+    {
+        adaptive::integrate_until(
+            self,
+            state,
+            t,
+            |stepper, state| {
+                stepper.do_step(system, state);
+                Ok(())
+            },
+            |t, state, count| observer.observe(t, state, count),
+        )
+        .unwrap_or_else(|error| unreachable!("{}", error))
+    }
+}
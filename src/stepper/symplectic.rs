@@ -0,0 +1,269 @@
+use ndarray::{Dimension, IntoNdProducer, Zip};
+use std::fmt::Debug;
+
+use crate::hamiltonian::Hamiltonian;
+use crate::stats::Stats;
+
+use super::{SymplecticStepper, ZipMarker};
+
+/// The splitting methods of `Symplectic`.
+///
+/// Each is a sequence of kicks `p += b_i dt F(q)` and drifts `q += a_i dt V(p)`, which are the
+/// exact flows of the potential and the kinetic part of a separable Hamiltonian. Being
+/// compositions of such flows, all of them are symplectic, and all but `Euler` are symmetric and
+/// hence time reversible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymplecticMethod {
+    /// The symplectic Euler method of order 1, a kick followed by a drift.
+    Euler,
+    /// The Störmer–Verlet or leapfrog method of order 2 in its position form: a half drift, a
+    /// kick, and a half drift.
+    StormerVerlet,
+    /// The velocity Verlet method of order 2: a half kick, a drift, and a half kick.
+    VelocityVerlet,
+    /// The method of Forest and Ruth of order 4, the triple jump of `StormerVerlet` with the
+    /// substeps `θ dt`, `(1 - 2θ) dt`, `θ dt`, where `θ = 1 / (2 - 2^(1/3))`.
+    ForestRuth,
+    /// Yoshida's composition of order 4, the triple jump of `VelocityVerlet`.
+    Yoshida4,
+    /// Yoshida's composition of 7 steps of `VelocityVerlet` of order 6 (his solution A).
+    Yoshida6,
+    /// Yoshida's composition of 15 steps of `VelocityVerlet` of order 8 (his solution D).
+    Yoshida8,
+}
+
+impl SymplecticMethod {
+    /// The order of convergence of the method.
+    pub fn order(self) -> u32 {
+        match self {
+            SymplecticMethod::Euler => 1,
+            SymplecticMethod::StormerVerlet | SymplecticMethod::VelocityVerlet => 2,
+            SymplecticMethod::ForestRuth | SymplecticMethod::Yoshida4 => 4,
+            SymplecticMethod::Yoshida6 => 6,
+            SymplecticMethod::Yoshida8 => 8,
+        }
+    }
+
+    /// The coefficients `b_0, ..., b_m` of the kicks and `a_0, ..., a_(m-1)` of the drifts, taken
+    /// in the order `b_0, a_0, b_1, ..., a_(m-1), b_m`.
+    fn coefficients(self) -> (Vec<f64>, Vec<f64>) {
+        match self {
+            SymplecticMethod::Euler => (vec![1.0, 0.0], vec![1.0]),
+            SymplecticMethod::StormerVerlet => (vec![0.0, 1.0, 0.0], vec![0.5, 0.5]),
+            SymplecticMethod::VelocityVerlet => (vec![0.5, 0.5], vec![1.0]),
+            SymplecticMethod::ForestRuth => {
+                let theta = 1.0 / (2.0 - 2f64.cbrt());
+                (
+                    vec![0.0, theta, 1.0 - 2.0 * theta, theta, 0.0],
+                    vec![
+                        theta / 2.0,
+                        (1.0 - theta) / 2.0,
+                        (1.0 - theta) / 2.0,
+                        theta / 2.0,
+                    ],
+                )
+            }
+            SymplecticMethod::Yoshida4 => {
+                let w1 = 1.0 / (2.0 - 2f64.cbrt());
+                compose(&[w1, 1.0 - 2.0 * w1, w1])
+            }
+            SymplecticMethod::Yoshida6 => symmetric(&[
+                -1.177_679_984_178_87,
+                0.235_573_213_359_357,
+                0.784_513_610_477_560,
+            ]),
+            SymplecticMethod::Yoshida8 => symmetric(&[
+                0.102_799_849_391_985,
+                -1.960_610_232_975_49,
+                1.938_139_137_622_76,
+                -0.158_240_635_368_243,
+                -1.444_852_236_860_48,
+                0.253_693_336_566_229,
+                0.914_844_246_229_740,
+            ]),
+        }
+    }
+}
+
+/// The symmetric composition of `VelocityVerlet` with the substeps `w_n, ..., w_1, w_0, w_1,
+/// ..., w_n`, given `w_1, ..., w_n`, where `w_0 = 1 - 2 Σ w_i`.
+fn symmetric(weights: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let w0 = 1.0 - 2.0 * weights.iter().sum::<f64>();
+    let mut substeps: Vec<f64> = weights.iter().rev().cloned().collect();
+    substeps.push(w0);
+    substeps.extend(weights);
+    compose(&substeps)
+}
+
+/// The composition of `VelocityVerlet` with the given substeps, merging the adjacent half kicks.
+fn compose(substeps: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut kicks = vec![substeps[0] / 2.0];
+    for pair in substeps.windows(2) {
+        kicks.push((pair[0] + pair[1]) / 2.0);
+    }
+    kicks.push(substeps[substeps.len() - 1] / 2.0);
+    (kicks, substeps.to_vec())
+}
+
+/// Fixed-step symplectic splitting methods for separable Hamiltonian systems, see
+/// `SymplecticMethod`.
+///
+/// Unlike the Runge–Kutta methods, these keep the energy of the system bounded close to its
+/// initial value over long integrations, instead of letting it drift. The force and the
+/// velocity are evaluated once per kick and drift, each counting as an evaluation of the
+/// right-hand side. The drifts advance the time at which the force is evaluated.
+#[derive(Debug)]
+pub struct Symplectic<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) method: SymplecticMethod,
+    pub(crate) kicks: Vec<f64>,
+    pub(crate) drifts: Vec<f64>,
+
+    pub(crate) temp: T,
+
+    pub(crate) stats: Stats,
+}
+
+impl<T> Symplectic<T>
+where
+    T: Clone + Debug,
+{
+    pub fn new(state: &T, dt: f64, method: SymplecticMethod) -> Self {
+        let (kicks, drifts) = method.coefficients();
+
+        Symplectic {
+            dt,
+            t: 0.0,
+
+            method,
+            kicks,
+            drifts,
+
+            temp: state.clone(),
+
+            stats: Stats::new(),
+        }
+    }
+
+    pub fn method(&self) -> SymplecticMethod {
+        self.method
+    }
+}
+
+impl SymplecticStepper for Symplectic<f64> {
+    type State = f64;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut f64, momentum: &mut f64)
+    where
+        Sy: Hamiltonian<State = f64>,
+    {
+        let dt = self.dt;
+        let mut t = self.t;
+
+        for (i, &b) in self.kicks.iter().enumerate() {
+            if b != 0.0 {
+                system.force_into(t, position, &mut self.temp);
+                *momentum += b * dt * self.temp;
+                self.stats.rhs_evaluations += 1;
+            }
+            if let Some(&a) = self.drifts.get(i) {
+                system.velocity_into(t, momentum, &mut self.temp);
+                *position += a * dt * self.temp;
+                t += a * dt;
+                self.stats.rhs_evaluations += 1;
+            }
+        }
+
+        self.t += dt;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<D, P: ZipMarker> SymplecticStepper for Symplectic<P>
+where
+    P: Clone + Debug,
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, position: &mut P, momentum: &mut P)
+    where
+        Sy: Hamiltonian<State = P>,
+    {
+        let dt = self.dt;
+        let mut t = self.t;
+
+        for (i, &b) in self.kicks.iter().enumerate() {
+            if b != 0.0 {
+                system.force_into(t, position, &mut self.temp);
+                Zip::from(&mut *momentum)
+                    .and(&self.temp)
+                    .apply(|p, &f| *p += b * dt * f);
+                self.stats.rhs_evaluations += 1;
+            }
+            if let Some(&a) = self.drifts.get(i) {
+                system.velocity_into(t, momentum, &mut self.temp);
+                Zip::from(&mut *position)
+                    .and(&self.temp)
+                    .apply(|q, &v| *q += a * dt * v);
+                t += a * dt;
+                self.stats.rhs_evaluations += 1;
+            }
+        }
+
+        self.t += dt;
+        self.stats.accept(dt);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
@@ -0,0 +1,137 @@
+use ndarray::{IntoNdProducer, Ix1, Zip};
+use std::fmt::Debug;
+
+use crate::error::Error;
+use crate::jacobian::Jacobian;
+use crate::stats::Stats;
+
+use super::newton::Newton;
+use super::{ImplicitDenseOutput, ImplicitStepper, ZipMarker};
+
+/// The trapezoidal rule (Crank–Nicolson), x_{n+1} = x_n + dt/2 (f(t_n, x_n) + f(t_{n+1}, x_{n+1})).
+///
+/// Second order and A-stable, but stiff components are only weakly damped.
+#[derive(Debug)]
+pub struct Trapezoidal<T: Debug> {
+    pub(crate) dt: f64,
+    pub(crate) last_dt: f64,
+    pub(crate) t: f64,
+
+    pub(crate) newton: Newton<T>,
+
+    pub(crate) stats: Stats,
+}
+
+impl<P: ZipMarker> Trapezoidal<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    pub fn new(state: &P, dt: f64) -> Self {
+        let newton = Newton::new(state);
+
+        Trapezoidal {
+            dt,
+            last_dt: 0.0,
+            t: 0.0,
+            newton,
+            stats: Stats::new(),
+        }
+    }
+
+    /// Set the tolerance on the scaled Newton updates, `1e-10` by default.
+    pub fn with_newton_tolerance(mut self, tolerance: f64) -> Self {
+        self.newton.tolerance = tolerance;
+        self
+    }
+
+    /// Set the maximum number of Newton iterations per step, `20` by default.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.newton.max_iterations = max_iterations;
+        self
+    }
+}
+
+impl<P: ZipMarker> ImplicitStepper for Trapezoidal<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    type State = P;
+
+    fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+    where
+        Sy: Jacobian<State = P>,
+    {
+        system.try_differentiate_at_into(self.t, state, &mut self.newton.f0)?;
+        self.stats.rhs_evaluations += 1;
+
+        self.newton.solve(
+            system,
+            self.t,
+            self.dt,
+            state,
+            1.0,
+            0.5,
+            0.5,
+            &mut self.stats,
+        )?;
+
+        system.update_state(state, &self.newton.z);
+        self.last_dt = self.dt;
+        self.t += self.dt;
+        self.stats.accept(self.dt);
+        Ok(())
+    }
+
+    fn timestep(&self) -> f64 {
+        self.dt
+    }
+
+    fn time(&self) -> f64 {
+        self.t
+    }
+
+    fn set_time(&mut self, t: f64) {
+        self.t = t;
+    }
+
+    fn set_timestep(&mut self, dt: f64) {
+        self.dt = dt;
+    }
+
+    fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+}
+
+impl<P: ZipMarker> ImplicitDenseOutput for Trapezoidal<P>
+where
+    P: Clone + Debug,
+    for<'a> &'a P: IntoNdProducer<Dim = Ix1, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = Ix1, Item = &'a mut f64>,
+{
+    fn last_timestep(&self) -> f64 {
+        self.last_dt
+    }
+
+    /// The quadratic `x0 + dt (theta f0 + theta² / 2 (f1 - f0))`, whose slope goes linearly from
+    /// the derivative `f0` at the beginning to the derivative `f1` of the last Newton iteration.
+    fn interpolate(&self, theta: f64, out: &mut P) {
+        let dt = self.last_dt;
+        let w0 = dt * (theta - 0.5 - 0.5 * theta * theta);
+        let w1 = dt * 0.5 * (theta * theta - 1.0);
+
+        Zip::from(out)
+            .and(&self.newton.z)
+            .and(&self.newton.f0)
+            .and(&self.newton.f)
+            .apply(|out, &x1, &f0, &f1| *out = x1 + w0 * f0 + w1 * f1);
+    }
+}
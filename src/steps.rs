@@ -0,0 +1,60 @@
+use crate::ode::NonautonomousOde;
+use crate::stepper::Stepper;
+
+/// An iterator doing one step per item, created by `Stepper::iter`.
+///
+/// It yields the time and a copy of the state, starting with the initial state before the first
+/// step, and never ends by itself. Adapters like `take`, `take_while`, or `step_by` bound the
+/// integration. Used through `by_ref`, the state after the last step remains available through
+/// `state` and `into_state`.
+pub struct Steps<'a, St, Sy>
+where
+    St: Stepper + ?Sized,
+{
+    pub(crate) stepper: &'a mut St,
+    pub(crate) system: &'a mut Sy,
+    pub(crate) state: St::State,
+    pub(crate) started: bool,
+}
+
+impl<'a, St, Sy> Steps<'a, St, Sy>
+where
+    St: Stepper + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    /// The state after the last step.
+    pub fn state(&self) -> &St::State {
+        &self.state
+    }
+
+    /// The current time of the stepper.
+    pub fn time(&self) -> f64 {
+        self.stepper.time()
+    }
+
+    /// Return the state after the last step.
+    pub fn into_state(self) -> St::State {
+        self.state
+    }
+}
+
+impl<'a, St, Sy> Iterator for Steps<'a, St, Sy>
+where
+    St: Stepper + ?Sized,
+    Sy: NonautonomousOde<State = St::State>,
+{
+    type Item = (f64, St::State);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            self.stepper.do_step(self.system, &mut self.state);
+        } else {
+            self.started = true;
+        }
+        Some((self.stepper.time(), self.state.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
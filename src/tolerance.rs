@@ -0,0 +1,220 @@
+use ndarray::prelude::*;
+use ndarray::{Dimension, IntoNdProducer, Zip};
+
+use crate::stepper::ZipMarker;
+
+/// An absolute or relative tolerance, either shared by all components of the state or given per
+/// component.
+///
+/// Per-component tolerances are matched to the components in the order of iteration, which is
+/// the order of the elements for `Vec`s and one dimensional arrays.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tolerance {
+    Scalar(f64),
+    Vector(Array1<f64>),
+}
+
+impl Tolerance {
+    /// The tolerance of the `i`-th component.
+    ///
+    /// Panics if the tolerance is given for fewer than `i + 1` components.
+    pub fn get(&self, i: usize) -> f64 {
+        match self {
+            Tolerance::Scalar(tolerance) => *tolerance,
+            Tolerance::Vector(tolerances) => match tolerances.get(i) {
+                Some(tolerance) => *tolerance,
+                None => panic!(
+                    "Tolerance: given for {} components, but the state has more",
+                    tolerances.len()
+                ),
+            },
+        }
+    }
+}
+
+impl From<f64> for Tolerance {
+    fn from(tolerance: f64) -> Self {
+        Tolerance::Scalar(tolerance)
+    }
+}
+
+impl From<Array1<f64>> for Tolerance {
+    fn from(tolerances: Array1<f64>) -> Self {
+        Tolerance::Vector(tolerances)
+    }
+}
+
+impl From<Vec<f64>> for Tolerance {
+    fn from(tolerances: Vec<f64>) -> Self {
+        Tolerance::Vector(Array1::from_vec(tolerances))
+    }
+}
+
+/// The norm combining the scaled errors of the components into the error of a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorNorm {
+    /// The root mean square of the scaled errors.
+    WeightedRms,
+    /// The largest scaled error.
+    Max,
+}
+
+/// States whose `f64` components tolerances and error norms are applied to, and which are
+/// combined componentwise for interpolation.
+pub trait Componentwise {
+    /// Call `f` with the corresponding components of `a`, `b`, and `c`, in order.
+    fn for_each_component<F>(a: &Self, b: &Self, c: &Self, f: F)
+    where
+        F: FnMut(f64, f64, f64);
+
+    /// Set each component of `out` to `f` of the corresponding components of `a` and `b`.
+    fn assign_components<F>(out: &mut Self, a: &Self, b: &Self, f: F)
+    where
+        F: FnMut(f64, f64) -> f64;
+
+    /// Set `out` to the linear combination `c_1 * x_1 + c_2 * x_2 + ...` of the `terms`
+    /// `(c_i, x_i)`, or to zero if there are none.
+    fn linear_combination(out: &mut Self, terms: &[(f64, &Self)]);
+}
+
+impl Componentwise for f64 {
+    fn for_each_component<F>(a: &f64, b: &f64, c: &f64, mut f: F)
+    where
+        F: FnMut(f64, f64, f64),
+    {
+        f(*a, *b, *c)
+    }
+
+    fn assign_components<F>(out: &mut f64, a: &f64, b: &f64, mut f: F)
+    where
+        F: FnMut(f64, f64) -> f64,
+    {
+        *out = f(*a, *b);
+    }
+
+    fn linear_combination(out: &mut f64, terms: &[(f64, &f64)]) {
+        *out = terms.iter().map(|&(c, x)| c * x).sum();
+    }
+}
+
+impl<D, P: ZipMarker> Componentwise for P
+where
+    D: Dimension,
+    for<'a> &'a P: IntoNdProducer<Dim = D, Item = &'a f64>,
+    for<'a> &'a mut P: IntoNdProducer<Dim = D, Item = &'a mut f64>,
+{
+    fn for_each_component<F>(a: &P, b: &P, c: &P, mut f: F)
+    where
+        F: FnMut(f64, f64, f64),
+    {
+        Zip::from(a).and(b).and(c).apply(|&a, &b, &c| f(a, b, c));
+    }
+
+    fn assign_components<F>(out: &mut P, a: &P, b: &P, mut f: F)
+    where
+        F: FnMut(f64, f64) -> f64,
+    {
+        Zip::from(out)
+            .and(a)
+            .and(b)
+            .apply(|out, &a, &b| *out = f(a, b));
+    }
+
+    fn linear_combination(out: &mut P, terms: &[(f64, &P)]) {
+        Zip::from(&mut *out).apply(|out| *out = 0.0);
+        for &(c, x) in terms {
+            Zip::from(&mut *out).and(x).apply(|out, &x| *out += c * x);
+        }
+    }
+}
+
+/// Measures the local error of a step for adaptive steppers.
+///
+/// The error `e_i` of each component is scaled by `atol_i + rtol_i * max(|x_i|, |y_i|)`, where
+/// `x` and `y` are the states before and after the step, and the scaled errors are combined by
+/// the error norm. A step is acceptable if its error is at most `1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorControl {
+    pub(crate) atol: Tolerance,
+    pub(crate) rtol: Tolerance,
+    pub(crate) norm: ErrorNorm,
+}
+
+impl ErrorControl {
+    /// Control the error against the absolute and relative tolerances `atol` and `rtol`, using
+    /// the weighted RMS norm.
+    pub fn new<A, R>(atol: A, rtol: R) -> Self
+    where
+        A: Into<Tolerance>,
+        R: Into<Tolerance>,
+    {
+        ErrorControl {
+            atol: atol.into(),
+            rtol: rtol.into(),
+            norm: ErrorNorm::WeightedRms,
+        }
+    }
+
+    pub fn with_norm(mut self, norm: ErrorNorm) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    pub fn atol(&self) -> &Tolerance {
+        &self.atol
+    }
+
+    pub fn rtol(&self) -> &Tolerance {
+        &self.rtol
+    }
+
+    pub fn norm(&self) -> ErrorNorm {
+        self.norm
+    }
+
+    /// The error of a step from `x` to `y` with the local error estimate `error`.
+    ///
+    /// A NaN in any component results in a NaN error.
+    ///
+    /// Panics if per-component tolerances don't match the number of components.
+    pub fn error<T>(&self, error: &T, x: &T, y: &T) -> f64
+    where
+        T: Componentwise,
+    {
+        let mut acc = 0f64;
+        let mut n = 0;
+
+        T::for_each_component(error, x, y, |e, x, y| {
+            let scaled = (e / (self.atol.get(n) + self.rtol.get(n) * x.abs().max(y.abs()))).abs();
+            acc = match self.norm {
+                ErrorNorm::WeightedRms => acc + scaled * scaled,
+                // Unlike f64::max, keep any NaN.
+                ErrorNorm::Max => {
+                    if acc.is_nan() || scaled <= acc {
+                        acc
+                    } else {
+                        scaled
+                    }
+                }
+            };
+            n += 1;
+        });
+
+        for tolerance in &[&self.atol, &self.rtol] {
+            if let Tolerance::Vector(tolerances) = tolerance {
+                assert_eq!(
+                    tolerances.len(),
+                    n,
+                    "ErrorControl: tolerances given for {} components, but the state has {}",
+                    tolerances.len(),
+                    n
+                );
+            }
+        }
+
+        match self.norm {
+            ErrorNorm::WeightedRms if n > 0 => (acc / n as f64).sqrt(),
+            _ => acc,
+        }
+    }
+}
@@ -1,11 +1,20 @@
-use crate::Ode;
-use crate::{Euler, Heun, RungeKutta4, Stepper};
+use crate::controller::StepSizeController;
+use crate::error::{self, Error, Guarded};
+use crate::stats::Stats;
+use crate::stepper::adaptive::{self, Adaptive};
+use crate::stepper::dormand_prince_5::*;
+use crate::tolerance::Componentwise;
+use crate::{
+    ButcherTableau, DenseOutput, DormandPrince5, Euler, ExplicitRungeKutta, Heun, RungeKutta4,
+    Stepper,
+};
+use crate::{Event, EventOccurrence, NonautonomousOde, Observer, Ode};
 
-use tuple::{Splat, A1, A10, A11, A12, A2, A3, A4, A5, A6, A7, A8, A9};
+use tuple::{Splat, TupleElements, A1, A10, A11, A12, A2, A3, A4, A5, A6, A7, A8, A9};
 
 macro_rules! impl_ode_for_tuples {
     ( $tup:ty ) => {
-        impl Ode for Box<Fn($tup) -> $tup>
+        impl Ode for Box<dyn Fn($tup) -> $tup>
         {
             type State = $tup;
 
@@ -14,7 +23,7 @@ macro_rules! impl_ode_for_tuples {
             }
         }
 
-        impl<'a> Ode for &'a Fn($tup) -> $tup
+        impl<'a> Ode for &'a dyn Fn($tup) -> $tup
         {
             type State = $tup;
 
@@ -23,7 +32,7 @@ macro_rules! impl_ode_for_tuples {
             }
         }
 
-        impl Ode for Box<FnMut($tup) -> $tup>
+        impl Ode for Box<dyn FnMut($tup) -> $tup>
         {
             type State = $tup;
 
@@ -32,7 +41,7 @@ macro_rules! impl_ode_for_tuples {
             }
         }
 
-        impl<'a> Ode for &'a mut FnMut($tup) -> $tup
+        impl<'a> Ode for &'a mut dyn FnMut($tup) -> $tup
         {
             type State = $tup;
 
@@ -40,6 +49,42 @@ macro_rules! impl_ode_for_tuples {
                 derivative.clone_from(&self(*state));
             }
         }
+
+        impl NonautonomousOde for Box<dyn Fn(f64, $tup) -> $tup>
+        {
+            type State = $tup;
+
+            fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+                derivative.clone_from(&self(t, *state));
+            }
+        }
+
+        impl<'a> NonautonomousOde for &'a dyn Fn(f64, $tup) -> $tup
+        {
+            type State = $tup;
+
+            fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+                derivative.clone_from(&self(t, *state));
+            }
+        }
+
+        impl NonautonomousOde for Box<dyn FnMut(f64, $tup) -> $tup>
+        {
+            type State = $tup;
+
+            fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+                derivative.clone_from(&self(t, *state));
+            }
+        }
+
+        impl<'a> NonautonomousOde for &'a mut dyn FnMut(f64, $tup) -> $tup
+        {
+            type State = $tup;
+
+            fn differentiate_at_into(&mut self, t: f64, state: &Self::State, derivative: &mut Self::State) {
+                derivative.clone_from(&self(t, *state));
+            }
+        }
     };
 
     ( $( $tup:ty ),+ ) => {
@@ -51,21 +96,72 @@ macro_rules! impl_ode_for_tuples {
 
 macro_rules! impl_stepper_for_tuples {
     ( $tuple:ty ) => {
+        impl Componentwise for $tuple
+        {
+            fn for_each_component<F>(a: &Self, b: &Self, c: &Self, mut f: F)
+                where F: FnMut(f64, f64, f64),
+            {
+                for ((&a, &b), &c) in a.elements().zip(b.elements()).zip(c.elements()) {
+                    f(a, b, c);
+                }
+            }
+
+            fn assign_components<F>(out: &mut Self, a: &Self, b: &Self, mut f: F)
+                where F: FnMut(f64, f64) -> f64,
+            {
+                for ((out, &a), &b) in out.elements_mut().zip(a.elements()).zip(b.elements()) {
+                    *out = f(a, b);
+                }
+            }
+
+            fn linear_combination(out: &mut Self, terms: &[(f64, &Self)]) {
+                *out = <$tuple as Splat<_>>::splat(0.0);
+                for &(c, x) in terms {
+                    *out += <$tuple as Splat<_>>::splat(c) * *x;
+                }
+            }
+        }
+
         impl Stepper for Euler<$tuple>
         {
             type State = $tuple;
 
             fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
-                where Sy: Ode<State = Self::State>,
+                where Sy: NonautonomousOde<State = Self::State>,
             {
-                system.differentiate_into(state, &mut self.temp);
-                self.temp = *state + self.temp * <$tuple as Splat<_>>::splat(self.dt);
+                system.differentiate_at_into(self.t, state, &mut self.k1);
+                self.temp = *state + self.k1 * <$tuple as Splat<_>>::splat(self.dt);
                 system.update_state(state, &self.temp);
+                self.last_dt = self.dt;
+                self.t += self.dt;
+
+                self.stats.rhs_evaluations += 1;
+                self.stats.accept(self.dt);
             }
 
             fn timestep(&self) -> f64 {
                 self.dt
             }
+
+            fn time(&self) -> f64 {
+                self.t
+            }
+
+            fn set_time(&mut self, t: f64) {
+                self.t = t;
+            }
+
+            fn set_timestep(&mut self, dt: f64) {
+                self.dt = dt;
+            }
+
+            fn stats(&self) -> &Stats {
+                &self.stats
+            }
+
+            fn reset_stats(&mut self) {
+                self.stats = Stats::new();
+            }
         }
 
         impl Stepper for Heun<$tuple>
@@ -73,20 +169,47 @@ macro_rules! impl_stepper_for_tuples {
             type State = $tuple;
 
             fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
-                where Sy: Ode<State = Self::State>,
+                where Sy: NonautonomousOde<State = Self::State>,
             {
                let dt = <$tuple as Splat<_>>::splat(self.dt);
                let dt_2 = <$tuple as Splat<_>>::splat(self.dt_2);
 
-               system.differentiate_into(state, &mut self.k1);
-               system.differentiate_into(&(*state + dt * self.k1), &mut self.k2);
+               let t = self.t;
+
+               system.differentiate_at_into(t, state, &mut self.k1);
+               system.differentiate_at_into(t + self.dt, &(*state + dt * self.k1), &mut self.k2);
                self.temp = *state + dt_2 * (self.k1 + self.k2);
                system.update_state(state, &self.temp);
+               self.last_dt = self.dt;
+               self.t += self.dt;
+
+               self.stats.rhs_evaluations += 2;
+               self.stats.accept(self.dt);
             }
 
             fn timestep(&self) -> f64 {
                 self.dt
             }
+
+            fn time(&self) -> f64 {
+                self.t
+            }
+
+            fn set_time(&mut self, t: f64) {
+                self.t = t;
+            }
+
+            fn set_timestep(&mut self, dt: f64) {
+                self.set_timestep(dt);
+            }
+
+            fn stats(&self) -> &Stats {
+                &self.stats
+            }
+
+            fn reset_stats(&mut self) {
+                self.stats = Stats::new();
+            }
         }
 
         impl Stepper for RungeKutta4<$tuple>
@@ -94,25 +217,364 @@ macro_rules! impl_stepper_for_tuples {
             type State = $tuple;
 
             fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
-                where Sy: Ode<State = Self::State>,
+                where Sy: NonautonomousOde<State = Self::State>,
             {
                 let dt = <$tuple as Splat<_>>::splat(self.dt);
                 let dt_2 = <$tuple as Splat<_>>::splat(self.dt_2);
                 let dt_3 = <$tuple as Splat<_>>::splat(self.dt_3);
                 let dt_6 = <$tuple as Splat<_>>::splat(self.dt_6);
 
-                system.differentiate_into(state, &mut self.k1);
-                system.differentiate_into(&(*state + dt_2 * self.k1), &mut self.k2);
-                system.differentiate_into(&(*state + dt_2 * self.k2), &mut self.k3);
-                system.differentiate_into(&(*state + dt * self.k3), &mut self.k4);
+                let t = self.t;
+                let t_2 = t + self.dt_2;
+
+                system.differentiate_at_into(t, state, &mut self.k1);
+                system.differentiate_at_into(t_2, &(*state + dt_2 * self.k1), &mut self.k2);
+                system.differentiate_at_into(t_2, &(*state + dt_2 * self.k2), &mut self.k3);
+                system.differentiate_at_into(t + self.dt, &(*state + dt * self.k3), &mut self.k4);
 
                 self.temp = *state + dt_6 * (self.k1 + self.k4)+ dt_3 * (self.k2 + self.k3);
                 system.update_state(state, &self.temp);
+                self.last_dt = self.dt;
+                self.t += self.dt;
+
+                self.stats.rhs_evaluations += 4;
+                self.stats.accept(self.dt);
+            }
+
+            fn timestep(&self) -> f64 {
+                self.dt
+            }
+
+            fn time(&self) -> f64 {
+                self.t
+            }
+
+            fn set_time(&mut self, t: f64) {
+                self.t = t;
+            }
+
+            fn set_timestep(&mut self, dt: f64) {
+                self.set_timestep(dt);
+            }
+
+            fn stats(&self) -> &Stats {
+                &self.stats
+            }
+
+            fn reset_stats(&mut self) {
+                self.stats = Stats::new();
+            }
+        }
+
+        impl Stepper for ExplicitRungeKutta<$tuple>
+        {
+            type State = $tuple;
+
+            fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+                where Sy: NonautonomousOde<State = Self::State>,
+            {
+                let dt = self.dt;
+                let t = self.t;
+                let ButcherTableau { a, b, c } = &self.tableau;
+
+                system.differentiate_at_into(t, state, &mut self.k[0]);
+
+                for i in 1..self.k.len() {
+                    self.temp = *state;
+                    for j in 0..i {
+                        self.temp += <$tuple as Splat<_>>::splat(dt * a[(i, j)]) * self.k[j];
+                    }
+                    system.differentiate_at_into(t + c[i] * dt, &self.temp, &mut self.k[i]);
+                }
+
+                self.temp = *state;
+                for (b_i, k_i) in b.iter().zip(&self.k) {
+                    self.temp += <$tuple as Splat<_>>::splat(dt * b_i) * *k_i;
+                }
+
+                system.update_state(state, &self.temp);
+                self.last_dt = dt;
+                self.t += dt;
+
+                self.stats.rhs_evaluations += self.k.len();
+                self.stats.accept(dt);
+            }
+
+            fn timestep(&self) -> f64 {
+                self.dt
+            }
+
+            fn time(&self) -> f64 {
+                self.t
+            }
+
+            fn set_time(&mut self, t: f64) {
+                self.t = t;
+            }
+
+            fn set_timestep(&mut self, dt: f64) {
+                self.dt = dt;
+            }
+
+            fn stats(&self) -> &Stats {
+                &self.stats
+            }
+
+            fn reset_stats(&mut self) {
+                self.stats = Stats::new();
+            }
+        }
+
+        impl<C> DormandPrince5<$tuple, C>
+            where C: StepSizeController,
+        {
+            /// Do one accepted step, failing if the step size underflows.
+            pub(crate) fn step<Sy>(&mut self, system: &mut Sy, state: &mut $tuple) -> Result<(), Error>
+                where Sy: NonautonomousOde<State = $tuple>,
+            {
+                self.step_checked(system, state, |_| Ok(()))
+            }
+
+            /// Do one accepted step like `step`, failing as soon as `check` fails after the
+            /// evaluations of the right-hand side of an attempt.
+            fn step_checked<Sy, F>(&mut self, system: &mut Sy, state: &mut $tuple, check: F) -> Result<(), Error>
+                where Sy: NonautonomousOde<State = $tuple>,
+                      F: Fn(&Sy) -> Result<(), Error>,
+            {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+
+                let t = self.t;
+                system.differentiate_at_into(t, state, &mut self.k1);
+                check(system)?;
+                self.stats.rhs_evaluations += 1;
+
+                loop {
+                    let h = self.dt;
+                    let dt = splat(h);
+
+                    system.differentiate_at_into(t + C2 * h, &(*state + dt * splat(A21) * self.k1), &mut self.k2);
+                    system.differentiate_at_into(
+                        t + C3 * h,
+                        &(*state + dt * (splat(A31) * self.k1 + splat(A32) * self.k2)),
+                        &mut self.k3,
+                    );
+                    system.differentiate_at_into(
+                        t + C4 * h,
+                        &(*state + dt * (splat(A41) * self.k1 + splat(A42) * self.k2 + splat(A43) * self.k3)),
+                        &mut self.k4,
+                    );
+                    system.differentiate_at_into(
+                        t + C5 * h,
+                        &(*state + dt * (splat(A51) * self.k1 + splat(A52) * self.k2
+                                         + splat(A53) * self.k3 + splat(A54) * self.k4)),
+                        &mut self.k5,
+                    );
+                    system.differentiate_at_into(
+                        t + h,
+                        &(*state + dt * (splat(A61) * self.k1 + splat(A62) * self.k2 + splat(A63) * self.k3
+                                         + splat(A64) * self.k4 + splat(A65) * self.k5)),
+                        &mut self.k6,
+                    );
+                    self.temp = *state + dt * (splat(B1) * self.k1 + splat(B3) * self.k3 + splat(B4) * self.k4
+                                               + splat(B5) * self.k5 + splat(B6) * self.k6);
+                    system.differentiate_at_into(t + h, &self.temp, &mut self.k7);
+
+                    self.err = dt * (splat(E1) * self.k1 + splat(E3) * self.k3 + splat(E4) * self.k4
+                                     + splat(E5) * self.k5 + splat(E6) * self.k6 + splat(E7) * self.k7);
+
+                    check(system)?;
+                    self.stats.rhs_evaluations += 6;
+                    let error = self.error_control.error(&self.err, state, &self.temp);
+                    // The error estimate of the embedded 4th order solution is O(dt^5).
+                    if self.adapt(error, 5)? {
+                        break;
+                    }
+                }
+                system.update_state(state, &self.temp);
+                Ok(())
+            }
+        }
+
+        impl<C> Stepper for DormandPrince5<$tuple, C>
+            where C: StepSizeController,
+        {
+            type State = $tuple;
+
+            fn do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State)
+                where Sy: NonautonomousOde<State = Self::State>,
+            {
+                if let Err(error) = self.step(system, state) {
+                    panic!("DormandPrince5: {}", error);
+                }
+            }
+
+            fn try_do_step<Sy>(&mut self, system: &mut Sy, state: &mut Self::State) -> Result<(), Error>
+                where Sy: NonautonomousOde<State = Self::State>,
+                      Self::State: Componentwise,
+            {
+                error::guarded_step(self, system, state, |stepper, system, state| {
+                    // The work of a failed step is undone along with the step.
+                    let stats = stepper.stats;
+                    let result = stepper.step_checked(system, state, Guarded::check);
+                    if result.is_err() {
+                        stepper.stats = stats;
+                    }
+                    result
+                })
             }
 
             fn timestep(&self) -> f64 {
                 self.dt
             }
+
+            fn time(&self) -> f64 {
+                self.t
+            }
+
+            fn set_time(&mut self, t: f64) {
+                self.t = t;
+            }
+
+            fn set_timestep(&mut self, dt: f64) {
+                self.dt = dt;
+            }
+
+            fn stats(&self) -> &Stats {
+                &self.stats
+            }
+
+            fn reset_stats(&mut self) {
+                self.stats = Stats::new();
+            }
+
+            fn integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> f64
+                where Sy: NonautonomousOde<State = Self::State>,
+            {
+                self.integrate_n_accepted(system, state, n, &mut ())
+            }
+
+            fn integrate_time<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, t: f64) -> (f64, usize)
+                where Sy: NonautonomousOde<State = Self::State>,
+            {
+                self.integrate_until(system, state, t, &mut ())
+            }
+
+            fn integrate_n_steps_with<Sy, O>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize, observer: &mut O) -> f64
+                where Sy: NonautonomousOde<State = Self::State>,
+                      O: Observer<Self::State>,
+            {
+                self.integrate_n_accepted(system, state, n, observer)
+            }
+
+            fn integrate_time_with<Sy, O>(&mut self, system: &mut Sy, state: &mut Self::State, t: f64, observer: &mut O) -> (f64, usize)
+                where Sy: NonautonomousOde<State = Self::State>,
+                      O: Observer<Self::State>,
+            {
+                self.integrate_until(system, state, t, observer)
+            }
+
+            fn integrate_time_with_events<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, t: f64, events: &mut [Event<'_, Self::State>]) -> (f64, Vec<EventOccurrence<Self::State>>)
+                where Self: DenseOutput,
+                      Sy: NonautonomousOde<State = Self::State>,
+            {
+                adaptive::integrate_until_with_events(self, system, state, t, events)
+            }
+
+            fn try_integrate_n_steps<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, n: usize) -> Result<f64, Error>
+                where Sy: NonautonomousOde<State = Self::State>,
+                      Self::State: Componentwise,
+            {
+                self.try_integrate_n_accepted(system, state, n)
+            }
+
+            fn try_integrate_time<Sy>(&mut self, system: &mut Sy, state: &mut Self::State, t: f64) -> Result<(f64, usize), Error>
+                where Sy: NonautonomousOde<State = Self::State>,
+                      Self::State: Componentwise,
+            {
+                self.try_integrate_until(system, state, t)
+            }
+        }
+
+        impl DenseOutput for Euler<$tuple>
+        {
+            fn last_timestep(&self) -> f64 {
+                self.last_dt
+            }
+
+            fn interpolate(&self, theta: f64, out: &mut Self::State) {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+
+                *out = self.temp + splat(self.dense_weight(theta)) * self.k1;
+            }
+        }
+
+        impl DenseOutput for ExplicitRungeKutta<$tuple>
+        {
+            fn last_timestep(&self) -> f64 {
+                self.last_dt
+            }
+
+            fn interpolate(&self, theta: f64, out: &mut Self::State) {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+
+                *out = self.temp;
+                for (i, k_i) in self.k.iter().enumerate() {
+                    *out += splat(self.dense_weight(theta, i)) * *k_i;
+                }
+            }
+        }
+
+        impl DenseOutput for Heun<$tuple>
+        {
+            fn last_timestep(&self) -> f64 {
+                self.last_dt
+            }
+
+            fn interpolate(&self, theta: f64, out: &mut Self::State) {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+                let (w1, w2) = self.dense_weights(theta);
+
+                *out = self.temp + splat(w1) * self.k1 + splat(w2) * self.k2;
+            }
+        }
+
+        impl DenseOutput for RungeKutta4<$tuple>
+        {
+            fn last_timestep(&self) -> f64 {
+                self.last_dt
+            }
+
+            fn interpolate(&self, theta: f64, out: &mut Self::State) {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+                let (w1, w2, w3, w4) = self.dense_weights(theta);
+
+                *out = self.temp
+                    + splat(w1) * self.k1
+                    + splat(w2) * self.k2
+                    + splat(w3) * self.k3
+                    + splat(w4) * self.k4;
+            }
+        }
+
+        impl<C> DenseOutput for DormandPrince5<$tuple, C>
+            where C: StepSizeController,
+        {
+            fn last_timestep(&self) -> f64 {
+                self.last_dt
+            }
+
+            fn interpolate(&self, theta: f64, out: &mut Self::State) {
+                let splat = |c: f64| <$tuple as Splat<_>>::splat(c);
+                let [w1, w3, w4, w5, w6, w7] = self.dense_weights(theta);
+
+                *out = self.temp
+                    + splat(w1) * self.k1
+                    + splat(w3) * self.k3
+                    + splat(w4) * self.k4
+                    + splat(w5) * self.k5
+                    + splat(w6) * self.k6
+                    + splat(w7) * self.k7;
+            }
         }
     };
     ( $( $tuple:ty ),+ ) => {
@@ -0,0 +1,146 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = a * x ⇒ x(t) = exp(a*t) x(0)
+struct Exponential {
+    a: f64,
+}
+
+impl Ode for Exponential {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = self.a * x;
+    }
+}
+
+// Independent decays dx_i/dt = -r_i * x_i
+struct Decay {
+    rates: Vec<f64>,
+}
+
+impl Ode for Decay {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+        for ((d, x), r) in into.iter_mut().zip(x).zip(&self.rates) {
+            *d = -r * x;
+        }
+    }
+}
+
+// dx/dt = cos(t) ⇒ x(t) = sin(t) + x(0)
+struct Driven;
+
+impl NonautonomousOde for Driven {
+    type State = f64;
+
+    fn differentiate_at_into(&mut self, t: f64, _x: &f64, into: &mut f64) {
+        *into = t.cos();
+    }
+}
+
+// d²x/dt² = -x, written as a first order system.
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, state: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = state[1];
+        into[1] = -state[0];
+    }
+}
+
+#[test]
+fn dormand_prince_hits_final_time() {
+    let mut sys = Exponential { a: 1.0 };
+    let mut x = 1.0;
+
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-12, 1e-10);
+    let (t, steps) = stepper.integrate_time(&mut sys, &mut x, 1.0);
+
+    assert_eq!(t, 1.0);
+    assert!(steps > 0);
+    assert_relative_eq!(x, f64::exp(1.0), max_relative = 1e-9);
+}
+
+#[test]
+fn dormand_prince_oscillator_period() {
+    let mut sys = Oscillator;
+    let mut state = array![1.0, 0.0];
+
+    let period = 2.0 * std::f64::consts::PI;
+
+    let mut stepper = DormandPrince5::new(&state, 0.01).with_tolerances(1e-10, 1e-10);
+    stepper.integrate_time(&mut sys, &mut state, period);
+
+    assert_relative_eq!(state[0], 1.0, epsilon = 1e-8);
+    assert_relative_eq!(state[1], 0.0, epsilon = 1e-8);
+}
+
+#[test]
+fn dormand_prince_rejects_too_large_step() {
+    let mut sys = Decay {
+        rates: vec![1.0, 10.0, 50.0],
+    };
+    let mut state = vec![1.0; 3];
+
+    let mut stepper = DormandPrince5::new(&state, 10.0).with_tolerances(1e-10, 1e-8);
+    let t = stepper.integrate_n_steps(&mut sys, &mut state, 1);
+
+    assert!(t < 10.0);
+    assert_eq!(t, stepper.last_timestep());
+    for (x, r) in state.iter().zip(&sys.rates) {
+        assert_relative_eq!(*x, f64::exp(-r * t), epsilon = 1e-8);
+    }
+}
+
+#[test]
+fn dormand_prince_time_dependent() {
+    let mut sys = Driven;
+    let mut x = 0.0;
+
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+    stepper.set_time(1.0);
+    stepper.integrate_time(&mut sys, &mut x, 2.0);
+
+    assert_eq!(stepper.time(), 3.0);
+    assert_relative_eq!(x, f64::sin(3.0) - f64::sin(1.0), epsilon = 1e-9);
+}
+
+#[test]
+fn dormand_prince_backward_in_time() {
+    let mut sys = Driven;
+    let mut x = 0.0;
+
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+    stepper.set_time(3.0);
+    let (t, _) = stepper.integrate_time(&mut sys, &mut x, -2.0);
+
+    assert_eq!(t, -2.0);
+    assert_eq!(stepper.time(), 1.0);
+    assert!(stepper.timestep() < 0.0);
+    assert_relative_eq!(x, f64::sin(1.0) - f64::sin(3.0), epsilon = 1e-9);
+
+    let (t, _) = stepper.integrate_time(&mut sys, &mut x, 2.0);
+    assert_eq!(t, 2.0);
+    assert_eq!(stepper.time(), 3.0);
+    assert_relative_eq!(x, 0.0, epsilon = 1e-9);
+}
+
+#[test]
+fn dormand_prince_fast_time_scales() {
+    // Step sizes far below f64::EPSILON are fine near t = 0.
+    let mut x = 1.0;
+    let mut stepper = DormandPrince5::new(&x, 1e-19).with_tolerances(1e-12, 1e-8);
+    let mut system = Exponential { a: -1e20 };
+
+    stepper
+        .try_integrate_time(&mut system, &mut x, 1e-19)
+        .unwrap();
+    assert_relative_eq!(x, (-10f64).exp(), max_relative = 1e-6);
+}
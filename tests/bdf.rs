@@ -0,0 +1,198 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// The Robertson chemical kinetics in residual form, with the conservation of mass as an
+// algebraic equation,
+//   0 = dy1/dt + 0.04 y1 - 1e4 y2 y3
+//   0 = dy2/dt - 0.04 y1 + 1e4 y2 y3 + 3e7 y2²
+//   0 = y1 + y2 + y3 - 1
+struct Robertson;
+
+impl Residual for Robertson {
+    fn residual_into(
+        &mut self,
+        _t: f64,
+        y: &Array1<f64>,
+        dy: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) {
+        residual[0] = dy[0] + 0.04 * y[0] - 1e4 * y[1] * y[2];
+        residual[1] = dy[1] - 0.04 * y[0] + 1e4 * y[1] * y[2] + 3e7 * y[1] * y[1];
+        residual[2] = y[0] + y[1] + y[2] - 1.0;
+    }
+}
+
+impl ResidualJacobian for Robertson {
+    fn residual_jacobian_into(
+        &mut self,
+        _t: f64,
+        y: &Array1<f64>,
+        _dy: &Array1<f64>,
+        alpha: f64,
+        jacobian: &mut Array2<f64>,
+    ) {
+        jacobian.assign(&array![
+            [alpha + 0.04, -1e4 * y[2], -1e4 * y[1]],
+            [-0.04, alpha + 1e4 * y[2] + 6e7 * y[1], 1e4 * y[1]],
+            [1.0, 1.0, 1.0],
+        ]);
+    }
+}
+
+// The implicit ODE (1 + x²) dx/dt + x (1 + x²) = 0, so that x(t) = exp(-t) x(0).
+struct Implicit;
+
+impl Residual for Implicit {
+    fn residual_into(
+        &mut self,
+        _t: f64,
+        x: &Array1<f64>,
+        dx: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) {
+        residual[0] = (1.0 + x[0] * x[0]) * (dx[0] + x[0]);
+    }
+}
+
+// dx/dt = -x, refusing to be evaluated after t = 1.
+struct Expiring;
+
+impl Residual for Expiring {
+    fn residual_into(
+        &mut self,
+        t: f64,
+        x: &Array1<f64>,
+        dx: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) {
+        self.try_residual_into(t, x, dx, residual).unwrap();
+    }
+
+    fn try_residual_into(
+        &mut self,
+        t: f64,
+        x: &Array1<f64>,
+        dx: &Array1<f64>,
+        residual: &mut Array1<f64>,
+    ) -> Result<(), Error> {
+        if t > 1.0 {
+            return Err(Error::Domain(format!("t = {} is too late", t)));
+        }
+        residual[0] = dx[0] + x[0];
+        Ok(())
+    }
+}
+
+#[test]
+fn robertson() {
+    let mut y = array![1.0, 0.0, 0.0];
+    let mut dy = Array1::zeros(3);
+    let mut stepper = Bdf::new(&y, 1e-6)
+        .with_tolerances(1e-10, 1e-6)
+        .with_algebraic_variables(&[2]);
+    stepper
+        .make_consistent(&mut Robertson, &mut y, &mut dy)
+        .unwrap();
+    assert_relative_eq!(dy[0], -0.04, epsilon = 1e-14);
+    assert_relative_eq!(dy[1], 0.04, epsilon = 1e-14);
+
+    let (t, steps) = stepper
+        .integrate_time(&mut Robertson, &mut y, &mut dy, 40.0)
+        .unwrap();
+    assert_eq!(t, 40.0);
+    assert_eq!(stepper.time(), 40.0);
+
+    // The reference solution of Hairer and Wanner.
+    assert_relative_eq!(y[0], 0.715_827_068_9, max_relative = 1e-4);
+    assert_relative_eq!(y[1], 9.185_534_765e-6, max_relative = 1e-3);
+    assert_relative_eq!(y[2], 0.284_163_745_6, max_relative = 1e-4);
+    assert_relative_eq!(y.sum(), 1.0, epsilon = 1e-12);
+    assert_relative_eq!(dy[0], -0.04 * y[0] + 1e4 * y[1] * y[2], max_relative = 1e-3);
+
+    assert!(stepper.order() > 2);
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, steps);
+    assert!(stats.jacobian_evaluations >= steps);
+    assert!(stats.newton_iterations >= steps);
+}
+
+#[test]
+fn finite_differences() {
+    let mut y = array![1.0, 0.0, 0.0];
+    let mut dy = Array1::zeros(3);
+    let mut system = FiniteDifference::new(Robertson);
+    let mut stepper = Bdf::new(&y, 1e-6)
+        .with_tolerances(1e-10, 1e-6)
+        .with_algebraic_variables(&[2]);
+    stepper
+        .make_consistent(&mut system, &mut y, &mut dy)
+        .unwrap();
+    stepper
+        .integrate_time(&mut system, &mut y, &mut dy, 40.0)
+        .unwrap();
+
+    assert_relative_eq!(y[0], 0.715_827_068_9, max_relative = 1e-4);
+    assert_relative_eq!(y[2], 0.284_163_745_6, max_relative = 1e-4);
+}
+
+#[test]
+fn consistent_derivative_of_an_implicit_ode() {
+    let mut x = array![2.0];
+    let mut dx = array![0.0];
+    let mut system = FiniteDifference::new(Implicit);
+    let mut stepper = Bdf::new(&x, 1e-3).with_tolerances(1e-10, 1e-10);
+    stepper
+        .make_consistent(&mut system, &mut x, &mut dx)
+        .unwrap();
+    assert_relative_eq!(dx[0], -2.0, max_relative = 1e-8);
+
+    stepper
+        .integrate_time(&mut system, &mut x, &mut dx, 2.0)
+        .unwrap();
+    let exact = 2.0 * (-2f64).exp();
+    assert_relative_eq!(x[0], exact, max_relative = 1e-7);
+    assert_relative_eq!(dx[0], -exact, max_relative = 1e-5);
+}
+
+#[test]
+fn accuracy_follows_the_tolerance() {
+    let error = |tolerance: f64| {
+        let mut x = array![1.0];
+        let mut dx = array![-1.0];
+        let mut stepper = Bdf::new(&x, 1e-3).with_tolerances(tolerance, tolerance);
+        stepper
+            .integrate_time(&mut FiniteDifference::new(Implicit), &mut x, &mut dx, 1.0)
+            .unwrap();
+        (x[0] - (-1f64).exp()).abs()
+    };
+
+    assert!(error(1e-9) < error(1e-5) / 100.0);
+}
+
+#[test]
+fn reports_errors_of_the_residual() {
+    let mut x = array![1.0];
+    let mut dx = array![-1.0];
+    let mut stepper = Bdf::new(&x, 0.1);
+    let mut system = FiniteDifference::new(Expiring);
+    match stepper.integrate_time(&mut system, &mut x, &mut dx, 2.0) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("{:?}", other),
+    }
+    assert!(stepper.time() <= 1.0);
+    assert!(stepper.stats().accepted_steps > 0);
+}
+
+#[test]
+fn reports_errors_of_the_finite_differences() {
+    let (x, dx) = (array![1.0], array![-1.0]);
+    let mut system = FiniteDifference::new(Expiring);
+    let mut jacobian = Array2::zeros((1, 1));
+    match system.try_residual_jacobian_into(2.0, &x, &dx, 1.0, &mut jacobian) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("{:?}", other),
+    }
+}
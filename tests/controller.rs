@@ -0,0 +1,109 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// The harmonic oscillator, x = (cos t, -sin t) for x(0) = (1, 0)
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = x[1];
+        into[1] = -x[0];
+    }
+}
+
+fn accepted(step: StepSize) -> f64 {
+    match step {
+        StepSize::Accepted(dt) => dt,
+        other => panic!("expected an accepted step, got {:?}", other),
+    }
+}
+
+fn rejected(step: StepSize) -> f64 {
+    match step {
+        StepSize::Rejected(dt) => dt,
+        other => panic!("expected a rejected step, got {:?}", other),
+    }
+}
+
+#[test]
+fn integral_controller() {
+    let mut controller = IController::new();
+
+    assert_relative_eq!(accepted(controller.adapt(1.0, 1.0 / 32.0, 5)), 1.8);
+    assert_relative_eq!(rejected(controller.adapt(1.0, 32.0, 5)), 0.45);
+
+    // The factor is bounded, and rejected steps never grow.
+    assert_relative_eq!(accepted(controller.adapt(1.0, 0.0, 5)), 5.0);
+    assert_relative_eq!(rejected(controller.adapt(1.0, 1e10, 5)), 0.2);
+    assert_relative_eq!(rejected(controller.adapt(1.0, f64::NAN, 5)), 0.2);
+
+    let mut controller = IController::new()
+        .with_safety(1.0)
+        .with_factor_bounds(0.5, 1.5)
+        .with_timestep_bounds(0.1, 1.2);
+    assert_relative_eq!(accepted(controller.adapt(1.0, 1.0 / 32.0, 5)), 1.2);
+    assert_relative_eq!(rejected(controller.adapt(0.15, 1e10, 5)), 0.1);
+
+    // The direction of integration is kept.
+    assert_relative_eq!(accepted(controller.adapt(-1.0, 1.0 / 32.0, 5)), -1.2);
+}
+
+#[test]
+fn pi_controller_uses_previous_error() {
+    let mut pi = PiController::new().with_safety(1.0).with_gains(1.0, 0.5);
+
+    // Without a previous step, this is the integral controller.
+    assert_relative_eq!(accepted(pi.adapt(1.0, 1.0 / 32.0, 5)), 2.0);
+    // Now the small previous error damps the growth.
+    assert_relative_eq!(accepted(pi.adapt(1.0, 1.0 / 32.0, 5)), f64::sqrt(2.0));
+
+    // Rejections don't change the memory.
+    assert_relative_eq!(rejected(pi.adapt(1.0, 32.0, 5)), 0.5);
+    assert_relative_eq!(accepted(pi.adapt(1.0, 1.0, 5)), f64::powf(2.0, -0.5));
+}
+
+#[test]
+fn pid_controller_uses_step_size_ratio() {
+    let mut pid = PidController::h211b(4.0).with_safety(1.0);
+    let error = f64::powi(2.0, -10);
+
+    // error^(-1/20) = sqrt(2)
+    assert_relative_eq!(accepted(pid.adapt(1.0, error, 5)), f64::sqrt(2.0));
+
+    // Doubling the step size with the same error contributes (dt/dt_prev)^(-1/4), and the memory
+    // of the previous error another factor of sqrt(2).
+    assert_relative_eq!(
+        accepted(pid.adapt(2.0, error, 5)),
+        2.0 * 2.0 * f64::powf(2.0, -0.25),
+        max_relative = 1e-14
+    );
+}
+
+fn integrate<C>(controller: C) -> Array1<f64>
+where
+    C: StepSizeController,
+{
+    let mut x = array![1.0, 0.0];
+    let mut stepper = DormandPrince5::new(&x, 0.1)
+        .with_tolerances(1e-10, 1e-10)
+        .with_controller(controller);
+    stepper.integrate_time(&mut Oscillator, &mut x, 10.0);
+    x
+}
+
+#[test]
+fn dormand_prince_with_controllers() {
+    for x in &[
+        integrate(IController::new()),
+        integrate(PiController::new()),
+        integrate(PidController::new()),
+    ] {
+        assert_relative_eq!(x[0], f64::cos(10.0), epsilon = 1e-8);
+        assert_relative_eq!(x[1], -f64::sin(10.0), epsilon = 1e-8);
+    }
+}
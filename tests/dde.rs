@@ -0,0 +1,156 @@
+use freude::*;
+
+// dx/dt = -x(t - 1), with x(t) = 1 for t <= 0.
+struct DelayedDecay;
+
+impl Dde for DelayedDecay {
+    type State = f64;
+
+    fn delays(&self) -> Vec<f64> {
+        vec![1.0]
+    }
+
+    fn differentiate_into(&mut self, t: f64, _x: &f64, history: &History<f64>, into: &mut f64) {
+        *into = -history.state_at(t - 1.0);
+    }
+}
+
+// The solution of `DelayedDecay` by the method of steps, a polynomial of degree n + 1 on
+// [n, n + 1].
+fn delayed_decay(t: f64) -> f64 {
+    let mut x = 0.0;
+    let mut factorial = 1.0;
+    for k in 0..=(t.floor() as i32 + 1) {
+        if k > 0 {
+            factorial *= f64::from(k);
+        }
+        let s = t - f64::from(k) + 1.0;
+        if s > 0.0 {
+            x += (-s).powi(k) / factorial;
+        }
+    }
+    x
+}
+
+// The pantograph equation dx/dt = -x(t/2), whose delay t/2 vanishes at t = 0.
+struct Pantograph;
+
+impl Dde for Pantograph {
+    type State = Vec<f64>;
+
+    fn differentiate_into(
+        &mut self,
+        t: f64,
+        _x: &Vec<f64>,
+        history: &History<Vec<f64>>,
+        into: &mut Vec<f64>,
+    ) {
+        into[0] = -history.state_at(t / 2.0)[0];
+    }
+}
+
+// x(t) = Σ_k (-t)^k / (k! 2^(k (k - 1) / 2)) for x(0) = 1.
+fn pantograph(t: f64) -> f64 {
+    let mut x = 0.0;
+    let mut term = 1.0;
+    for k in 0..40 {
+        x += term;
+        term *= -t / (f64::from(k + 1) * 2f64.powi(k));
+    }
+    x
+}
+
+#[test]
+fn steps_onto_the_discontinuities() {
+    let mut x = 1.0;
+    let mut integrator = DdeIntegrator::new(RungeKutta4::new(&x, 0.3), |_, x: &mut f64| *x = 1.0);
+    let (t, _) = integrator.integrate_time(&mut DelayedDecay, &mut x, 3.0);
+    assert_eq!(t, 3.0);
+
+    let times = integrator.history().times();
+    for discontinuity in &[1.0, 2.0, 3.0] {
+        assert!(times.contains(discontinuity));
+    }
+
+    // The solution is piecewise cubic up to t = 3, which RK4 and the interpolation of the
+    // history reproduce exactly.
+    assert!((x - delayed_decay(3.0)).abs() < 1e-12, "{}", x);
+    assert_eq!(integrator.stepper().timestep(), 0.3);
+}
+
+#[test]
+fn tracking_the_discontinuities_improves_the_accuracy() {
+    let error = |order| {
+        let mut x = 1.0;
+        let mut integrator =
+            DdeIntegrator::new(RungeKutta4::new(&x, 0.3), |_, x: &mut f64| *x = 1.0)
+                .with_discontinuity_order(order);
+        integrator.integrate_time(&mut DelayedDecay, &mut x, 3.0);
+        (x - delayed_decay(3.0)).abs()
+    };
+
+    assert!(error(3) < error(0) / 100.0);
+}
+
+#[test]
+fn adaptive_integration() {
+    let mut x = 1.0;
+    let stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+    let mut integrator = DdeIntegrator::new(stepper, |_, x: &mut f64| *x = 1.0);
+    integrator.integrate_time(&mut DelayedDecay, &mut x, 5.0);
+
+    assert!((x - delayed_decay(5.0)).abs() < 1e-8, "{}", x);
+    for t in &[0.5, 1.5, 2.25, 4.0] {
+        let past = integrator.history().state_at(*t);
+        assert!((past - delayed_decay(*t)).abs() < 1e-7);
+    }
+}
+
+#[test]
+fn vanishing_delay() {
+    let mut x = vec![1.0];
+    let mut integrator =
+        DdeIntegrator::new(RungeKutta4::new(&x, 0.01), |_, x: &mut Vec<f64>| x[0] = 1.0);
+    integrator.integrate_n_steps(&mut Pantograph, &mut x, 200);
+
+    assert!((integrator.stepper().time() - 2.0).abs() < 1e-12);
+    assert!((x[0] - pantograph(2.0)).abs() < 1e-8, "{}", x[0]);
+}
+
+// `DelayedDecay`, counting the evaluations of its right-hand side.
+struct CountingDecay {
+    evaluations: usize,
+}
+
+impl Dde for CountingDecay {
+    type State = f64;
+
+    fn delays(&self) -> Vec<f64> {
+        vec![1.0]
+    }
+
+    fn differentiate_into(&mut self, t: f64, x: &f64, history: &History<f64>, into: &mut f64) {
+        self.evaluations += 1;
+        DelayedDecay.differentiate_into(t, x, history, into);
+    }
+}
+
+#[test]
+fn stats_count_the_recorded_derivatives() {
+    let mut x = 1.0;
+    let mut integrator = DdeIntegrator::new(RungeKutta4::new(&x, 0.3), |_, x: &mut f64| *x = 1.0);
+    let mut system = CountingDecay { evaluations: 0 };
+    integrator.integrate_n_steps(&mut system, &mut x, 10);
+
+    // One derivative per step is recorded, and one at the initial time.
+    let stats = integrator.stats();
+    assert_eq!(stats.accepted_steps, 10);
+    assert_eq!(stats.rhs_evaluations, system.evaluations);
+    assert_eq!(
+        stats.rhs_evaluations,
+        integrator.stepper().stats().rhs_evaluations + 11
+    );
+
+    integrator.reset_stats();
+    assert_eq!(integrator.stats(), Stats::new());
+}
@@ -0,0 +1,171 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = v, dv/dt = -x
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = state[1];
+        into[1] = -state[0];
+    }
+}
+
+// dx/dt = -x
+struct Decay;
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-x);
+    }
+}
+
+// dx/dt = cos(t)
+struct Forced;
+
+impl NonautonomousOde for Forced {
+    type State = f64;
+
+    fn differentiate_at_into(&mut self, t: f64, _x: &f64, into: &mut f64) {
+        *into = t.cos();
+    }
+}
+
+fn assert_reproduces_endpoints<St>(mut stepper: St)
+where
+    St: DenseOutput<State = Array1<f64>>,
+{
+    let x0 = array![1.0, -2.0];
+    let mut x = x0.clone();
+    stepper.do_step(&mut Decay, &mut x);
+
+    let mut out = Array1::zeros(2);
+    stepper.interpolate(0.0, &mut out);
+    assert_relative_eq!(out[0], x0[0], max_relative = 1e-14);
+    assert_relative_eq!(out[1], x0[1], max_relative = 1e-14);
+
+    stepper.interpolate(1.0, &mut out);
+    assert_relative_eq!(out[0], x[0], max_relative = 1e-14);
+    assert_relative_eq!(out[1], x[1], max_relative = 1e-14);
+}
+
+#[test]
+fn endpoints_are_reproduced() {
+    let x = Array1::zeros(2);
+    assert_reproduces_endpoints(Euler::new(&x, 0.3));
+    assert_reproduces_endpoints(ExplicitRungeKutta::new(&x, 0.3, ButcherTableau::ssprk3()));
+    assert_reproduces_endpoints(Heun::new(&x, 0.3));
+    assert_reproduces_endpoints(RungeKutta4::new(&x, 0.3));
+    assert_reproduces_endpoints(DormandPrince5::new(&x, 0.3));
+}
+
+#[test]
+fn dormand_prince_dense_output_is_accurate() {
+    let mut x = vec![1.0, 0.0];
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-12, 1e-12);
+
+    let mut out = vec![0.0; 2];
+    let mut largest = 0f64;
+    while stepper.time() < 5.0 {
+        stepper.do_step(&mut Oscillator, &mut x);
+
+        let t0 = stepper.time() - stepper.last_timestep();
+        for &theta in &[0.1, 0.25, 0.5, 0.75, 0.9] {
+            stepper.interpolate(theta, &mut out);
+            let t = t0 + theta * stepper.last_timestep();
+            largest = largest.max((out[0] - t.cos()).abs());
+            largest = largest.max((out[1] + t.sin()).abs());
+        }
+    }
+
+    assert!(largest < 1e-8, "largest interpolation error {:e}", largest);
+}
+
+#[test]
+fn hermite_interpolation_is_third_order() {
+    // The error of the interpolant in the middle of a single step shrinks like dt^4.
+    let error = |dt: f64| {
+        let mut x = array![1.0];
+        let mut stepper = RungeKutta4::new(&x, dt);
+        stepper.do_step(&mut Decay, &mut x);
+
+        let mut out = Array1::zeros(1);
+        stepper.interpolate(0.5, &mut out);
+        (out[0] - (-0.5 * dt).exp()).abs()
+    };
+
+    let ratio = error(0.2) / error(0.1);
+    assert!(ratio > 12.0 && ratio < 20.0, "error ratio {}", ratio);
+}
+
+#[test]
+fn interpolate_at_uses_the_integration_time() {
+    let mut x = 0.0;
+    let mut stepper = RungeKutta4::new(&x, 0.2);
+    stepper.set_time(1.0);
+    stepper.integrate_n_steps(&mut Forced, &mut x, 3);
+
+    let mut out = 0.0;
+    for &t in &[1.4, 1.45, 1.5, 1.55, 1.6] {
+        stepper.interpolate_at(t, &mut out);
+        assert_relative_eq!(out, t.sin() - 1f64.sin(), epsilon = 1e-4);
+    }
+}
+
+#[test]
+fn interpolation_backward_in_time() {
+    let mut x = vec![1.0, 0.0];
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-12, 1e-12);
+    stepper.integrate_time(&mut Oscillator, &mut x, -1.0);
+
+    assert!(stepper.last_timestep() < 0.0);
+
+    let mut out = vec![0.0; 2];
+    let t = stepper.time() - 0.5 * stepper.last_timestep();
+    stepper.interpolate_at(t, &mut out);
+    assert_relative_eq!(out[0], t.cos(), epsilon = 1e-8);
+    assert_relative_eq!(out[1], -t.sin(), epsilon = 1e-8);
+}
+
+fn assert_implicit_interpolant<St>(mut stepper: St, order: i32)
+where
+    St: ImplicitDenseOutput<State = Array1<f64>>,
+{
+    let x0 = array![1.0, -2.0];
+    let mut x = x0.clone();
+    stepper.set_time(1.0);
+    stepper
+        .do_step(&mut FiniteDifference::new(Decay), &mut x)
+        .unwrap();
+
+    let mut out = Array1::zeros(2);
+    stepper.interpolate_at(1.0, &mut out);
+    assert!(out.all_close(&x0, 1e-12));
+    stepper.interpolate(1.0, &mut out);
+    assert!(out.all_close(&x, 1e-12));
+
+    // The interpolant inside the step is as accurate as the method.
+    let dt = stepper.last_timestep();
+    stepper.interpolate(0.5, &mut out);
+    let exact = &x0 * (-0.5 * dt).exp();
+    assert!(
+        (out[0] - exact[0]).abs() < 2.0 * dt.powi(order),
+        "{:?}",
+        out
+    );
+}
+
+#[test]
+fn implicit_steppers_interpolate() {
+    let x = Array1::zeros(2);
+    assert_implicit_interpolant(BackwardEuler::new(&x, 0.05), 2);
+    assert_implicit_interpolant(Trapezoidal::new(&x, 0.05), 3);
+    assert_implicit_interpolant(ImplicitMidpoint::new(&x, 0.05), 2);
+}
@@ -0,0 +1,254 @@
+use std::error::Error as _;
+
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = -1 / (2 sqrt(x)), which is only defined for x >= 0; x(t) = (1 - 3t/4)^(2/3) reaches
+// zero at t = 4/3.
+struct Sqrt;
+
+impl Ode for Sqrt {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        self.try_differentiate_into(x, into).unwrap();
+    }
+
+    fn try_differentiate_into(&mut self, x: &f64, into: &mut f64) -> Result<(), Error> {
+        if *x < 0.0 {
+            return Err(Error::Domain(format!("sqrt of {}", x)));
+        }
+        *into = -0.5 / x.sqrt();
+        Ok(())
+    }
+}
+
+// dx/dt = -x, aborting the integration after t = 1.
+struct AbortingDecay;
+
+impl NonautonomousOde for AbortingDecay {
+    type State = Array1<f64>;
+
+    fn differentiate_at_into(&mut self, t: f64, x: &Array1<f64>, into: &mut Array1<f64>) {
+        self.try_differentiate_at_into(t, x, into).unwrap();
+    }
+
+    fn try_differentiate_at_into(
+        &mut self,
+        t: f64,
+        x: &Array1<f64>,
+        into: &mut Array1<f64>,
+    ) -> Result<(), Error> {
+        if t > 1.0 {
+            return Err(Error::Aborted);
+        }
+        into.assign(&-x);
+        Ok(())
+    }
+}
+
+// dx/dt = 1, which is only defined for x <= 1.05, counting its evaluations.
+struct Bounded {
+    evaluations: usize,
+}
+
+impl Ode for Bounded {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        self.try_differentiate_into(x, into).unwrap();
+    }
+
+    fn try_differentiate_into(&mut self, x: &f64, into: &mut f64) -> Result<(), Error> {
+        self.evaluations += 1;
+        if *x > 1.05 {
+            return Err(Error::Domain(format!("{} out of bounds", x)));
+        }
+        *into = 1.0;
+        Ok(())
+    }
+}
+
+// dx/dt = x^2, which blows up at t = 1 / x(0).
+struct Blowup;
+
+impl Ode for Blowup {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = x[0] * x[0];
+    }
+}
+
+// dx/dt = 1, but NaN after t = 0.5.
+struct NanAfterHalf;
+
+impl NonautonomousOde for NanAfterHalf {
+    type State = f64;
+
+    fn differentiate_at_into(&mut self, t: f64, _x: &f64, into: &mut f64) {
+        *into = if t > 0.5 { f64::NAN } else { 1.0 };
+    }
+}
+
+#[test]
+fn domain_error_leaves_state_unchanged() {
+    let mut x = 1.0;
+    let mut stepper = RungeKutta4::new(&x, 0.1);
+
+    let result = stepper.try_integrate_time(&mut Sqrt, &mut x, 2.0);
+
+    match result {
+        Err(Error::Domain(_)) => {}
+        other => panic!("expected a domain error, got {:?}", other),
+    }
+    // The failing step is undone; the steps before it are kept.
+    assert!(x > 0.0 && x < 1.0);
+    assert!(stepper.time() > 1.0 && stepper.time() < 4.0 / 3.0);
+
+    let mut y = 1.0;
+    let mut reference = RungeKutta4::new(&y, 0.1);
+    let steps = (stepper.time() / 0.1).round() as usize;
+    reference.integrate_n_steps(&mut Sqrt, &mut y, steps);
+    assert_eq!(x, y);
+}
+
+#[test]
+fn aborted_by_the_right_hand_side() {
+    let mut x = array![1.0, 2.0];
+    let mut stepper = Heun::new(&x, 0.25);
+
+    let result = stepper.try_integrate_n_steps(&mut AbortingDecay, &mut x, 10);
+
+    // The step from t = 1 evaluates the right-hand side at t = 1.25.
+    assert_eq!(result, Err(Error::Aborted));
+    assert_relative_eq!(stepper.time(), 1.0);
+    assert_relative_eq!(x[0], 0.78125f64.powi(4), epsilon = 1e-12);
+}
+
+#[test]
+fn non_finite_state() {
+    let mut x = vec![1.0];
+    let mut stepper = Euler::new(&x, 0.5);
+
+    // 1 -> 1.5 -> 2.625 -> 6.07 -> ... overflows after a few more steps.
+    let result = stepper.try_integrate_n_steps(&mut Blowup, &mut x, 20);
+
+    match result {
+        Err(Error::NonFinite { time }) => assert_relative_eq!(time, stepper.time()),
+        other => panic!("expected a non-finite state, got {:?}", other),
+    }
+    assert!(x[0].is_finite());
+}
+
+#[test]
+fn step_size_underflow() {
+    let mut x = 0.0;
+    let mut stepper = DormandPrince5::new(&x, 0.1);
+
+    let result = stepper.try_integrate_time(&mut NanAfterHalf, &mut x, 1.0);
+
+    match result {
+        Err(Error::StepSizeUnderflow { time, timestep }) => {
+            assert!(time <= 0.5);
+            assert!(timestep.abs() <= 4.0 * f64::EPSILON * time.abs());
+        }
+        other => panic!("expected a step size underflow, got {:?}", other),
+    }
+    assert_relative_eq!(x, stepper.time(), epsilon = 1e-12);
+}
+
+#[test]
+fn dormand_prince_reports_domain_errors() {
+    let mut x = 1.0;
+    let mut stepper = DormandPrince5::new(&x, 0.1);
+
+    match stepper.try_integrate_time(&mut Sqrt, &mut x, 2.0) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("expected a domain error, got {:?}", other),
+    }
+    assert!(x >= 0.0);
+}
+
+#[test]
+fn failed_steps_restore_the_timestep() {
+    let mut x = 1.0;
+    let mut stepper = DormandPrince5::new(&x, 0.1);
+
+    // The rejected attempts of the failing step shrink the step size before the error.
+    loop {
+        let (t, dt) = (stepper.time(), stepper.timestep());
+        if let Err(error) = stepper.try_do_step(&mut Sqrt, &mut x) {
+            match error {
+                Error::Domain(_) => {}
+                other => panic!("expected a domain error, got {:?}", other),
+            }
+            assert_eq!(stepper.time(), t);
+            assert_eq!(stepper.timestep(), dt);
+            break;
+        }
+    }
+}
+
+#[test]
+fn domain_errors_fail_the_step_right_away() {
+    let mut x = 1.0;
+    let mut stepper = DormandPrince5::new(&x, 0.1);
+    let mut system = Bounded { evaluations: 0 };
+
+    // The stages of the step reach beyond x = 1.05, which fails the first attempt rather than
+    // shrinking the step size until it underflows.
+    match stepper.try_do_step(&mut system, &mut x) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("expected a domain error, got {:?}", other),
+    }
+    assert_eq!(x, 1.0);
+    assert_eq!(stepper.time(), 0.0);
+    assert_eq!(stepper.timestep(), 0.1);
+    assert!(system.evaluations <= 7);
+
+    // The failed step is undone, including its work.
+    assert_eq!(*stepper.stats(), Stats::new());
+
+    // A smaller step stays within the bounds.
+    stepper.set_timestep(0.02);
+    stepper
+        .try_do_step(&mut system, &mut x)
+        .unwrap_or_else(|error| panic!("DormandPrince5: {}", error));
+    assert_relative_eq!(x, 1.02, epsilon = 1e-12);
+    assert_eq!(stepper.stats().accepted_steps, 1);
+    assert_eq!(stepper.stats().rejected_steps, 0);
+    assert_eq!(stepper.stats().rhs_evaluations, 7);
+}
+
+#[test]
+fn maximum_number_of_steps() {
+    let mut stepper = RungeKutta4::new(&1.0, 0.1);
+    let options = SolveOptions::new().with_max_steps(5);
+
+    let result = try_solve(&mut stepper, &mut Sqrt, 1.0, 0.9, &options);
+    assert_eq!(
+        result.map(|solution| solution.len()),
+        Err(Error::MaxStepsExceeded { steps: 5 })
+    );
+
+    let options = SolveOptions::new().with_max_steps(10);
+    let solution = try_solve(&mut stepper, &mut Sqrt, 1.0, 0.0, &options).unwrap();
+    assert_eq!(solution.times()[solution.len() - 1], 0.0);
+}
+
+#[test]
+fn newton_errors_are_the_source() {
+    let error = Error::from(NewtonError::SingularMatrix);
+
+    assert_eq!(error, Error::Newton(NewtonError::SingularMatrix));
+    assert!(error.source().is_some());
+    assert!(Error::Aborted.source().is_none());
+    assert_eq!(
+        Error::MaxStepsExceeded { steps: 3 }.to_string(),
+        "maximum number of 3 steps exceeded"
+    );
+}
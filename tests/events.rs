@@ -0,0 +1,179 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// A ball falling under gravity, x = (height, velocity)
+struct Ball;
+
+impl Ode for Ball {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = x[1];
+        into[1] = -9.81;
+    }
+}
+
+// The harmonic oscillator, x = (sin t, cos t) for x(0) = (0, 1)
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = x[1];
+        into[1] = -x[0];
+    }
+}
+
+#[test]
+fn terminal_event_stops_integration() {
+    let mut x = array![10.0, 0.0];
+    let mut stepper = RungeKutta4::new(&x, 0.1);
+
+    let mut events = [Event::new(|_t, x: &Array1<f64>| x[0])
+        .with_direction(Direction::Falling)
+        .with_terminal(true)];
+    let (tacc, occurrences) =
+        stepper.integrate_time_with_events(&mut Ball, &mut x, 10.0, &mut events);
+
+    let impact = f64::sqrt(2.0 * 10.0 / 9.81);
+    assert_eq!(occurrences.len(), 1);
+    assert_eq!(occurrences[0].index, 0);
+    assert!(occurrences[0].terminal);
+    assert_relative_eq!(occurrences[0].time, impact, epsilon = 1e-9);
+    assert_relative_eq!(tacc, impact, epsilon = 1e-9);
+    assert_relative_eq!(stepper.time(), impact, epsilon = 1e-9);
+    assert_eq!(x, occurrences[0].state);
+    assert!(x[0] <= 0.0);
+    assert_relative_eq!(x[0], 0.0, epsilon = 1e-8);
+}
+
+#[test]
+fn direction_filtering() {
+    let x0 = array![0.0, 1.0];
+
+    let mut x = x0.clone();
+    let mut stepper = RungeKutta4::new(&x, 0.01);
+
+    let mut events = [
+        Event::new(|_t, x: &Array1<f64>| x[0]).with_direction(Direction::Rising),
+        Event::new(|_t, x: &Array1<f64>| x[0]).with_direction(Direction::Falling),
+        Event::new(|_t, x: &Array1<f64>| x[0]),
+    ];
+    let (_, occurrences) =
+        stepper.integrate_time_with_events(&mut Oscillator, &mut x, 10.0, &mut events);
+
+    let times = |index| {
+        occurrences
+            .iter()
+            .filter(|occurrence| occurrence.index == index)
+            .map(|occurrence| occurrence.time)
+            .collect::<Vec<_>>()
+    };
+
+    let pi = std::f64::consts::PI;
+    let expected = [
+        vec![2.0 * pi],
+        vec![pi, 3.0 * pi],
+        vec![pi, 2.0 * pi, 3.0 * pi],
+    ];
+    for (index, expected) in expected.iter().enumerate() {
+        let times = times(index);
+        assert_eq!(times.len(), expected.len());
+        for (t, t_expected) in times.iter().zip(expected) {
+            assert_relative_eq!(t, t_expected, epsilon = 1e-8);
+        }
+    }
+
+    // The events are reported in order.
+    assert!(occurrences.windows(2).all(|w| w[0].time <= w[1].time));
+
+    // Non-terminal events don't affect the integration.
+    let mut y = x0.clone();
+    RungeKutta4::new(&y, 0.01).integrate_time(&mut Oscillator, &mut y, 10.0);
+    assert_eq!(x, y);
+}
+
+#[test]
+fn time_dependent_event() {
+    let mut x = array![0.0, 1.0];
+    let mut stepper = RungeKutta4::new(&x, 0.1);
+    stepper.set_time(1.0);
+
+    // Triggered once the phase t + x_0 crosses 2.5
+    let mut events = [Event::new(|t, x: &Array1<f64>| t + x[0] - 2.5).with_terminal(true)];
+    let (_, occurrences) =
+        stepper.integrate_n_steps_with_events(&mut Oscillator, &mut x, 100, &mut events);
+
+    let t = occurrences[0].time;
+    assert_relative_eq!(t + occurrences[0].state[0], 2.5, epsilon = 1e-9);
+
+    // x_0 = sin(t - 1), up to the error of the method
+    assert_relative_eq!(t + f64::sin(t - 1.0), 2.5, epsilon = 1e-5);
+}
+
+#[test]
+fn adaptive_stepper() {
+    let mut x = array![0.0, 1.0];
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+
+    let mut events = [Event::new(|_t, x: &Array1<f64>| x[1])
+        .with_direction(Direction::Rising)
+        .with_terminal(true)];
+    let (_, occurrences) =
+        stepper.integrate_time_with_events(&mut Oscillator, &mut x, 10.0, &mut events);
+
+    assert_eq!(occurrences.len(), 1);
+    assert_relative_eq!(
+        occurrences[0].time,
+        1.5 * std::f64::consts::PI,
+        epsilon = 1e-8
+    );
+    assert_relative_eq!(x[0], -1.0, epsilon = 1e-8);
+}
+
+#[test]
+fn adaptive_stepper_reaches_the_end() {
+    let mut x = array![0.0, 1.0];
+    let mut stepper = DormandPrince5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+
+    let mut events = [Event::new(|_t, x: &Array1<f64>| x[0])];
+    let (tacc, occurrences) =
+        stepper.integrate_time_with_events(&mut Oscillator, &mut x, 10.0, &mut events);
+
+    assert_eq!(tacc, 10.0);
+    assert_eq!(stepper.time(), 10.0);
+    assert_relative_eq!(x[0], f64::sin(10.0), epsilon = 1e-8);
+
+    let pi = std::f64::consts::PI;
+    assert_eq!(occurrences.len(), 3);
+    for (occurrence, t) in occurrences.iter().zip(&[pi, 2.0 * pi, 3.0 * pi]) {
+        assert_relative_eq!(occurrence.time, t, epsilon = 1e-8);
+    }
+}
+
+#[test]
+fn locating_events_leaves_the_step_intact() {
+    let mut x = array![0.0, 1.0];
+    let mut stepper = RungeKutta4::new(&x, 0.5);
+
+    let mut events = [Event::new(|_t, x: &Array1<f64>| x[0] - 0.3)];
+    let (_, occurrences) =
+        stepper.integrate_n_steps_with_events(&mut Oscillator, &mut x, 1, &mut events);
+    assert_eq!(occurrences.len(), 1);
+    assert_relative_eq!(occurrences[0].time, 0.3f64.asin(), epsilon = 1e-3);
+
+    // The dense output still covers the step that was taken.
+    let mut out = Array1::zeros(2);
+    stepper.interpolate_at(0.5, &mut out);
+    assert_eq!(out, x);
+    stepper.interpolate_at(0.25, &mut out);
+    assert_relative_eq!(out[0], 0.25f64.sin(), epsilon = 1e-3);
+
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, 1);
+    assert_eq!(stats.rhs_evaluations, 4);
+}
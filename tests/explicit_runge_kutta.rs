@@ -0,0 +1,127 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx_i/dt = cos(t) x_i
+struct Modulated;
+
+impl NonautonomousOde for Modulated {
+    type State = Array1<f64>;
+
+    fn differentiate_at_into(&mut self, t: f64, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(x);
+        *into *= t.cos();
+    }
+}
+
+// dx/dt = x ⇒ x(t) = exp(t) x(0)
+struct Exponential;
+
+impl Ode for Exponential {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = *x;
+    }
+}
+
+macro_rules! mk_tableau_test {
+    ($stepper:ident, $tableau:ident) => {
+        #[allow(non_snake_case)]
+        mod $stepper {
+            use approx::assert_relative_eq;
+            use ndarray::array;
+
+            use super::{Exponential, Modulated};
+            use freude::*;
+
+            #[test]
+            fn same_as_handwritten_scalar() {
+                let mut x1 = 1.0;
+                let mut x2 = x1;
+
+                let mut handwritten = $stepper::new(&x1, 0.1);
+                let mut generic = ExplicitRungeKutta::new(&x2, 0.1, ButcherTableau::$tableau());
+
+                handwritten.integrate_n_steps(&mut Exponential, &mut x1, 20);
+                generic.integrate_n_steps(&mut Exponential, &mut x2, 20);
+
+                assert_relative_eq!(x1, x2, max_relative = 1e-13);
+            }
+
+            #[test]
+            fn same_as_handwritten_array() {
+                let mut x1 = array![1.0, -0.5, 2.0];
+                let mut x2 = x1.clone();
+
+                let mut handwritten = $stepper::new(&x1, 0.1);
+                let mut generic = ExplicitRungeKutta::new(&x2, 0.1, ButcherTableau::$tableau());
+
+                handwritten.integrate_n_steps(&mut Modulated, &mut x1, 20);
+                generic.integrate_n_steps(&mut Modulated, &mut x2, 20);
+
+                assert_relative_eq!(handwritten.time(), generic.time());
+                for (a, b) in x1.iter().zip(&x2) {
+                    assert_relative_eq!(a, b, max_relative = 1e-13);
+                }
+            }
+        }
+    };
+
+    ($($stepper:ident:$tableau:ident),+) => {
+        $(
+            mk_tableau_test!($stepper, $tableau);
+        )+
+    };
+}
+
+mk_tableau_test!(Euler:euler,Heun:heun,RungeKutta4:rk4);
+
+fn global_error(tableau: ButcherTableau, dt: f64) -> f64 {
+    let mut x = vec![1.0];
+    let steps = (1.0 / dt).round() as usize;
+
+    struct VecExponential;
+
+    impl Ode for VecExponential {
+        type State = Vec<f64>;
+
+        fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+            into.clone_from(x);
+        }
+    }
+
+    let mut stepper = ExplicitRungeKutta::new(&x, dt, tableau);
+    stepper.integrate_n_steps(&mut VecExponential, &mut x, steps);
+
+    (x[0] - f64::exp(1.0)).abs()
+}
+
+#[test]
+fn convergence_order() {
+    let methods = vec![
+        (ButcherTableau::midpoint(), 2.0),
+        (ButcherTableau::ralston(), 2.0),
+        (ButcherTableau::ssprk3(), 3.0),
+        (ButcherTableau::three_eighths(), 4.0),
+    ];
+
+    for (tableau, order) in methods {
+        let coarse = global_error(tableau.clone(), 0.02);
+        let fine = global_error(tableau, 0.01);
+
+        assert_relative_eq!(f64::log2(coarse / fine), order, epsilon = 0.1);
+    }
+}
+
+#[test]
+#[should_panic]
+fn reject_implicit_tableau() {
+    ButcherTableau::new(
+        array![[0.5, 0.0], [0.5, 0.5]],
+        array![0.5, 0.5],
+        array![0.5, 1.0],
+    );
+}
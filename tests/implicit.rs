@@ -0,0 +1,176 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// Independent decays dx_i/dt = -r_i * x_i
+struct Decay {
+    rates: Array1<f64>,
+}
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&(-&self.rates * x));
+    }
+}
+
+// dx/dt = -x on a Vec
+struct VecDecay;
+
+impl Ode for VecDecay {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+        for (d, x) in into.iter_mut().zip(x) {
+            *d = -x;
+        }
+    }
+}
+
+// The Van der Pol oscillator, dx/dt = y, dy/dt = μ (1 - x²) y - x
+struct VanDerPol {
+    mu: f64,
+}
+
+impl Ode for VanDerPol {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, state: &Array1<f64>, into: &mut Array1<f64>) {
+        let (x, y) = (state[0], state[1]);
+        into[0] = y;
+        into[1] = self.mu * (1.0 - x * x) * y - x;
+    }
+}
+
+impl Jacobian for VanDerPol {
+    fn jacobian_into(&mut self, _t: f64, state: &Array1<f64>, jacobian: &mut Array2<f64>) {
+        let (x, y) = (state[0], state[1]);
+        jacobian[(0, 0)] = 0.0;
+        jacobian[(0, 1)] = 1.0;
+        jacobian[(1, 0)] = -2.0 * self.mu * x * y - 1.0;
+        jacobian[(1, 1)] = self.mu * (1.0 - x * x);
+    }
+}
+
+#[test]
+fn stiff_decay_is_stable() {
+    let rates = array![1000.0, 1.0];
+    let mut system = FiniteDifference::new(Decay { rates });
+
+    let mut x1 = array![1.0, 1.0];
+    let mut x2 = x1.clone();
+    let mut x3 = x1.clone();
+
+    BackwardEuler::new(&x1, 0.1)
+        .integrate_n_steps(&mut system, &mut x1, 10)
+        .unwrap();
+    Trapezoidal::new(&x2, 0.1)
+        .integrate_n_steps(&mut system, &mut x2, 10)
+        .unwrap();
+    ImplicitMidpoint::new(&x3, 0.1)
+        .integrate_n_steps(&mut system, &mut x3, 10)
+        .unwrap();
+
+    assert!(x1[0].abs() < 1e-10);
+    assert!(x2[0].abs() <= 1.0);
+    assert!(x3[0].abs() <= 1.0);
+
+    let exact = f64::exp(-1.0);
+    assert_relative_eq!(x1[1], exact, max_relative = 0.1);
+    assert_relative_eq!(x2[1], exact, max_relative = 1e-3);
+    assert_relative_eq!(x3[1], exact, max_relative = 1e-3);
+}
+
+fn global_error<St>(mut stepper: St, dt: f64) -> f64
+where
+    St: ImplicitStepper<State = Vec<f64>>,
+{
+    let mut system = FiniteDifference::new(VecDecay);
+    let mut x = vec![1.0];
+
+    let steps = (1.0 / dt).round() as usize;
+    stepper
+        .integrate_n_steps(&mut system, &mut x, steps)
+        .unwrap();
+
+    (x[0] - f64::exp(-1.0)).abs()
+}
+
+#[test]
+fn convergence_order() {
+    let x = vec![1.0];
+
+    let ratio = global_error(BackwardEuler::new(&x, 0.02), 0.02)
+        / global_error(BackwardEuler::new(&x, 0.01), 0.01);
+    assert_relative_eq!(ratio.log2(), 1.0, epsilon = 0.1);
+
+    let ratio = global_error(Trapezoidal::new(&x, 0.02), 0.02)
+        / global_error(Trapezoidal::new(&x, 0.01), 0.01);
+    assert_relative_eq!(ratio.log2(), 2.0, epsilon = 0.1);
+
+    let ratio = global_error(ImplicitMidpoint::new(&x, 0.02), 0.02)
+        / global_error(ImplicitMidpoint::new(&x, 0.01), 0.01);
+    assert_relative_eq!(ratio.log2(), 2.0, epsilon = 0.1);
+}
+
+#[test]
+fn analytic_jacobian_matches_finite_difference() {
+    let mut analytic = VanDerPol { mu: 5.0 };
+    let mut approximated = FiniteDifference::new(VanDerPol { mu: 5.0 });
+
+    let mut x1 = array![2.0, 0.0];
+    let mut x2 = x1.clone();
+
+    let mut stepper = Trapezoidal::new(&x1, 0.01);
+    stepper
+        .integrate_n_steps(&mut analytic, &mut x1, 100)
+        .unwrap();
+
+    let mut stepper = Trapezoidal::new(&x2, 0.01);
+    stepper
+        .integrate_n_steps(&mut approximated, &mut x2, 100)
+        .unwrap();
+
+    assert_relative_eq!(x1[0], x2[0], epsilon = 1e-8);
+    assert_relative_eq!(x1[1], x2[1], epsilon = 1e-8);
+}
+
+#[test]
+fn reports_non_convergence() {
+    let mut system = VanDerPol { mu: 5.0 };
+    let mut x = array![2.0, 0.0];
+
+    let mut stepper = BackwardEuler::new(&x, 0.5).with_max_iterations(1);
+    let result = stepper.do_step(&mut system, &mut x);
+
+    match result {
+        Err(Error::Newton(NewtonError::NotConverged { iterations, .. })) => {
+            assert_eq!(iterations, 1)
+        }
+        other => panic!("expected a convergence failure, got {:?}", other),
+    }
+    assert_eq!(x, array![2.0, 0.0]);
+    assert_eq!(stepper.time(), 0.0);
+}
+
+#[test]
+fn backward_in_time() {
+    let mut system = FiniteDifference::new(VecDecay);
+    let x0 = vec![1.0];
+    let mut x = x0.clone();
+
+    let mut stepper = Trapezoidal::new(&x, 0.01);
+    let (forward, forward_steps) = stepper.integrate_time(&mut system, &mut x, 1.0).unwrap();
+    let (t, steps) = stepper
+        .integrate_time(&mut system, &mut x, -forward)
+        .unwrap();
+
+    assert_eq!(t, -forward);
+    assert_eq!(steps, forward_steps);
+    assert_relative_eq!(stepper.time(), 0.0, epsilon = 1e-12);
+    // The trapezoidal rule is symmetric, so going back undoes the forward steps.
+    assert_relative_eq!(x[0], x0[0], epsilon = 1e-12);
+}
@@ -0,0 +1,95 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = a * x
+struct Exponential {
+    a: f64,
+}
+
+impl Ode for Exponential {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = self.a * x;
+    }
+}
+
+// Independent decays dx_i/dt = -r_i * x_i
+struct Decay {
+    rates: Array1<f64>,
+}
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&(-&self.rates * x));
+    }
+}
+
+#[test]
+fn scales_with_the_dynamics() {
+    let control = ErrorControl::new(1e-6, 1e-6);
+
+    let slow = initial_timestep(&mut Exponential { a: 1.0 }, 0.0, &1.0, 10.0, 4, &control);
+    let fast = initial_timestep(&mut Exponential { a: 100.0 }, 0.0, &1.0, 10.0, 4, &control);
+
+    assert!(slow > 0.0);
+    assert!(fast < slow);
+
+    // Tighter tolerances result in smaller steps.
+    let tight = ErrorControl::new(1e-10, 1e-10);
+    let smaller = initial_timestep(&mut Exponential { a: 1.0 }, 0.0, &1.0, 10.0, 4, &tight);
+    assert!(smaller < slow);
+}
+
+#[test]
+fn bounded_and_directed() {
+    let control = ErrorControl::new(1e-3, 1e-3);
+    let mut system = Decay {
+        rates: array![1e-6, 1e-5],
+    };
+    let x = array![1.0, 1.0];
+
+    let dt = initial_timestep(&mut system, 0.0, &x, 0.5, 4, &control);
+    assert_eq!(dt, 0.5);
+
+    let dt = initial_timestep(&mut system, 0.0, &x, -0.5, 4, &control);
+    assert_eq!(dt, -0.5);
+
+    // Also at rest
+    let dt = initial_timestep(&mut system, 0.0, &array![0.0, 0.0], 1.0, 4, &control);
+    assert!(dt > 0.0 && dt <= 1.0);
+}
+
+#[test]
+fn good_enough_for_a_fixed_step() {
+    let control = ErrorControl::new(1e-8, 1e-8);
+    let mut system = Exponential { a: -2.0 };
+
+    let mut x = 1.0;
+    let dt = initial_timestep(&mut system, 0.0, &x, 1.0, 4, &control);
+    let steps = (1.0 / dt).ceil() as usize;
+    RungeKutta4::new(&x, 1.0 / steps as f64).integrate_n_steps(&mut system, &mut x, steps);
+
+    assert_relative_eq!(x, f64::exp(-2.0), max_relative = 1e-6);
+}
+
+#[test]
+fn dormand_prince_starts_with_the_proposal() {
+    let mut system = Decay {
+        rates: array![50.0, 1.0],
+    };
+    let mut x = array![1.0, 1.0];
+
+    let mut stepper = DormandPrince5::new(&x, 1.0).with_tolerances(1e-8, 1e-8);
+    stepper.select_initial_timestep(&mut system, &x, 1.0);
+    assert!(stepper.timestep() < 0.1);
+
+    stepper.integrate_time(&mut system, &mut x, 1.0);
+    assert_relative_eq!(x[0], f64::exp(-50.0), epsilon = 1e-8);
+    assert_relative_eq!(x[1], f64::exp(-1.0), max_relative = 1e-6);
+}
@@ -0,0 +1,157 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// The Van der Pol oscillator, dx/dt = y, dy/dt = μ (1 - x²) y - x
+struct VanDerPol {
+    mu: f64,
+}
+
+impl Ode for VanDerPol {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, state: &Array1<f64>, into: &mut Array1<f64>) {
+        let (x, y) = (state[0], state[1]);
+        into[0] = y;
+        into[1] = self.mu * (1.0 - x * x) * y - x;
+    }
+}
+
+impl Jacobian for VanDerPol {
+    fn jacobian_into(&mut self, _t: f64, state: &Array1<f64>, jacobian: &mut Array2<f64>) {
+        let (x, y) = (state[0], state[1]);
+        jacobian[(0, 0)] = 0.0;
+        jacobian[(0, 1)] = 1.0;
+        jacobian[(1, 0)] = -2.0 * self.mu * x * y - 1.0;
+        jacobian[(1, 1)] = self.mu * (1.0 - x * x);
+    }
+}
+
+// dx_0/dt = x_0 x_1, dx_1/dt = sin(t x_0) on a Vec
+struct Coupled;
+
+impl NonautonomousOde for Coupled {
+    type State = Vec<f64>;
+
+    fn differentiate_at_into(&mut self, t: f64, x: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = x[0] * x[1];
+        into[1] = f64::sin(t * x[0]);
+    }
+}
+
+fn analytic(state: &Array1<f64>) -> Array2<f64> {
+    let mut jacobian = Array2::zeros((2, 2));
+    VanDerPol { mu: 5.0 }.jacobian_into(0.0, state, &mut jacobian);
+    jacobian
+}
+
+fn approximated(system: &mut FiniteDifference<VanDerPol>, state: &Array1<f64>) -> Array2<f64> {
+    let mut jacobian = Array2::zeros((2, 2));
+    system.jacobian_into(0.0, state, &mut jacobian);
+    jacobian
+}
+
+#[test]
+fn forward_difference() {
+    let state = array![1.5, -2.0];
+    let mut system = FiniteDifference::new(VanDerPol { mu: 5.0 });
+
+    let expected = analytic(&state);
+    let jacobian = approximated(&mut system, &state);
+
+    for (a, b) in jacobian.iter().zip(&expected) {
+        assert_relative_eq!(a, b, epsilon = 1e-6);
+    }
+}
+
+#[test]
+fn central_difference() {
+    let state = array![1.5, -2.0];
+    let mut system =
+        FiniteDifference::new(VanDerPol { mu: 5.0 }).with_differencing(Differencing::Central);
+
+    let expected = analytic(&state);
+    let jacobian = approximated(&mut system, &state);
+
+    for (a, b) in jacobian.iter().zip(&expected) {
+        assert_relative_eq!(a, b, epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn custom_perturbation() {
+    let state = array![1.5, -2.0];
+    let mut system = FiniteDifference::new(VanDerPol { mu: 5.0 }).with_perturbation(1e-3);
+    assert_eq!(system.perturbation(), 1e-3);
+
+    let expected = analytic(&state);
+    let jacobian = approximated(&mut system, &state);
+
+    // The truncation error of forward differences is of the order of the perturbation.
+    let error = (&jacobian - &expected)
+        .mapv(f64::abs)
+        .fold(0.0, |m: f64, &e| m.max(e));
+    assert!(error > 1e-6);
+    assert!(error < 1e-1);
+}
+
+#[test]
+fn vec_state() {
+    let t = 0.5;
+    let state = vec![2.0, 3.0];
+    let mut system = FiniteDifference::new(Coupled).with_differencing(Differencing::Central);
+
+    let mut jacobian = Array2::zeros((2, 2));
+    system.jacobian_into(t, &state, &mut jacobian);
+
+    let expected = array![[3.0, 2.0], [t * f64::cos(t * 2.0), 0.0]];
+    for (a, b) in jacobian.iter().zip(&expected) {
+        assert_relative_eq!(a, b, epsilon = 1e-9);
+    }
+}
+
+// dx/dt = sqrt(1 - x), which is only defined for x <= 1.
+struct Capped;
+
+impl Ode for Capped {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        self.try_differentiate_into(x, into).unwrap();
+    }
+
+    fn try_differentiate_into(
+        &mut self,
+        x: &Array1<f64>,
+        into: &mut Array1<f64>,
+    ) -> Result<(), Error> {
+        if x[0] > 1.0 {
+            return Err(Error::Domain(format!("sqrt of {}", 1.0 - x[0])));
+        }
+        into[0] = (1.0 - x[0]).sqrt();
+        Ok(())
+    }
+}
+
+#[test]
+fn errors_of_the_perturbed_right_hand_side() {
+    // The perturbation moves the state out of the domain.
+    let mut x = array![1.0];
+    let mut system = FiniteDifference::new(Capped);
+    let mut jacobian = Array2::zeros((1, 1));
+    match system.try_jacobian_into(0.0, &x, &mut jacobian) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("expected a domain error, got {:?}", other),
+    }
+
+    // The implicit steppers report them instead of panicking.
+    let mut stepper = BackwardEuler::new(&x, 0.1);
+    match stepper.do_step(&mut system, &mut x) {
+        Err(Error::Domain(_)) => {}
+        other => panic!("expected a domain error, got {:?}", other),
+    }
+    assert_eq!(x, array![1.0]);
+    assert_eq!(stepper.time(), 0.0);
+}
@@ -1,4 +1,4 @@
-use freude::Ode;
+use freude::{NonautonomousOde, Ode};
 
 // Some generic Ode with dx/dt = a * x ⇒ x(t) = c * exp(a*t)
 #[derive(Clone)]
@@ -15,6 +15,19 @@ impl Ode for SimpleODE {
     }
 }
 
+// A driven system dx/dt = cos(ω t) ⇒ x(t) = sin(ω t) / ω + x(0)
+struct DrivenODE {
+    omega: f64,
+}
+
+impl NonautonomousOde for DrivenODE {
+    type State = f64;
+
+    fn differentiate_at_into(&mut self, t: f64, _x: &f64, into: &mut f64) {
+        *into = f64::cos(self.omega * t);
+    }
+}
+
 macro_rules! mk_stepper_test {
     ($stepper:ident, $error_order:expr) => {
         #[allow(non_snake_case)]
@@ -22,7 +35,7 @@ macro_rules! mk_stepper_test {
             use approx::assert_relative_eq;
 
             use freude::*;
-            use super::SimpleODE;
+            use super::{DrivenODE, SimpleODE};
 
             #[test]
             fn stepper() {
@@ -78,6 +91,89 @@ macro_rules! mk_stepper_test {
 
                 assert_relative_eq!(x1, x2);
             }
+
+            #[test]
+            fn integrate_time_exact() {
+                let mut sys = SimpleODE { a: 1., c: 1. };
+
+                let mut x1 = 1.0;
+                let mut x2 = x1;
+
+                let timestep = 0.1;
+                let total_time = 1.05;
+
+                let mut stepper = $stepper::new(&x1, timestep);
+                let (tacc, count, remainder) = stepper.integrate_time_exact(&mut sys, &mut x1, total_time);
+
+                assert_eq!(tacc, total_time);
+                assert_eq!(count, 11);
+                assert_relative_eq!(remainder, 0.05, epsilon = 1e-12);
+                assert_eq!(stepper.time(), total_time);
+                assert_eq!(stepper.timestep(), timestep);
+
+                let mut stepper = $stepper::new(&x2, timestep);
+                stepper.integrate_n_steps(&mut sys, &mut x2, 10);
+                $stepper::new(&x2, remainder).do_step(&mut sys, &mut x2);
+                assert_relative_eq!(x1, x2, max_relative = 1e-14);
+
+                let exact = sys.c * f64::exp(sys.a * total_time);
+                assert_relative_eq!(exact, x1, max_relative=(count as f64 * timestep.powi($error_order)));
+
+                // Multiples of the step size need no shortened step.
+                let mut stepper = $stepper::new(&x1, timestep);
+                let (_, count, remainder) = stepper.integrate_time_exact(&mut sys, &mut x1, 1.0);
+                assert_eq!(count, 10);
+                assert_eq!(remainder, 0.0);
+            }
+
+            #[test]
+            fn backward_in_time() {
+                let mut sys = SimpleODE { a: 1., c: 1. };
+                let x0 = 1.0;
+                let mut x = x0;
+
+                let timestep = 0.1;
+                let total_time = 1.0;
+
+                let mut stepper = $stepper::new(&x, timestep);
+                let (forward, steps) = stepper.integrate_time(&mut sys, &mut x, total_time);
+                assert_relative_eq!(x, sys.c * f64::exp(sys.a * total_time), max_relative=(steps as f64 * timestep.powi($error_order)));
+
+                let (backward, back_steps) = stepper.integrate_time(&mut sys, &mut x, -forward);
+
+                assert_eq!(back_steps, steps);
+                assert_eq!(backward, -forward);
+                assert!(stepper.timestep() < 0.0);
+                assert_relative_eq!(stepper.time(), 0.0, epsilon = 1e-12);
+                assert_relative_eq!(x0, x, max_relative=(2.0 * steps as f64 * timestep.powi($error_order)));
+
+                // A negative step size integrates backward, too.
+                let mut stepper = $stepper::new(&x, -timestep);
+                stepper.integrate_n_steps(&mut sys, &mut x, steps);
+                assert_relative_eq!(stepper.time(), -forward, epsilon = 1e-12);
+
+                let (t, _, _) = stepper.integrate_time_exact(&mut sys, &mut x, -0.55);
+                assert_eq!(t, -0.55);
+                assert_relative_eq!(stepper.time(), -forward - 0.55, epsilon = 1e-12);
+            }
+
+            #[test]
+            fn time_dependent() {
+                let mut sys = DrivenODE { omega: 2.0 };
+                let mut x = 0.0;
+
+                let timestep = 0.1;
+                let steps = 10;
+                let total_time = timestep * steps as f64;
+
+                let exact = f64::sin(sys.omega * total_time) / sys.omega;
+
+                let mut stepper = $stepper::new(&x, timestep);
+                stepper.integrate_n_steps(&mut sys, &mut x, steps);
+
+                assert_relative_eq!(stepper.time(), total_time);
+                assert_relative_eq!(exact, x, max_relative=(steps as f64 * timestep.powi($error_order)));
+            }
         }
     };
 
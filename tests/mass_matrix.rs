@@ -0,0 +1,186 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// The Robertson chemical kinetics with the conservation of mass as an algebraic equation,
+//   dy1/dt = -0.04 y1 + 1e4 y2 y3
+//   dy2/dt = 0.04 y1 - 1e4 y2 y3 - 3e7 y2²
+//        0 = y1 + y2 + y3 - 1
+struct Robertson;
+
+impl Ode for Robertson {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, y: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = -0.04 * y[0] + 1e4 * y[1] * y[2];
+        into[1] = 0.04 * y[0] - 1e4 * y[1] * y[2] - 3e7 * y[1] * y[1];
+        into[2] = y[0] + y[1] + y[2] - 1.0;
+    }
+}
+
+impl Jacobian for Robertson {
+    fn jacobian_into(&mut self, _t: f64, y: &Array1<f64>, jacobian: &mut Array2<f64>) {
+        jacobian.assign(&array![
+            [-0.04, 1e4 * y[2], 1e4 * y[1]],
+            [0.04, -1e4 * y[2] - 6e7 * y[1], -1e4 * y[1]],
+            [1.0, 1.0, 1.0],
+        ]);
+    }
+}
+
+impl MassMatrix for Robertson {
+    fn mass_matrix_into(&mut self, _t: f64, mass: &mut Array2<f64>) {
+        mass.assign(&array![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]]);
+    }
+}
+
+// dx/dt = -y with the algebraic equation 0 = y - x, so that x(t) = y(t) = exp(-t).
+struct SemiExplicit;
+
+impl Ode for SemiExplicit {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = -state[1];
+        into[1] = state[1] - state[0];
+    }
+}
+
+impl MassMatrix for SemiExplicit {
+    fn mass_matrix_into(&mut self, _t: f64, mass: &mut Array2<f64>) {
+        mass.assign(&array![[1.0, 0.0], [0.0, 0.0]]);
+    }
+}
+
+// M dx/dt = -M x with a full mass matrix, so that x(t) = exp(-t) x(0).
+struct FullMass;
+
+impl Ode for FullMass {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-mass().dot(x));
+    }
+}
+
+impl MassMatrix for FullMass {
+    fn mass_matrix_into(&mut self, _t: f64, mass_matrix: &mut Array2<f64>) {
+        mass_matrix.assign(&mass());
+    }
+}
+
+fn mass() -> Array2<f64> {
+    array![[2.0, 1.0], [1.0, 1.0]]
+}
+
+// dx/dt = -x with a given singular mass matrix.
+struct SingularMass(Array2<f64>);
+
+impl Ode for SingularMass {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-x);
+    }
+}
+
+impl MassMatrix for SingularMass {
+    fn mass_matrix_into(&mut self, _t: f64, mass_matrix: &mut Array2<f64>) {
+        mass_matrix.assign(&self.0);
+    }
+}
+
+#[test]
+fn robertson() {
+    let mut y = array![1.0, 0.0, 0.0];
+    let mut stepper = Radau5::new(&y, 1e-6).with_tolerances(1e-10, 1e-8);
+    let (t, steps) = stepper
+        .integrate_time(&mut Robertson, &mut y, 40.0)
+        .unwrap();
+
+    assert_eq!(t, 40.0);
+    assert!(steps < 200, "{} steps", steps);
+
+    // The reference solution of Hairer and Wanner.
+    assert_relative_eq!(y[0], 0.715_827_068_9, max_relative = 1e-6);
+    assert_relative_eq!(y[1], 9.185_534_765e-6, max_relative = 1e-5);
+    assert_relative_eq!(y[2], 0.284_163_745_6, max_relative = 1e-6);
+    assert_relative_eq!(y.sum(), 1.0, epsilon = 1e-12);
+
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, steps);
+    assert!(stats.jacobian_evaluations >= steps);
+    assert!(stats.lu_factorizations >= 2 * steps);
+}
+
+#[test]
+fn consistent_initial_conditions() {
+    let mut y = array![1.0, 0.0, 0.5];
+    let mut stepper = Radau5::new(&y, 1e-6);
+    stepper
+        .make_consistent(&mut FiniteDifference::new(Robertson), &mut y)
+        .unwrap();
+    assert_eq!(y[0], 1.0);
+    assert_eq!(y[1], 0.0);
+    assert!(y[2].abs() < 1e-14);
+
+    let mut x = vec![1.0, 3.0];
+    let mut stepper = Radau5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+    let mut system = FiniteDifference::new(SemiExplicit);
+    stepper.make_consistent(&mut system, &mut x).unwrap();
+    assert_relative_eq!(x[1], 1.0, epsilon = 1e-12);
+
+    stepper.integrate_time(&mut system, &mut x, 2.0).unwrap();
+    assert_relative_eq!(x[0], (-2f64).exp(), max_relative = 1e-8);
+    assert_relative_eq!(x[1], x[0], max_relative = 1e-8);
+}
+
+#[test]
+fn full_mass_matrix() {
+    let mut x = array![1.0, -2.0];
+    let mut stepper = Radau5::new(&x, 0.1).with_tolerances(1e-10, 1e-10);
+    let mut system = FiniteDifference::new(FullMass);
+
+    // Without zero rows and columns, the state is already consistent.
+    stepper.make_consistent(&mut system, &mut x).unwrap();
+    assert_eq!(x, array![1.0, -2.0]);
+
+    stepper.integrate_time(&mut system, &mut x, 1.0).unwrap();
+    let exact = (-1f64).exp();
+    assert_relative_eq!(x[0], exact, max_relative = 1e-8);
+    assert_relative_eq!(x[1], -2.0 * exact, max_relative = 1e-8);
+}
+
+#[test]
+fn rejects_mass_matrices_that_are_not_semi_explicit() {
+    // A zero row without a zero column, and a singular mass matrix without either.
+    for mass in &[
+        array![[1.0, 1.0], [0.0, 0.0]],
+        array![[1.0, 1.0], [1.0, 1.0]],
+    ] {
+        let mut x = array![1.0, 2.0];
+        let mut stepper = Radau5::new(&x, 0.1);
+        let mut system = FiniteDifference::new(SingularMass(mass.clone()));
+        assert_eq!(
+            stepper.make_consistent(&mut system, &mut x),
+            Err(Error::SingularMassMatrix { time: 0.0 })
+        );
+        assert_eq!(x, array![1.0, 2.0]);
+    }
+}
+
+#[test]
+fn accuracy_follows_the_tolerance() {
+    let error = |tolerance: f64| {
+        let mut x = array![1.0, -2.0];
+        let mut stepper = Radau5::new(&x, 0.1).with_tolerances(tolerance, tolerance);
+        stepper
+            .integrate_time(&mut FiniteDifference::new(FullMass), &mut x, 1.0)
+            .unwrap();
+        (x[0] - (-1f64).exp()).abs()
+    };
+
+    assert!(error(1e-9) < error(1e-5) / 100.0);
+}
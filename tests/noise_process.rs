@@ -0,0 +1,173 @@
+use approx::assert_relative_eq;
+use ndarray::{arr2, Array1, Array2};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use freude::*;
+
+fn increments<W: Componentwise + Clone>(
+    noise: &mut NoiseProcess<W, StdRng>,
+    dt: f64,
+    n: usize,
+) -> Vec<W> {
+    (0..n).map(|_| noise.increment(dt).clone()).collect()
+}
+
+#[test]
+fn replays_the_same_path() {
+    let mut noise = NoiseProcess::new(&vec![0.0; 3], StdRng::seed_from_u64(1));
+    let first = increments(&mut noise, 0.1, 10);
+    assert_relative_eq!(noise.time(), 1.0, epsilon = 1e-12);
+
+    noise.set_time(0.0);
+    assert_eq!(increments(&mut noise, 0.1, 10), first);
+
+    // The same seed gives the same path, another one a different path.
+    let mut same = NoiseProcess::new(&vec![0.0; 3], StdRng::seed_from_u64(1));
+    assert_eq!(increments(&mut same, 0.1, 10), first);
+    let mut other = NoiseProcess::new(&vec![0.0; 3], StdRng::seed_from_u64(2));
+    assert_ne!(increments(&mut other, 0.1, 10), first);
+
+    // After clearing, the path is a new one.
+    noise.clear();
+    assert_eq!(noise.time(), 0.0);
+    assert_ne!(increments(&mut noise, 0.1, 10), first);
+}
+
+#[test]
+fn refined_paths_are_consistent() {
+    let mut noise = NoiseProcess::new(&Array2::<f64>::zeros((2, 2)), StdRng::seed_from_u64(3));
+    let coarse = increments(&mut noise, 0.5, 2);
+
+    // The fine increments are drawn from the Brownian bridge and add up to the coarse ones.
+    noise.set_time(0.0);
+    let fine = increments(&mut noise, 0.125, 8);
+    for (k, coarse) in coarse.iter().enumerate() {
+        let sum = fine[4 * k..4 * (k + 1)]
+            .iter()
+            .fold(Array2::zeros((2, 2)), |sum, dw| sum + dw);
+        for (a, b) in sum.iter().zip(coarse) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+    assert_eq!(noise.value_at(1.0), &(&coarse[0] + &coarse[1]));
+}
+
+#[test]
+fn bridge_has_the_law_of_the_wiener_process() {
+    let paths = 20_000;
+    let mut noise = NoiseProcess::new(&0.0, StdRng::seed_from_u64(4));
+
+    // Draw W(1) first, then W(1/4) and W(1/2) in between.
+    let (mut quarter, mut half, mut product) = (0.0, 0.0, 0.0);
+    for _ in 0..paths {
+        noise.clear();
+        let end = *noise.value_at(1.0);
+        let w_half = *noise.value_at(0.5);
+        let w_quarter = *noise.value_at(0.25);
+
+        quarter += w_quarter * w_quarter;
+        half += w_half * w_half;
+        product += w_quarter * end;
+    }
+    let n = f64::from(paths);
+    assert!(
+        (quarter / n - 0.25).abs() < 0.01,
+        "E[W(1/4)²] = {}",
+        quarter / n
+    );
+    assert!((half / n - 0.5).abs() < 0.02, "E[W(1/2)²] = {}", half / n);
+    assert!(
+        (product / n - 0.25).abs() < 0.01,
+        "E[W(1/4) W(1)] = {}",
+        product / n
+    );
+}
+
+#[test]
+fn correlated_noise() {
+    let covariance = arr2(&[[1.0, 0.5], [0.5, 2.0]]);
+    let mut noise = NoiseProcess::new(&Array1::<f64>::zeros(2), StdRng::seed_from_u64(5))
+        .with_covariance(&covariance);
+
+    let dt = 0.01;
+    let n = 20_000;
+    let mut sample = Array2::<f64>::zeros((2, 2));
+    for dw in increments(&mut noise, dt, n) {
+        for i in 0..2 {
+            for j in 0..2 {
+                sample[(i, j)] += dw[i] * dw[j] / (dt * n as f64);
+            }
+        }
+    }
+    for (a, b) in sample.iter().zip(&covariance) {
+        assert!(
+            (a - b).abs() < 0.05 * b.abs().max(1.0),
+            "{} vs {}",
+            sample,
+            covariance
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "not positive definite")]
+fn rejects_indefinite_covariances() {
+    NoiseProcess::new(&vec![0.0; 2], StdRng::seed_from_u64(6))
+        .with_covariance(&arr2(&[[1.0, 2.0], [2.0, 1.0]]));
+}
+
+#[test]
+fn integrals_are_consistent_with_refinement() {
+    let mut noise = NoiseProcess::new(&vec![0.0; 2], StdRng::seed_from_u64(7));
+    let (dw, dz) = noise.increment_with_integral(1.0);
+    let (dw, dz) = (dw.clone(), dz.clone());
+
+    // ∫_0^1 = ∫_0^(1/2) + ∫_(1/2)^1 + W(1/2) / 2, where the halves are drawn by the bridge.
+    noise.set_time(0.0);
+    let (first_dw, first_dz) = noise.increment_with_integral(0.5);
+    let (first_dw, first_dz) = (first_dw.clone(), first_dz.clone());
+    let (second_dw, second_dz) = noise.increment_with_integral(0.5);
+    let (second_dw, second_dz) = (second_dw.clone(), second_dz.clone());
+    for i in 0..2 {
+        assert_relative_eq!(first_dw[i] + second_dw[i], dw[i], epsilon = 1e-12);
+        assert_relative_eq!(
+            first_dz[i] + second_dz[i] + 0.5 * first_dw[i],
+            dz[i],
+            epsilon = 1e-12
+        );
+    }
+
+    // Integrating backward over the second half gives its integral from the end.
+    let (back_dw, back_dz) = noise.increment_with_integral(-0.5);
+    assert_relative_eq!(back_dw[0], -second_dw[0], epsilon = 1e-12);
+    assert_relative_eq!(
+        back_dz[0],
+        -(second_dz[0] - 0.5 * second_dw[0]),
+        epsilon = 1e-12
+    );
+}
+
+#[test]
+fn integrals_have_the_law_of_the_wiener_process() {
+    let paths = 20_000;
+    let mut noise = NoiseProcess::new(&0.0, StdRng::seed_from_u64(8));
+
+    // Draw the integral over [0, 1] after its end points, by the bridge.
+    let (mut ww, mut wz, mut zz) = (0.0, 0.0, 0.0);
+    for _ in 0..paths {
+        noise.clear();
+        noise.value_at(1.0);
+        noise.value_at(0.75);
+        let (dw, dz) = noise.increment_with_integral(1.0);
+
+        ww += dw * dw;
+        wz += dw * dz;
+        zz += dz * dz;
+    }
+    // E[W(1)²] = 1, E[W(1) Z(1)] = 1/2, and E[Z(1)²] = 1/3 for Z(t) = ∫ W(s) ds.
+    let n = f64::from(paths);
+    assert!((ww / n - 1.0).abs() < 0.04, "E[W²] = {}", ww / n);
+    assert!((wz / n - 0.5).abs() < 0.02, "E[WZ] = {}", wz / n);
+    assert!((zz / n - 1.0 / 3.0).abs() < 0.015, "E[Z²] = {}", zz / n);
+}
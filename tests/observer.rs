@@ -0,0 +1,98 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = -x
+struct Decay;
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-x);
+    }
+}
+
+struct ScalarDecay;
+
+impl Ode for ScalarDecay {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = -x;
+    }
+}
+
+#[test]
+fn closure_sees_every_step() {
+    let mut x = array![1.0, 2.0];
+    let mut stepper = RungeKutta4::new(&x, 0.1);
+
+    let mut seen = Vec::new();
+    stepper.integrate_n_steps_with(&mut Decay, &mut x, 5, &mut |t, _: &Array1<f64>, step| {
+        seen.push((t, step))
+    });
+
+    assert_eq!(seen.len(), 6);
+    for (i, &(t, step)) in seen.iter().enumerate() {
+        assert_eq!(step, i);
+        assert_relative_eq!(t, 0.1 * i as f64, epsilon = 1e-12);
+    }
+}
+
+#[test]
+fn trajectory_matches_stepping() {
+    let x0 = array![1.0, 2.0];
+
+    let mut x = x0.clone();
+    let mut trajectory = Trajectory::new();
+    let (tacc, count) =
+        Heun::new(&x, 0.1).integrate_time_with(&mut Decay, &mut x, 1.0, &mut trajectory);
+
+    assert_eq!(trajectory.len(), count + 1);
+    assert_relative_eq!(trajectory.times()[count], tacc);
+    assert_eq!(trajectory.states()[0], x0);
+    assert_eq!(trajectory.states()[count], x);
+
+    let mut y = x0.clone();
+    let mut stepper = Heun::new(&y, 0.1);
+    for state in &trajectory.states()[1..] {
+        stepper.do_step(&mut Decay, &mut y);
+        assert_eq!(state, &y);
+    }
+}
+
+#[test]
+fn array_recorder() {
+    let mut x = array![1.0, 2.0];
+    let mut recorder = ArrayRecorder::new();
+    DormandPrince5::new(&x, 0.1).integrate_time_with(&mut Decay, &mut x, 2.0, &mut recorder);
+
+    let (times, states) = recorder.into_inner();
+    assert_eq!(states.dim(), (times.len(), 2));
+    assert_relative_eq!(times[times.len() - 1], 2.0);
+    for (&t, row) in times.iter().zip(states.genrows()) {
+        assert_relative_eq!(row[0], f64::exp(-t), max_relative = 1e-3);
+        assert_relative_eq!(row[1], 2.0 * f64::exp(-t), max_relative = 1e-3);
+    }
+
+    let mut x = 1.0;
+    let mut recorder = ArrayRecorder::new();
+    Euler::new(&x, 0.5).integrate_n_steps_with(&mut ScalarDecay, &mut x, 2, &mut recorder);
+    assert_eq!(recorder.states(), array![[1.0], [0.5], [0.25]]);
+}
+
+#[test]
+fn sampled() {
+    let mut x = 1.0;
+    let mut sampled = Sampled::new(Trajectory::new(), 3);
+    Euler::new(&x, 0.1).integrate_n_steps_with(&mut ScalarDecay, &mut x, 10, &mut sampled);
+
+    let trajectory = sampled.into_inner();
+    assert_eq!(trajectory.len(), 4);
+    for (i, &t) in trajectory.times().iter().enumerate() {
+        assert_relative_eq!(t, 0.3 * i as f64, epsilon = 1e-12);
+    }
+}
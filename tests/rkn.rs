@@ -0,0 +1,173 @@
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// The pendulum d²q/dt² = -sin(q).
+struct Pendulum;
+
+impl SecondOrderOde for Pendulum {
+    type State = f64;
+
+    fn acceleration_into(&mut self, _t: f64, q: &f64, acceleration: &mut f64) {
+        *acceleration = -q.sin();
+    }
+}
+
+// Two uncoupled harmonic oscillators d²x/dt² = -ω² x with ω = 1 and 2.
+struct Oscillators;
+
+impl SecondOrderOde for Oscillators {
+    type State = Array1<f64>;
+
+    fn acceleration_into(&mut self, _t: f64, x: &Array1<f64>, acceleration: &mut Array1<f64>) {
+        acceleration[0] = -x[0];
+        acceleration[1] = -4.0 * x[1];
+    }
+}
+
+// The Kepler problem d²q/dt² = -q / |q|³ in the plane.
+struct Kepler;
+
+impl SecondOrderOde for Kepler {
+    type State = Vec<f64>;
+
+    fn acceleration_into(&mut self, _t: f64, q: &Vec<f64>, acceleration: &mut Vec<f64>) {
+        let r3 = (q[0] * q[0] + q[1] * q[1]).powf(1.5);
+        acceleration[0] = -q[0] / r3;
+        acceleration[1] = -q[1] / r3;
+    }
+}
+
+// d²q/dt² = 1, with an acceleration that becomes NaN after t = 1/2.
+struct NanAfterHalf;
+
+impl SecondOrderOde for NanAfterHalf {
+    type State = f64;
+
+    fn acceleration_into(&mut self, t: f64, _q: &f64, acceleration: &mut f64) {
+        *acceleration = if t > 0.5 { f64::NAN } else { 1.0 };
+    }
+}
+
+#[test]
+fn nystrom4_order_of_convergence() {
+    let (mut q_ref, mut v_ref) = (1.0, 0.0);
+    let mut stepper = Nystrom4::new(&q_ref, 1e-3);
+    stepper.integrate_n_steps(&mut Pendulum, &mut q_ref, &mut v_ref, 2000);
+
+    let error = |dt: f64| {
+        let (mut q, mut v) = (1.0, 0.0);
+        let mut stepper = Nystrom4::new(&q, dt);
+        stepper.integrate_n_steps(&mut Pendulum, &mut q, &mut v, (2.0 / dt).round() as usize);
+        (q - q_ref).abs() + (v - v_ref).abs()
+    };
+
+    let observed = (error(0.1) / error(0.05)).log2();
+    assert!((observed - 4.0).abs() < 0.3, "observed order {}", observed);
+}
+
+#[test]
+fn nystrom4_arrays_and_stats() {
+    let (mut x, mut v) = (array![1.0, 0.0], array![0.0, 2.0]);
+    let mut stepper = Nystrom4::new(&x, 1.0 / 128.0);
+    let (t, steps) = stepper.integrate_time(&mut Oscillators, &mut x, &mut v, 1.0);
+    assert_eq!(steps, 128);
+    assert_eq!(t, 1.0);
+
+    // x0 = cos(t) and x1 = sin(2t).
+    assert!((x[0] - t.cos()).abs() < 1e-8);
+    assert!((v[0] + t.sin()).abs() < 1e-8);
+    assert!((x[1] - (2.0 * t).sin()).abs() < 1e-7);
+    assert!((v[1] - 2.0 * (2.0 * t).cos()).abs() < 1e-7);
+
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, 128);
+    assert_eq!(stats.rhs_evaluations, 3 * 128);
+}
+
+#[test]
+fn rkn64_accuracy_follows_the_tolerance() {
+    // An orbit of eccentricity 0.5 with a period of 2π.
+    let e = 0.5f64;
+    let period = 2.0 * std::f64::consts::PI;
+
+    let mut errors = Vec::new();
+    let mut evaluations = Vec::new();
+    for &tol in &[1e-6, 1e-9] {
+        let (mut q, mut v) = (
+            vec![1.0 - e, 0.0],
+            vec![0.0, ((1.0 + e) / (1.0 - e)).sqrt()],
+        );
+        let mut stepper = Rkn64::new(&q, 0.01).with_tolerances(tol, tol);
+        let (t, _) = stepper.integrate_time(&mut Kepler, &mut q, &mut v, period);
+        assert_eq!(t, period);
+        assert_eq!(stepper.time(), period);
+
+        let error = (q[0] - (1.0 - e)).abs() + q[1].abs() + v[0].abs();
+        assert!(error < 100.0 * tol, "tolerance {}: error {}", tol, error);
+        errors.push(error);
+        evaluations.push(stepper.stats().rhs_evaluations);
+    }
+    assert!(errors[1] < 1e-2 * errors[0], "{:?}", errors);
+    assert!(evaluations[1] > evaluations[0]);
+}
+
+#[test]
+fn rkn64_beats_nystrom4() {
+    // The same accuracy on the pendulum with far fewer evaluations of the acceleration.
+    let (mut q_ref, mut v_ref) = (1.0, 0.0);
+    let mut stepper = Nystrom4::new(&q_ref, 1e-3);
+    stepper.integrate_n_steps(&mut Pendulum, &mut q_ref, &mut v_ref, 10_000);
+
+    let (mut q, mut v) = (1.0, 0.0);
+    let mut rkn64 = Rkn64::new(&q, 0.1).with_tolerances(1e-10, 1e-10);
+    rkn64.integrate_time(&mut Pendulum, &mut q, &mut v, 10.0);
+    assert!((q - q_ref).abs() < 1e-8);
+    assert!((v - v_ref).abs() < 1e-8);
+
+    let stats = rkn64.stats();
+    assert_eq!(
+        stats.rhs_evaluations,
+        7 * stats.accepted_steps + 6 * stats.rejected_steps
+    );
+    assert!(stats.rhs_evaluations < 3 * 10_000 / 10);
+}
+
+#[test]
+fn second_order_odes_are_hamiltonian() {
+    let (mut x, mut v) = (array![1.0, 0.0], array![0.0, 2.0]);
+    let mut stepper = Symplectic::new(&x, 1.0 / 64.0, SymplecticMethod::Yoshida6);
+    stepper.integrate_time(&mut Oscillators, &mut x, &mut v, 1.0);
+    assert!((x[0] - 1f64.cos()).abs() < 1e-9);
+    assert!((x[1] - 2f64.sin()).abs() < 1e-9);
+}
+
+#[test]
+fn rkn64_reports_step_size_underflow() {
+    let (mut q, mut v) = (0.0, 0.0);
+    let mut stepper = Rkn64::new(&q, 0.1);
+
+    match stepper.try_integrate_time(&mut NanAfterHalf, &mut q, &mut v, 1.0) {
+        Err(Error::StepSizeUnderflow { time, .. }) => assert!(time <= 0.5),
+        other => panic!("expected a step size underflow, got {:?}", other),
+    }
+    let t = stepper.time();
+    assert!(q.is_finite() && v.is_finite());
+    assert!((v - t).abs() < 1e-12 && (q - t * t / 2.0).abs() < 1e-12);
+
+    // A failed step leaves the state, the time, and the step size unchanged.
+    let dt = stepper.timestep();
+    assert!(stepper
+        .try_do_step(&mut NanAfterHalf, &mut q, &mut v)
+        .is_err());
+    assert_eq!((stepper.time(), stepper.timestep()), (t, dt));
+    assert!((v - t).abs() < 1e-12);
+}
+
+#[test]
+#[should_panic(expected = "Rkn64: step size underflow")]
+fn rkn64_panics_on_step_size_underflow() {
+    let (mut q, mut v) = (0.0, 0.0);
+    Rkn64::new(&q, 0.1).integrate_time(&mut NanAfterHalf, &mut q, &mut v, 1.0);
+}
@@ -0,0 +1,213 @@
+use approx::assert_relative_eq;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use freude::*;
+
+const MU: f64 = 0.5;
+const SIGMA: f64 = 0.5;
+
+// Geometric Brownian motion dx = μ x dt + σ x dW, together with the Wiener process itself,
+// dw = dW, both driven by the same scalar noise.
+struct Gbm;
+
+impl Ode for Gbm {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = MU * state[0];
+        into[1] = 0.0;
+    }
+}
+
+impl Sde for Gbm {
+    fn noise(&self) -> Noise {
+        Noise::Scalar
+    }
+
+    fn diffusion_into(&mut self, _t: f64, state: &Vec<f64>, diffusion: &mut [Vec<f64>]) {
+        diffusion[0][0] = SIGMA * state[0];
+        diffusion[0][1] = 1.0;
+    }
+}
+
+// Geometric Brownian motion on a scalar state.
+struct ScalarGbm;
+
+impl Ode for ScalarGbm {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = MU * x;
+    }
+}
+
+impl Sde for ScalarGbm {
+    fn diffusion_into(&mut self, _t: f64, x: &f64, diffusion: &mut [f64]) {
+        diffusion[0] = SIGMA * x;
+    }
+}
+
+// Independent Ornstein–Uhlenbeck processes dx_i = -x_i dt + dW_i.
+struct OrnsteinUhlenbeck;
+
+impl Ode for OrnsteinUhlenbeck {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+        for (d, x) in into.iter_mut().zip(x) {
+            *d = -x;
+        }
+    }
+}
+
+impl Sde for OrnsteinUhlenbeck {
+    fn diffusion_into(&mut self, _t: f64, _x: &Vec<f64>, diffusion: &mut [Vec<f64>]) {
+        for g in diffusion[0].iter_mut() {
+            *g = 1.0;
+        }
+    }
+}
+
+/// The mean absolute error at t = 1 against the exact solution on the same Brownian path.
+fn strong_error<St, F>(new_stepper: F, dt: f64, paths: usize) -> f64
+where
+    St: SdeStepper<State = Vec<f64>>,
+    F: Fn(&Vec<f64>, f64, NoiseProcess<f64, StdRng>) -> St,
+{
+    let x0 = vec![1.0, 0.0];
+    let noise = NoiseProcess::new(&0.0, StdRng::seed_from_u64(1));
+    let mut stepper = new_stepper(&x0, dt, noise);
+
+    let mut error = 0.0;
+    for _ in 0..paths {
+        let mut x = x0.clone();
+        stepper.set_time(0.0);
+        let (t, _) = stepper.integrate_time(&mut Gbm, &mut x, 1.0);
+
+        let exact = ((MU - SIGMA * SIGMA / 2.0) * t + SIGMA * x[1]).exp();
+        error += (x[0] - exact).abs();
+    }
+    error / paths as f64
+}
+
+/// The least squares slope of log(error) over log(dt).
+fn order(dts: &[f64], errors: &[f64]) -> f64 {
+    let n = dts.len() as f64;
+    let xs: Vec<f64> = dts.iter().map(|dt| dt.ln()).collect();
+    let ys: Vec<f64> = errors.iter().map(|e| e.ln()).collect();
+    let x_mean = xs.iter().sum::<f64>() / n;
+    let y_mean = ys.iter().sum::<f64>() / n;
+
+    let covariance: f64 = xs
+        .iter()
+        .zip(&ys)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: f64 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+    covariance / variance
+}
+
+#[test]
+fn strong_convergence() {
+    let dts = [1.0 / 8.0, 1.0 / 16.0, 1.0 / 32.0, 1.0 / 64.0, 1.0 / 128.0];
+
+    let errors: Vec<f64> = dts
+        .iter()
+        .map(|&dt| strong_error(EulerMaruyama::new, dt, 500))
+        .collect();
+    let euler_maruyama = order(&dts, &errors);
+    assert!(
+        (euler_maruyama - 0.5).abs() < 0.15,
+        "Euler–Maruyama strong order {}",
+        euler_maruyama
+    );
+
+    let errors: Vec<f64> = dts
+        .iter()
+        .map(|&dt| strong_error(Milstein::new, dt, 500))
+        .collect();
+    let milstein = order(&dts, &errors);
+    assert!(
+        (milstein - 1.0).abs() < 0.15,
+        "Milstein strong order {}",
+        milstein
+    );
+}
+
+#[test]
+fn weak_convergence() {
+    let paths = 20_000;
+    let mean_at_one = |dt: f64| {
+        let noise = NoiseProcess::new(&0.0, StdRng::seed_from_u64(2));
+        let mut stepper = EulerMaruyama::new(&1.0, dt, noise);
+        let mut sum = 0.0;
+        for _ in 0..paths {
+            let mut x = 1.0;
+            stepper.noise_process_mut().clear();
+            stepper.integrate_time(&mut ScalarGbm, &mut x, 1.0);
+            sum += x;
+        }
+        sum / f64::from(paths)
+    };
+
+    // E[x(1)] = e^μ; the bias of Euler–Maruyama is 1 - (1 + μ dt)^(1/dt) e^(-μ) = O(dt).
+    let exact = MU.exp();
+    let coarse = (mean_at_one(0.5) - exact).abs();
+    let fine = (mean_at_one(1.0 / 32.0) - exact).abs();
+    assert_relative_eq!(coarse, exact - (1.0 + MU * 0.5).powi(2), epsilon = 0.03);
+    assert!(fine < 0.03);
+    assert!(fine < coarse / 2.0);
+}
+
+#[test]
+fn seeded_runs_are_reproducible() {
+    let run = |seed| {
+        let mut x = vec![1.0; 3];
+        let noise = NoiseProcess::new(&vec![0.0; 3], StdRng::seed_from_u64(seed));
+        let mut stepper = Milstein::new(&x, 0.01, noise);
+        stepper.integrate_n_steps(&mut OrnsteinUhlenbeck, &mut x, 100);
+        x
+    };
+
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+
+    // Diagonal noise drives every component independently.
+    let x = run(7);
+    assert_ne!(x[0], x[1]);
+    assert_ne!(x[1], x[2]);
+}
+
+#[test]
+fn steps_follow_the_noise_process() {
+    let noise = NoiseProcess::new(&vec![0.0; 3], StdRng::seed_from_u64(9));
+    let mut stepper = EulerMaruyama::new(&vec![0.0; 3], 0.1, noise);
+    let mut x = vec![0.0; 3];
+    stepper.integrate_n_steps(&mut OrnsteinUhlenbeck, &mut x, 10);
+
+    // Replaying the realization reproduces the path.
+    let mut replayed = vec![0.0; 3];
+    stepper.set_time(0.0);
+    stepper.noise_process_mut().set_time(0.0);
+    stepper.integrate_n_steps(&mut OrnsteinUhlenbeck, &mut replayed, 10);
+    assert_eq!(replayed, x);
+
+    // Milstein on a finer grid of the same realization stays close to it.
+    let mut noise = stepper.noise_process().clone();
+    noise.set_time(0.0);
+    let mut fine = Milstein::new(&vec![0.0; 3], 0.01, noise);
+    let mut y = vec![0.0; 3];
+    fine.integrate_n_steps(&mut OrnsteinUhlenbeck, &mut y, 100);
+    for (x, y) in x.iter().zip(&y) {
+        assert!((x - y).abs() < 0.2, "{:?} vs {:?}", x, y);
+    }
+}
+
+#[test]
+#[should_panic(expected = "the noise process has 1 components, but the system is driven by 3")]
+fn noise_process_must_match_the_noise() {
+    let noise = NoiseProcess::new(&0.0, StdRng::seed_from_u64(10));
+    let mut stepper = EulerMaruyama::new(&vec![0.0; 3], 0.1, noise);
+    stepper.do_step(&mut OrnsteinUhlenbeck, &mut vec![0.0; 3]);
+}
@@ -0,0 +1,130 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = v, dv/dt = -x
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = state[1];
+        into[1] = -state[0];
+    }
+}
+
+// dx/dt = -x
+struct Decay;
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-x);
+    }
+}
+
+#[test]
+fn saves_every_step_and_ends_exactly() {
+    let x0 = array![1.0, 2.0];
+    let mut stepper = RungeKutta4::new(&x0, 0.3);
+    let solution = solve(
+        &mut stepper,
+        &mut Decay,
+        x0.clone(),
+        1.0,
+        &SolveOptions::new(),
+    );
+
+    // Three steps of 0.3 and a shortened one of 0.1.
+    assert_eq!(solution.len(), 5);
+    assert_eq!(solution.times()[0], 0.0);
+    assert_eq!(solution.times()[4], 1.0);
+    assert_eq!(solution.states()[0], x0);
+    assert_relative_eq!(solution.states()[4][0], (-1f64).exp(), epsilon = 1e-4);
+    assert_eq!(stepper.time(), 1.0);
+}
+
+#[test]
+fn save_at_times_off_the_step_grid() {
+    let times: Vec<f64> = (0..=20).map(|i| 0.25 * f64::from(i)).collect();
+
+    let x0 = vec![1.0, 0.0];
+    let mut stepper = DormandPrince5::new(&x0, 0.1).with_tolerances(1e-10, 1e-10);
+    let options = SolveOptions::new().with_save_at(times.clone());
+    let solution = solve(&mut stepper, &mut Oscillator, x0, 5.0, &options);
+
+    assert_eq!(solution.times(), &times[..]);
+    for (t, state) in &solution {
+        assert_relative_eq!(state[0], t.cos(), epsilon = 1e-8);
+        assert_relative_eq!(state[1], -t.sin(), epsilon = 1e-8);
+    }
+}
+
+#[test]
+fn lookup_by_time() {
+    let x0 = vec![1.0, 0.0];
+
+    let mut stepper = RungeKutta4::new(&x0, 0.05);
+    let linear = solve(
+        &mut stepper,
+        &mut Oscillator,
+        x0.clone(),
+        2.0,
+        &SolveOptions::new(),
+    );
+
+    let mut stepper = RungeKutta4::new(&x0, 0.05);
+    let options = SolveOptions::new().with_dense(true);
+    let hermite = solve(&mut stepper, &mut Oscillator, x0, 2.0, &options);
+
+    assert!(linear.derivatives().is_none());
+    assert_eq!(hermite.derivatives().map(|d| d.len()), Some(hermite.len()));
+
+    for &t in &[0.0, 0.33, 1.01, 1.999, 2.0] {
+        assert_relative_eq!(linear.at(t)[0], f64::cos(t), epsilon = 1e-3);
+        assert_relative_eq!(hermite.at(t)[0], f64::cos(t), epsilon = 1e-6);
+        assert_relative_eq!(hermite.at(t)[1], -f64::sin(t), epsilon = 1e-6);
+    }
+}
+
+#[test]
+fn backward_in_time() {
+    let x0 = array![1.0];
+    let mut stepper = Heun::new(&x0, 0.01);
+    stepper.set_time(1.0);
+
+    let options = SolveOptions::new()
+        .with_save_at(vec![0.75, 0.5, 0.25])
+        .with_dense(true);
+    let solution = solve(&mut stepper, &mut Decay, x0, 0.0, &options);
+
+    assert_eq!(solution.times(), &[0.75, 0.5, 0.25]);
+    for (t, state) in solution.iter() {
+        assert_relative_eq!(state[0], (1.0 - t).exp(), max_relative = 1e-4);
+    }
+    assert_relative_eq!(solution.at(0.6)[0], 0.4f64.exp(), max_relative = 1e-4);
+    assert_eq!(stepper.time(), 0.0);
+}
+
+#[test]
+fn into_array() {
+    let x0 = vec![1.0, 0.0];
+    let mut stepper = Heun::new(&x0, 0.5);
+    let solution = solve(&mut stepper, &mut Oscillator, x0, 1.0, &SolveOptions::new());
+
+    let array = solution.to_array();
+    assert_eq!(array, array![[1.0, 0.0], [0.875, -0.5], [0.515625, -0.875]]);
+}
+
+#[test]
+#[should_panic]
+fn unordered_save_at_times() {
+    let x0 = array![1.0];
+    let mut stepper = RungeKutta4::new(&x0, 0.1);
+    let options = SolveOptions::new().with_save_at(vec![0.5, 0.2]);
+    solve(&mut stepper, &mut Decay, x0, 1.0, &options);
+}
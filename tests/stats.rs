@@ -0,0 +1,74 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = v, dv/dt = -x
+struct Oscillator;
+
+impl Ode for Oscillator {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, state: &Array1<f64>, into: &mut Array1<f64>) {
+        into[0] = state[1];
+        into[1] = -state[0];
+    }
+}
+
+#[test]
+fn fixed_steps() {
+    let mut x = array![1.0, 0.0];
+    let mut stepper = RungeKutta4::new(&x, 0.1);
+    assert_eq!(*stepper.stats(), Stats::new());
+
+    let (_, steps) = stepper.integrate_time(&mut Oscillator, &mut x, 1.0);
+
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, steps);
+    assert_eq!(stats.rejected_steps, 0);
+    assert_eq!(stats.rhs_evaluations, 4 * steps);
+    assert_eq!(stats.jacobian_evaluations, 0);
+    assert_relative_eq!(stats.min_timestep, 0.1);
+    assert_relative_eq!(stats.max_timestep, 0.1);
+
+    stepper.reset_stats();
+    assert_eq!(*stepper.stats(), Stats::new());
+}
+
+#[test]
+fn adaptive_steps() {
+    let mut x = array![1.0, 0.0];
+    // The initial step size is far too large for the tolerances, so the first attempts fail.
+    let mut stepper = DormandPrince5::new(&x, 1.0).with_tolerances(1e-10, 1e-10);
+    stepper.integrate_time(&mut Oscillator, &mut x, 2.0);
+
+    let stats = *stepper.stats();
+    assert!(stats.accepted_steps > 0);
+    assert!(stats.rejected_steps > 0);
+    // Every attempt evaluates six stages, and every accepted step one more at its start.
+    assert_eq!(
+        stats.rhs_evaluations,
+        7 * stats.accepted_steps + 6 * stats.rejected_steps
+    );
+    assert!(stats.min_timestep < stats.max_timestep);
+    assert!(stats.max_timestep < 1.0);
+}
+
+#[test]
+fn implicit_steps() {
+    let mut system = FiniteDifference::new(Oscillator);
+
+    let mut x = array![1.0, 0.0];
+    let mut stepper = Trapezoidal::new(&x, 0.1);
+    stepper.integrate_n_steps(&mut system, &mut x, 10).unwrap();
+
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, 10);
+    assert_eq!(stats.jacobian_evaluations, 10);
+    assert_eq!(stats.lu_factorizations, 10);
+    assert!(stats.newton_iterations >= 10);
+    // One evaluation at the start of each step, one per Newton iteration, and three for each
+    // forward difference Jacobian of the two components.
+    assert_eq!(stats.rhs_evaluations, 10 + stats.newton_iterations + 3 * 10);
+}
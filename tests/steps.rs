@@ -0,0 +1,106 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+// dx/dt = -x
+struct Decay;
+
+impl Ode for Decay {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        into.assign(&-x);
+    }
+}
+
+struct ScalarDecay;
+
+impl Ode for ScalarDecay {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = -x;
+    }
+}
+
+#[test]
+fn yields_initial_state_then_every_step() {
+    let x0 = array![1.0, 2.0];
+
+    let mut stepper = RungeKutta4::new(&x0, 0.1);
+    let snapshots: Vec<_> = stepper.iter(&mut Decay, x0.clone()).take(6).collect();
+
+    let mut x = x0.clone();
+    let mut reference = RungeKutta4::new(&x, 0.1);
+    let mut trajectory = Trajectory::new();
+    reference.integrate_n_steps_with(&mut Decay, &mut x, 5, &mut trajectory);
+
+    assert_eq!(snapshots.len(), 6);
+    for ((t, state), (t_ref, state_ref)) in snapshots
+        .iter()
+        .zip(trajectory.times().iter().zip(trajectory.states()))
+    {
+        assert_eq!(t, t_ref);
+        assert_eq!(state, state_ref);
+    }
+    assert_eq!(stepper.time(), reference.time());
+}
+
+#[test]
+fn stops_early_with_take_while() {
+    let mut stepper = Euler::new(&1.0, 0.1);
+    let mut system = ScalarDecay;
+    let mut steps = stepper.iter(&mut system, 1.0);
+
+    let count = steps.by_ref().take_while(|&(_, x)| x > 0.5).count();
+
+    // 0.9^6 > 0.5 > 0.9^7; take_while consumes the first step failing the predicate.
+    assert_eq!(count, 7);
+    assert_relative_eq!(steps.time(), 0.7);
+    assert_relative_eq!(*steps.state(), 0.9f64.powi(7));
+    assert_relative_eq!(steps.into_state(), 0.9f64.powi(7));
+}
+
+#[test]
+fn composes_with_iterator_adapters() {
+    let mut stepper = Heun::new(&1.0, 0.01);
+    let sampled: Vec<f64> = stepper
+        .iter(&mut ScalarDecay, 1.0)
+        .step_by(10)
+        .take(11)
+        .map(|(t, _)| t)
+        .collect();
+
+    assert_eq!(sampled.len(), 11);
+    for (i, t) in sampled.iter().enumerate() {
+        assert_relative_eq!(*t, 0.1 * i as f64, epsilon = 1e-12);
+    }
+
+    let mut fine = RungeKutta4::new(&1.0, 0.05);
+    let mut coarse = RungeKutta4::new(&1.0, 0.1);
+    let largest = fine
+        .iter(&mut ScalarDecay, 1.0)
+        .step_by(2)
+        .zip(coarse.iter(&mut ScalarDecay, 1.0))
+        .take(10)
+        .map(|((_, x), (_, y))| (x - y).abs())
+        .fold(0.0, f64::max);
+    assert!(largest < 1e-6);
+}
+
+#[cfg(feature = "tuple")]
+#[test]
+fn tuple_states() {
+    use tuple::T2;
+
+    let mut f: &dyn Fn(T2<f64, f64>) -> T2<f64, f64> = &|T2(x, v)| T2(v, -x);
+
+    let mut stepper = RungeKutta4::new(&T2(1.0, 0.0), 0.01);
+    let (t, T2(x, v)) = stepper.iter(&mut f, T2(1.0, 0.0)).nth(100).unwrap();
+
+    assert_relative_eq!(t, 1.0, epsilon = 1e-12);
+    assert_relative_eq!(x, 1f64.cos(), epsilon = 1e-8);
+    assert_relative_eq!(v, -1f64.sin(), epsilon = 1e-8);
+}
@@ -0,0 +1,230 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use freude::*;
+
+const MU: f64 = 0.5;
+const SIGMA: f64 = 0.5;
+
+// Geometric Brownian motion dx = μ x dt + σ x dW, together with the Wiener process itself,
+// dw = dW, both driven by the same scalar noise.
+struct Gbm;
+
+impl Ode for Gbm {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = MU * state[0];
+        into[1] = 0.0;
+    }
+}
+
+impl Sde for Gbm {
+    fn noise(&self) -> Noise {
+        Noise::Scalar
+    }
+
+    fn diffusion_into(&mut self, _t: f64, state: &Vec<f64>, diffusion: &mut [Vec<f64>]) {
+        diffusion[0][0] = SIGMA * state[0];
+        diffusion[0][1] = 1.0;
+    }
+}
+
+// The Wiener process w and its time integral y, dw = dW and dy = w dt.
+struct IntegratedWiener;
+
+impl Ode for IntegratedWiener {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, state: &Vec<f64>, into: &mut Vec<f64>) {
+        into[0] = 0.0;
+        into[1] = state[0];
+    }
+}
+
+impl Sde for IntegratedWiener {
+    fn noise(&self) -> Noise {
+        Noise::Scalar
+    }
+
+    fn diffusion_into(&mut self, _t: f64, _state: &Vec<f64>, diffusion: &mut [Vec<f64>]) {
+        diffusion[0][0] = 1.0;
+        diffusion[0][1] = 0.0;
+    }
+}
+
+// The Ornstein–Uhlenbeck process dx = -x dt + σ dW.
+struct OrnsteinUhlenbeck;
+
+impl Ode for OrnsteinUhlenbeck {
+    type State = f64;
+
+    fn differentiate_into(&mut self, x: &f64, into: &mut f64) {
+        *into = -x;
+    }
+}
+
+impl Sde for OrnsteinUhlenbeck {
+    fn diffusion_into(&mut self, _t: f64, _x: &f64, diffusion: &mut [f64]) {
+        diffusion[0] = SIGMA;
+    }
+}
+
+/// The mean absolute error of geometric Brownian motion at t = 1 against the exact solution on
+/// the same Brownian path.
+fn gbm_error<C>(stepper: &mut StochasticRungeKutta<Vec<f64>, f64, StdRng, C>, paths: usize) -> f64
+where
+    C: StepSizeController,
+{
+    let mut error = 0.0;
+    for _ in 0..paths {
+        let mut x = vec![1.0, 0.0];
+        stepper.set_time(0.0);
+        stepper.noise_process_mut().clear();
+        stepper.integrate_time(&mut Gbm, &mut x, 1.0);
+
+        let exact = ((MU - SIGMA * SIGMA / 2.0) + SIGMA * x[1]).exp();
+        error += (x[0] - exact).abs();
+    }
+    error / paths as f64
+}
+
+/// The strong order of `method` with fixed steps, estimated on geometric Brownian motion.
+fn strong_order(method: SrkMethod) -> f64 {
+    let dts = [1.0 / 4.0, 1.0 / 8.0, 1.0 / 16.0, 1.0 / 32.0, 1.0 / 64.0];
+
+    // Accept every step and never change the step size.
+    let errors: Vec<f64> = dts
+        .iter()
+        .map(|&dt| {
+            let x0 = vec![1.0, 0.0];
+            let mut stepper = StochasticRungeKutta::new(
+                &x0,
+                dt,
+                method,
+                NoiseProcess::new(&0.0, StdRng::seed_from_u64(3)),
+            )
+            .with_tolerances(1e10, 1e10)
+            .with_controller(IController::new().with_factor_bounds(1.0, 1.0));
+            gbm_error(&mut stepper, 500)
+        })
+        .collect();
+
+    let orders: Vec<f64> = errors
+        .windows(2)
+        .map(|pair| (pair[0] / pair[1]).log2())
+        .collect();
+    orders.iter().sum::<f64>() / orders.len() as f64
+}
+
+#[test]
+fn strong_order_with_fixed_steps() {
+    let order = strong_order(SrkMethod::Sriw1);
+    assert!((order - 1.5).abs() < 0.2, "SRIW1 strong order {}", order);
+
+    let order = strong_order(SrkMethod::Sri2);
+    assert!((order - 1.0).abs() < 0.2, "SRI2 strong order {}", order);
+}
+
+#[test]
+fn adaptive_error_follows_the_tolerance() {
+    let x0 = vec![1.0, 0.0];
+    for &method in &[SrkMethod::Sriw1, SrkMethod::Sri2] {
+        let error = |tolerance: f64| {
+            let mut stepper = StochasticRungeKutta::new(
+                &x0,
+                0.1,
+                method,
+                NoiseProcess::new(&0.0, StdRng::seed_from_u64(4)),
+            )
+            .with_tolerances(tolerance, tolerance);
+            let error = gbm_error(&mut stepper, 200);
+            (error, *stepper.stats())
+        };
+
+        let (loose, loose_stats) = error(1e-2);
+        let (tight, tight_stats) = error(1e-4);
+
+        assert!(
+            tight < loose / 4.0,
+            "{:?}: errors {} and {}",
+            method,
+            loose,
+            tight
+        );
+        assert!(tight_stats.accepted_steps > loose_stats.accepted_steps);
+        assert!(tight_stats.rejected_steps > 0);
+    }
+}
+
+#[test]
+fn rejections_keep_the_brownian_path_unbiased() {
+    let paths = 4000;
+    let mut stepper = StochasticRungeKutta::new(
+        &vec![0.0, 0.0],
+        0.5,
+        SrkMethod::Sra1,
+        NoiseProcess::new(&0.0, StdRng::seed_from_u64(5)),
+    )
+    .with_tolerances(1e-3, 1e-3);
+
+    // E[W(1)²] = 1, E[W(1) Y(1)] = 1/2, and E[Y(1)²] = 1/3 for Y(t) = ∫ W(s) ds.
+    let (mut ww, mut wy, mut yy) = (0.0, 0.0, 0.0);
+    for _ in 0..paths {
+        let mut x = vec![0.0, 0.0];
+        stepper.set_time(0.0);
+        stepper.noise_process_mut().clear();
+        stepper.integrate_time(&mut IntegratedWiener, &mut x, 1.0);
+
+        // The accepted steps follow the realization of the noise process.
+        let noise = stepper.noise_process_mut();
+        let t = noise.time();
+        assert!((t - 1.0).abs() < 1e-12);
+        assert!((x[0] - noise.value_at(t)).abs() < 1e-12);
+
+        ww += x[0] * x[0];
+        wy += x[0] * x[1];
+        yy += x[1] * x[1];
+    }
+    let n = paths as f64;
+    assert!(stepper.stats().rejected_steps > paths);
+    assert!((ww / n - 1.0).abs() < 0.08, "E[W²] = {}", ww / n);
+    assert!((wy / n - 0.5).abs() < 0.04, "E[WY] = {}", wy / n);
+    assert!((yy / n - 1.0 / 3.0).abs() < 0.03, "E[Y²] = {}", yy / n);
+}
+
+#[test]
+fn additive_noise_moments() {
+    let paths = 4000;
+    let mut stepper = StochasticRungeKutta::new(
+        &1.0,
+        0.1,
+        SrkMethod::Sra1,
+        NoiseProcess::new(&0.0, StdRng::seed_from_u64(6)),
+    )
+    .with_tolerances(1e-4, 1e-4);
+
+    let (mut sum, mut squares) = (0.0, 0.0);
+    for _ in 0..paths {
+        let mut x = 1.0;
+        stepper.set_time(0.0);
+        stepper.noise_process_mut().clear();
+        let (t, _) = stepper.integrate_time(&mut OrnsteinUhlenbeck, &mut x, 1.0);
+        assert_eq!(t, 1.0);
+
+        sum += x;
+        squares += x * x;
+    }
+    let n = f64::from(paths);
+    let mean = sum / n;
+    let variance = squares / n - mean * mean;
+
+    let exact_mean = (-1f64).exp();
+    let exact_variance = SIGMA * SIGMA * (1.0 - (-2f64).exp()) / 2.0;
+    assert!((mean - exact_mean).abs() < 0.02, "mean {}", mean);
+    assert!(
+        (variance / exact_variance - 1.0).abs() < 0.08,
+        "variance {}",
+        variance
+    );
+}
@@ -0,0 +1,172 @@
+use ndarray::array;
+use ndarray::prelude::*;
+
+use freude::*;
+
+const METHODS: [SymplecticMethod; 7] = [
+    SymplecticMethod::Euler,
+    SymplecticMethod::StormerVerlet,
+    SymplecticMethod::VelocityVerlet,
+    SymplecticMethod::ForestRuth,
+    SymplecticMethod::Yoshida4,
+    SymplecticMethod::Yoshida6,
+    SymplecticMethod::Yoshida8,
+];
+
+// The pendulum d²q/dt² = -sin(q), with unit mass.
+struct Pendulum;
+
+impl Hamiltonian for Pendulum {
+    type State = f64;
+
+    fn force_into(&mut self, _t: f64, q: &f64, force: &mut f64) {
+        *force = -q.sin();
+    }
+}
+
+// The Kepler problem H = |p|²/2 - 1/|q| in the plane.
+struct Kepler;
+
+impl Hamiltonian for Kepler {
+    type State = Array1<f64>;
+
+    fn force_into(&mut self, _t: f64, q: &Array1<f64>, force: &mut Array1<f64>) {
+        let r3 = q.dot(q).powf(1.5);
+        force.assign(&(-q / r3));
+    }
+}
+
+fn kepler_energy(q: &Array1<f64>, p: &Array1<f64>) -> f64 {
+    p.dot(p) / 2.0 - 1.0 / q.dot(q).sqrt()
+}
+
+// The same Kepler problem as a first-order system for `RungeKutta4`.
+struct FirstOrderKepler;
+
+impl Ode for FirstOrderKepler {
+    type State = Array1<f64>;
+
+    fn differentiate_into(&mut self, x: &Array1<f64>, into: &mut Array1<f64>) {
+        let r3 = (x[0] * x[0] + x[1] * x[1]).powf(1.5);
+        into[0] = x[2];
+        into[1] = x[3];
+        into[2] = -x[0] / r3;
+        into[3] = -x[1] / r3;
+    }
+}
+
+// A particle of mass 2 in the potential V(q) = q² / 2 of a spring, H = p² / 4 + q² / 2.
+struct Heavy;
+
+impl Hamiltonian for Heavy {
+    type State = Vec<f64>;
+
+    fn velocity_into(&mut self, _t: f64, p: &Vec<f64>, velocity: &mut Vec<f64>) {
+        velocity[0] = p[0] / 2.0;
+    }
+
+    fn force_into(&mut self, _t: f64, q: &Vec<f64>, force: &mut Vec<f64>) {
+        force[0] = -q[0];
+    }
+}
+
+#[test]
+fn orders_of_convergence() {
+    // The reference solution, with the highest order and a small step size.
+    let (mut q_ref, mut p_ref) = (1.0, 0.0);
+    let mut stepper = Symplectic::new(&q_ref, 1e-3, SymplecticMethod::Yoshida8);
+    stepper.integrate_n_steps(&mut Pendulum, &mut q_ref, &mut p_ref, 2000);
+
+    for &method in METHODS.iter() {
+        let error = |dt: f64| {
+            let (mut q, mut p) = (1.0, 0.0);
+            let mut stepper = Symplectic::new(&q, dt, method);
+            stepper.integrate_n_steps(&mut Pendulum, &mut q, &mut p, (2.0 / dt).round() as usize);
+            (q - q_ref).abs() + (p - p_ref).abs()
+        };
+
+        let dt = if method.order() > 4 { 0.25 } else { 0.05 };
+        let observed = (error(dt) / error(dt / 2.0)).log2();
+        let expected = f64::from(method.order());
+        assert!(
+            (observed - expected).abs() < 0.3,
+            "{:?}: observed order {}",
+            method,
+            observed
+        );
+    }
+}
+
+#[test]
+fn energy_does_not_drift() {
+    // An orbit of eccentricity 0.5 with a period of 2π.
+    let e = 0.5f64;
+    let q0 = array![1.0 - e, 0.0];
+    let p0 = array![0.0, ((1.0 + e) / (1.0 - e)).sqrt()];
+    let energy = kepler_energy(&q0, &p0);
+    let period = 2.0 * std::f64::consts::PI;
+    let dt = period / 200.0;
+
+    // The largest errors of the energy over the first 10 and all of 100 periods.
+    let (mut q, mut p) = (q0.clone(), p0.clone());
+    let mut stepper = Symplectic::new(&q, dt, SymplecticMethod::VelocityVerlet);
+    let mut errors = (0f64, 0f64);
+    for step in 0..100 * 200 {
+        stepper.do_step(&mut Kepler, &mut q, &mut p);
+        let error = (kepler_energy(&q, &p) - energy).abs();
+        if step < 10 * 200 {
+            errors.0 = errors.0.max(error);
+        }
+        errors.1 = errors.1.max(error);
+    }
+    assert!(errors.1 < 1e-2);
+    assert!(errors.1 < 1.5 * errors.0, "{:?}", errors);
+
+    // RK4 with the same number of force evaluations drifts away from the energy.
+    let mut x = array![q0[0], q0[1], p0[0], p0[1]];
+    let mut rk4 = RungeKutta4::new(&x, 2.0 * dt);
+    let mut rk4_errors = (0f64, 0f64);
+    for step in 0..100 * 100 {
+        rk4.do_step(&mut FirstOrderKepler, &mut x);
+        let error = (kepler_energy(&array![x[0], x[1]], &array![x[2], x[3]]) - energy).abs();
+        if step < 10 * 100 {
+            rk4_errors.0 = rk4_errors.0.max(error);
+        }
+        rk4_errors.1 = rk4_errors.1.max(error);
+    }
+    assert!(rk4_errors.1 > 5.0 * rk4_errors.0, "{:?}", rk4_errors);
+}
+
+#[test]
+fn time_reversibility() {
+    for &method in METHODS[1..].iter() {
+        let (mut q, mut p) = (vec![1.0], vec![0.5]);
+        let mut stepper = Symplectic::new(&q, 0.1, method);
+        stepper.integrate_n_steps(&mut Heavy, &mut q, &mut p, 100);
+
+        stepper.set_timestep(-0.1);
+        stepper.integrate_n_steps(&mut Heavy, &mut q, &mut p, 100);
+        assert!((q[0] - 1.0).abs() < 1e-12, "{:?}", method);
+        assert!((p[0] - 0.5).abs() < 1e-12, "{:?}", method);
+        assert!(stepper.time().abs() < 1e-12);
+    }
+}
+
+#[test]
+fn mass_and_stats() {
+    // H = p² / 4 + q² / 2 oscillates with the angular frequency 1/sqrt(2).
+    let (mut q, mut p) = (vec![1.0], vec![0.0]);
+    let mut stepper = Symplectic::new(&q, 1.0 / 64.0, SymplecticMethod::Yoshida4);
+    let (t, steps) = stepper.integrate_time(&mut Heavy, &mut q, &mut p, 1.0);
+    assert_eq!(steps, 64);
+    assert_eq!(t, 1.0);
+
+    let omega = 0.5f64.sqrt();
+    assert!((q[0] - (omega * t).cos()).abs() < 1e-9);
+    assert!((p[0] + 2.0 * omega * (omega * t).sin()).abs() < 1e-9);
+
+    // Four kicks and three drifts per step.
+    let stats = stepper.stats();
+    assert_eq!(stats.accepted_steps, 64);
+    assert_eq!(stats.rhs_evaluations, 7 * 64);
+}
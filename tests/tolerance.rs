@@ -0,0 +1,90 @@
+use approx::assert_relative_eq;
+use ndarray::array;
+
+use freude::*;
+
+// Independent decays dx_i/dt = -r_i * x_i
+struct Decay {
+    rates: Vec<f64>,
+}
+
+impl Ode for Decay {
+    type State = Vec<f64>;
+
+    fn differentiate_into(&mut self, x: &Vec<f64>, into: &mut Vec<f64>) {
+        for ((d, x), r) in into.iter_mut().zip(x).zip(&self.rates) {
+            *d = -r * x;
+        }
+    }
+}
+
+#[test]
+fn weighted_rms_and_max_norms() {
+    let error = array![1e-3, -4e-3];
+    let x = array![1.0, -2.0];
+    let y = array![0.5, 0.0];
+
+    // The scales are 1e-3 + 1e-3 * 1 and 1e-3 + 1e-3 * 2.
+    let control = ErrorControl::new(1e-3, 1e-3);
+    assert_relative_eq!(
+        control.error(&error, &x, &y),
+        f64::sqrt((0.25 + 16.0 / 9.0) / 2.0)
+    );
+
+    let control = control.with_norm(ErrorNorm::Max);
+    assert_relative_eq!(control.error(&error, &x, &y), 4.0 / 3.0);
+
+    // Per-component tolerances, as Vec or array
+    let control = ErrorControl::new(vec![1e-3, 2e-3], array![0.0, 1e-3]).with_norm(ErrorNorm::Max);
+    assert_relative_eq!(control.error(&error, &x, &y), 1.0);
+
+    let scalar = ErrorControl::new(1e-3, 0.0);
+    assert_relative_eq!(scalar.error(&-2e-3, &1.0, &1.0), 2.0);
+}
+
+#[test]
+fn nan_propagates() {
+    let error = vec![f64::NAN, 1.0];
+    let x = vec![1.0, 1.0];
+
+    for norm in &[ErrorNorm::WeightedRms, ErrorNorm::Max] {
+        let control = ErrorControl::new(1e-3, 1e-3).with_norm(*norm);
+        assert!(control.error(&error, &x, &x).is_nan());
+    }
+}
+
+#[test]
+#[should_panic]
+fn mismatched_tolerances() {
+    let x = vec![1.0, 1.0, 1.0];
+    ErrorControl::new(vec![1e-3, 1e-3], 1e-3).error(&x, &x, &x);
+}
+
+#[test]
+fn mixed_scales() {
+    let rates = vec![1.0, 1.0];
+    let x0 = vec![1e-9, 1.0];
+    let exact: Vec<_> = x0.iter().map(|x| x * f64::exp(-1.0)).collect();
+
+    let mut x = x0.clone();
+    DormandPrince5::new(&x, 0.1)
+        .with_tolerances(1e-6, 1e-3)
+        .integrate_time(
+            &mut Decay {
+                rates: rates.clone(),
+            },
+            &mut x,
+            1.0,
+        );
+    let coarse = (x[0] - exact[0]).abs() / exact[0];
+
+    let mut x = x0.clone();
+    DormandPrince5::new(&x, 0.1)
+        .with_tolerances(vec![1e-18, 1e-6], vec![1e-9, 1e-3])
+        .with_error_norm(ErrorNorm::Max)
+        .integrate_time(&mut Decay { rates }, &mut x, 1.0);
+    let fine = (x[0] - exact[0]).abs() / exact[0];
+
+    assert!(fine < 1e-8);
+    assert!(fine < coarse);
+}